@@ -0,0 +1,396 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+use crate::error::{AppError, AppResult};
+
+const MAGIC: &[u8; 4] = b"YAP1";
+const HEADER_LEN: usize = 12;
+const SAMPLE_RATE: u32 = 16000;
+const EXTENSION: &str = "adpcm";
+
+/// Settings for keeping recordings alongside their transcripts
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ArchiveSettings {
+    pub enabled: bool,
+    /// Oldest recordings are pruned once the archive grows past this
+    pub max_bytes: u64,
+    pub max_age_days: Option<u32>,
+}
+
+impl Default for ArchiveSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_bytes: 500 * 1024 * 1024,
+            max_age_days: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ArchiveUsage {
+    pub count: usize,
+    pub bytes: u64,
+}
+
+/// Recordings stored as IMA ADPCM (4 bits per sample, ~8 KB per second of speech),
+/// one file per history entry id.
+pub struct AudioArchive {
+    dir: PathBuf,
+    settings: ArchiveSettings,
+}
+
+impl AudioArchive {
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            settings: ArchiveSettings::default(),
+        }
+    }
+
+    pub fn settings(&self) -> &ArchiveSettings {
+        &self.settings
+    }
+
+    pub fn set_settings(&mut self, settings: ArchiveSettings) {
+        self.settings = settings;
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.settings.enabled
+    }
+
+    fn path_for(&self, id: u64) -> PathBuf {
+        self.dir.join(format!("{}.{}", id, EXTENSION))
+    }
+
+    pub fn contains(&self, id: u64) -> bool {
+        self.path_for(id).exists()
+    }
+
+    /// Store 16 kHz mono samples for a history entry, returning the size on disk
+    pub fn store(&self, id: u64, samples: &[f32]) -> AppResult<u64> {
+        fs::create_dir_all(&self.dir)?;
+
+        let pcm: Vec<i16> = samples
+            .iter()
+            .map(|&s| (s * 32767.0).clamp(-32768.0, 32767.0) as i16)
+            .collect();
+
+        let mut bytes = Vec::with_capacity(HEADER_LEN + pcm.len() / 2 + 1);
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+        bytes.extend_from_slice(&(pcm.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&adpcm_encode(&pcm));
+
+        let path = self.path_for(id);
+        let tmp_path = path.with_extension("tmp");
+        fs::write(&tmp_path, &bytes)?;
+        fs::rename(&tmp_path, &path)?;
+
+        Ok(bytes.len() as u64)
+    }
+
+    /// Load a recording back as 16 kHz mono samples
+    pub fn load_samples(&self, id: u64) -> AppResult<Vec<f32>> {
+        let path = self.path_for(id);
+        if !path.exists() {
+            return Err(AppError::InvalidInput(format!("No recording archived for transcript {}", id)));
+        }
+
+        let bytes = fs::read(&path)?;
+        if bytes.len() < HEADER_LEN || &bytes[..4] != MAGIC {
+            return Err(AppError::Decode(format!("Archived recording {} is corrupt", id)));
+        }
+        let num_samples = u32::from_le_bytes([bytes[8], bytes[9], bytes[10], bytes[11]]) as usize;
+        // Two samples per byte; a torn write leaves fewer than the header promises
+        if num_samples > (bytes.len() - HEADER_LEN) * 2 {
+            return Err(AppError::Decode(format!("Archived recording {} is truncated", id)));
+        }
+
+        Ok(adpcm_decode(&bytes[HEADER_LEN..], num_samples)
+            .into_iter()
+            .map(|s| s as f32 / 32768.0)
+            .collect())
+    }
+
+    pub fn delete(&self, ids: &[u64]) -> AppResult<()> {
+        for &id in ids {
+            let path = self.path_for(id);
+            if path.exists() {
+                fs::remove_file(path)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn clear(&self) -> AppResult<()> {
+        let ids: Vec<u64> = self.list()?.into_iter().map(|f| f.id).collect();
+        self.delete(&ids)
+    }
+
+    pub fn usage(&self) -> AppResult<ArchiveUsage> {
+        let files = self.list()?;
+        Ok(ArchiveUsage {
+            count: files.len(),
+            bytes: files.iter().map(|f| f.bytes).sum(),
+        })
+    }
+
+    /// Highest id with a recording, so new transcripts never take it over
+    pub fn max_id(&self) -> AppResult<Option<u64>> {
+        Ok(self.list()?.into_iter().map(|f| f.id).max())
+    }
+
+    /// Remove recordings whose transcript is gone, older than the age limit, or over
+    /// the size quota (oldest first). Returns the ids removed. `live_ids` is `None`
+    /// when the history couldn't be read, so no recording is removed for lacking one.
+    pub fn prune(&self, live_ids: Option<&HashSet<u64>>) -> AppResult<Vec<u64>> {
+        let mut files = self.list()?;
+        let mut removed = Vec::new();
+
+        let cutoff = self
+            .settings
+            .max_age_days
+            .and_then(|days| SystemTime::now().checked_sub(Duration::from_secs(days as u64 * 24 * 60 * 60)));
+
+        files.retain(|f| {
+            let expired = cutoff.is_some_and(|cutoff| f.modified < cutoff);
+            let orphaned = live_ids.is_some_and(|ids| !ids.contains(&f.id));
+            if expired || orphaned {
+                removed.push(f.id);
+                false
+            } else {
+                true
+            }
+        });
+
+        // Ids grow monotonically, so the lowest ids are the oldest recordings
+        files.sort_by_key(|f| f.id);
+        let mut total: u64 = files.iter().map(|f| f.bytes).sum();
+        for f in &files {
+            if total <= self.settings.max_bytes {
+                break;
+            }
+            total -= f.bytes;
+            removed.push(f.id);
+        }
+
+        self.delete(&removed)?;
+        Ok(removed)
+    }
+
+    fn list(&self) -> AppResult<Vec<ArchivedFile>> {
+        if !self.dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut files = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) != Some(EXTENSION) {
+                continue;
+            }
+            let Some(id) = path.file_stem().and_then(|s| s.to_str()).and_then(|s| s.parse().ok()) else {
+                continue;
+            };
+            let metadata = entry.metadata()?;
+            files.push(ArchivedFile {
+                id,
+                bytes: metadata.len(),
+                modified: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+            });
+        }
+        Ok(files)
+    }
+}
+
+struct ArchivedFile {
+    id: u64,
+    bytes: u64,
+    modified: SystemTime,
+}
+
+/// Get the default archive directory
+pub fn get_archive_dir() -> PathBuf {
+    let mut path = dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."));
+    path.push("yap-to-text");
+    path.push("recordings");
+    path
+}
+
+// ============ IMA ADPCM ============
+
+const INDEX_TABLE: [i32; 16] = [-1, -1, -1, -1, 2, 4, 6, 8, -1, -1, -1, -1, 2, 4, 6, 8];
+
+const STEP_TABLE: [i32; 89] = [
+    7, 8, 9, 10, 11, 12, 13, 14, 16, 17, 19, 21, 23, 25, 28, 31, 34, 37, 41, 45, 50, 55, 60,
+    66, 73, 80, 88, 97, 107, 118, 130, 143, 157, 173, 190, 209, 230, 253, 279, 307, 337, 371,
+    408, 449, 494, 544, 598, 658, 724, 796, 876, 963, 1060, 1166, 1282, 1411, 1552, 1707, 1878,
+    2066, 2272, 2499, 2749, 3024, 3327, 3660, 4026, 4428, 4871, 5358, 5894, 6484, 7132, 7845,
+    8630, 9493, 10442, 11487, 12635, 13899, 15289, 16818, 18500, 20350, 22385, 24623, 27086,
+    29794, 32767,
+];
+
+#[derive(Default)]
+struct AdpcmState {
+    predictor: i32,
+    index: i32,
+}
+
+impl AdpcmState {
+    /// Apply a 4-bit code to the state, returning the reconstructed sample
+    fn step(&mut self, code: u8) -> i16 {
+        let step = STEP_TABLE[self.index as usize];
+        let mut diff = step >> 3;
+        if code & 4 != 0 {
+            diff += step;
+        }
+        if code & 2 != 0 {
+            diff += step >> 1;
+        }
+        if code & 1 != 0 {
+            diff += step >> 2;
+        }
+        if code & 8 != 0 {
+            self.predictor -= diff;
+        } else {
+            self.predictor += diff;
+        }
+        self.predictor = self.predictor.clamp(i16::MIN as i32, i16::MAX as i32);
+        self.index = (self.index + INDEX_TABLE[code as usize]).clamp(0, 88);
+        self.predictor as i16
+    }
+
+    fn encode(&mut self, sample: i16) -> u8 {
+        let step = STEP_TABLE[self.index as usize];
+        let mut diff = sample as i32 - self.predictor;
+        let mut code = 0u8;
+        if diff < 0 {
+            code = 8;
+            diff = -diff;
+        }
+        if diff >= step {
+            code |= 4;
+            diff -= step;
+        }
+        if diff >= step >> 1 {
+            code |= 2;
+            diff -= step >> 1;
+        }
+        if diff >= step >> 2 {
+            code |= 1;
+        }
+        // Track the decoder's reconstruction, not the input, so errors don't accumulate
+        self.step(code);
+        code
+    }
+}
+
+fn adpcm_encode(samples: &[i16]) -> Vec<u8> {
+    let mut state = AdpcmState::default();
+    samples
+        .chunks(2)
+        .map(|pair| {
+            let low = state.encode(pair[0]);
+            let high = pair.get(1).map_or(0, |&s| state.encode(s));
+            low | (high << 4)
+        })
+        .collect()
+}
+
+fn adpcm_decode(bytes: &[u8], num_samples: usize) -> Vec<i16> {
+    let mut state = AdpcmState::default();
+    // `num_samples` comes from the file, so don't trust it for the allocation
+    let mut samples = Vec::with_capacity(num_samples.min(bytes.len() * 2));
+    for &byte in bytes {
+        for code in [byte & 0x0f, byte >> 4] {
+            if samples.len() == num_samples {
+                return samples;
+            }
+            samples.push(state.step(code));
+        }
+    }
+    samples
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn archive(name: &str) -> AudioArchive {
+        let dir = std::env::temp_dir().join(format!("yap-archive-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        AudioArchive::new(dir)
+    }
+
+    fn sine(len: usize, freq: f32, amplitude: f32) -> Vec<i16> {
+        (0..len)
+            .map(|i| {
+                let t = i as f32 / SAMPLE_RATE as f32;
+                (amplitude * 32767.0 * (2.0 * std::f32::consts::PI * freq * t).sin()) as i16
+            })
+            .collect()
+    }
+
+    #[test]
+    fn adpcm_round_trips_a_sine_within_bounds() {
+        let input = sine(SAMPLE_RATE as usize, 440.0, 0.5);
+        let output = adpcm_decode(&adpcm_encode(&input), input.len());
+        assert_eq!(output.len(), input.len());
+
+        // The step size needs a few milliseconds to adapt from its smallest value
+        let settled = 160;
+        let max_error = input[settled..]
+            .iter()
+            .zip(&output[settled..])
+            .map(|(&a, &b)| (a as i32 - b as i32).abs())
+            .max()
+            .unwrap();
+        // Under 2% of full scale
+        assert!(max_error < 600, "max error {}", max_error);
+    }
+
+    #[test]
+    fn adpcm_keeps_silence_silent() {
+        // An odd length exercises the half-filled last byte
+        let input = vec![0i16; 1001];
+        let output = adpcm_decode(&adpcm_encode(&input), input.len());
+        assert_eq!(output, input);
+    }
+
+    #[test]
+    fn stored_recordings_load_back() {
+        let archive = archive("store");
+        let input: Vec<f32> = sine(1600, 300.0, 0.25).iter().map(|&s| s as f32 / 32768.0).collect();
+        archive.store(7, &input).unwrap();
+
+        let loaded = archive.load_samples(7).unwrap();
+        assert_eq!(loaded.len(), input.len());
+        assert_eq!(archive.max_id().unwrap(), Some(7));
+    }
+
+    #[test]
+    fn truncated_recordings_are_rejected() {
+        let archive = archive("truncated");
+        archive.store(1, &vec![0.1; 1600]).unwrap();
+        let path = archive.path_for(1);
+        let bytes = fs::read(&path).unwrap();
+        fs::write(&path, &bytes[..bytes.len() / 2]).unwrap();
+        assert!(archive.load_samples(1).is_err());
+
+        // A corrupt header asking for billions of samples fails without allocating them
+        let mut header = MAGIC.to_vec();
+        header.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+        header.extend_from_slice(&u32::MAX.to_le_bytes());
+        header.extend_from_slice(&[0; 16]);
+        fs::write(&path, header).unwrap();
+        assert!(archive.load_samples(1).is_err());
+    }
+}
@@ -1,22 +1,96 @@
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{FromSample, Sample, SampleFormat, SizedSample, StreamConfig, SupportedStreamConfig, SupportedStreamConfigRange};
+use std::collections::HashMap;
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use std::io::Cursor;
 use hound::{WavSpec, WavWriter};
 
+use crate::dsp::{self, DspSettings};
+use crate::error::{AppError, AppResult};
+use crate::levels::{InputLevel, LevelMeter, LevelWarning};
+
 /// Represents an audio input device
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct AudioDevice {
+    /// Stable within a host, see `device_ids`
     pub id: String,
     pub name: String,
+    /// The host API the device belongs to, e.g. "ALSA" or "CoreAudio"
+    pub host: String,
+    /// The host's default input
+    pub is_default: bool,
+}
+
+/// An audio host API the app can record through
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct AudioHost {
+    pub id: String,
+    pub is_default: bool,
+}
+
+/// One range of configurations a device accepts
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct SupportedFormat {
+    pub channels: u16,
+    pub min_sample_rate: u32,
+    pub max_sample_rate: u32,
+    /// cpal's name for the sample type, e.g. "f32" or "i16"
+    pub sample_format: String,
 }
 
-/// Shared recording state that is Send + Sync
+/// What a device can record. Queried separately because ALSA opens the device
+/// to find out, which is too slow to do for every device in a list.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct DeviceCapabilities {
+    pub device_id: String,
+    pub formats: Vec<SupportedFormat>,
+    pub default_format: Option<SupportedFormat>,
+}
+
+/// Something about the input devices the UI should hear about
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AudioEvent {
+    /// A device was plugged in or removed
+    DevicesChanged { devices: Vec<AudioDevice> },
+    /// The preferred device is missing, so recording used another one
+    DeviceFallback { requested: String, using: String },
+    /// The stream died mid-recording, usually because the device was unplugged
+    StreamInterrupted { device: String, error: String },
+    /// Recording carried on after an interruption
+    StreamRecovered { device: String },
+    /// Input level over the last metering interval
+    Level(InputLevel),
+    /// The input is overdriven; reported once per recording
+    Clipping,
+    /// Nothing above the noise floor after a few seconds; reported once per recording
+    TooQuiet { device: String },
+}
+
+pub type AudioEventSink = Arc<dyn Fn(AudioEvent) + Send + Sync>;
+
+/// Shared recording state that is Send + Sync. Clones share the same state.
+#[derive(Clone)]
 pub struct RecordingState {
     pub samples: Arc<Mutex<Vec<f32>>>,
     pub is_recording: Arc<AtomicBool>,
+    /// Present while recording
+    pub capture: Arc<Mutex<Option<CaptureThread>>>,
     pub sample_rate: Arc<Mutex<u32>>,
     pub selected_device_id: Arc<Mutex<Option<String>>>,
+    /// Conditioning applied before a recording is transcribed
+    pub dsp: Arc<Mutex<DspSettings>>,
+    /// Zero-based channels mixed into the recording; empty mixes them all
+    pub input_channels: Arc<Mutex<Vec<u16>>>,
+    /// `None` uses the platform's default host
+    pub host_id: Arc<Mutex<Option<String>>>,
+    /// Tried in order when the selected device is missing, before the system default
+    pub fallback_device_ids: Arc<Mutex<Vec<String>>>,
+    pub event_sink: Arc<Mutex<Option<AudioEventSink>>>,
 }
 
 impl RecordingState {
@@ -24,8 +98,22 @@ impl RecordingState {
         Self {
             samples: Arc::new(Mutex::new(Vec::new())),
             is_recording: Arc::new(AtomicBool::new(false)),
+            capture: Arc::new(Mutex::new(None)),
             sample_rate: Arc::new(Mutex::new(16000)),
             selected_device_id: Arc::new(Mutex::new(None)),
+            dsp: Arc::new(Mutex::new(DspSettings::default())),
+            input_channels: Arc::new(Mutex::new(Vec::new())),
+            host_id: Arc::new(Mutex::new(None)),
+            fallback_device_ids: Arc::new(Mutex::new(Vec::new())),
+            event_sink: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    fn emit(&self, event: AudioEvent) {
+        let sink = self.event_sink.lock().unwrap().clone();
+        match sink {
+            Some(sink) => sink(event),
+            None => log::debug!("Audio event with no listener: {:?}", event),
         }
     }
 }
@@ -36,119 +124,469 @@ impl Default for RecordingState {
     }
 }
 
-/// Start recording from the selected input device (or default if none selected)
-/// Returns immediately, recording happens in background
-pub fn start_recording(state: &RecordingState) -> Result<(), String> {
-    if state.is_recording.load(Ordering::SeqCst) {
-        return Err("Already recording".to_string());
+/// Where audio events go, e.g. to the frontend
+pub fn set_event_sink(state: &RecordingState, sink: impl Fn(AudioEvent) + Send + Sync + 'static) {
+    *state.event_sink.lock().unwrap() = Some(Arc::new(sink));
+}
+
+/// The selected device, else the first fallback that's plugged in, else the
+/// system default. Returns the device with its id.
+fn choose_device(host: &cpal::Host, state: &RecordingState) -> AppResult<(cpal::Device, String)> {
+    let selected = state.selected_device_id.lock().unwrap().clone();
+    let fallbacks = state.fallback_device_ids.lock().unwrap().clone();
+
+    let mut available = enumerate(host)?;
+    for id in selected.iter().chain(fallbacks.iter()) {
+        if let Some(i) = available.iter().position(|(_, info)| info.id == *id) {
+            let (device, info) = available.swap_remove(i);
+            if let Some(requested) = selected.as_ref().filter(|s| **s != info.id) {
+                log::warn!("Input device '{}' not found, using '{}'", requested, info.id);
+                state.emit(AudioEvent::DeviceFallback {
+                    requested: requested.clone(),
+                    using: info.id.clone(),
+                });
+            }
+            return Ok((device, info.id));
+        }
+    }
+
+    let device = host
+        .default_input_device()
+        .ok_or_else(|| AppError::Device("No input device available".to_string()))?;
+    let name = device.name().unwrap_or_else(|_| "default".to_string());
+    if let Some(requested) = selected {
+        log::warn!("Input device '{}' not found, using the default", requested);
+        state.emit(AudioEvent::DeviceFallback { requested, using: name.clone() });
     }
+    Ok((device, name))
+}
 
-    // Clear previous samples
-    {
-        let mut samples = state.samples.lock().unwrap();
-        samples.clear();
+/// How much we prefer each sample format, lowest first. f32 needs no conversion.
+fn format_rank(format: SampleFormat) -> Option<u8> {
+    match format {
+        SampleFormat::F32 => Some(0),
+        SampleFormat::I16 => Some(1),
+        SampleFormat::I32 => Some(2),
+        SampleFormat::U16 => Some(3),
+        SampleFormat::I8 | SampleFormat::U8 => Some(4),
+        SampleFormat::F64 => Some(5),
+        SampleFormat::U32 | SampleFormat::I64 | SampleFormat::U64 => Some(6),
+        _ => None,
     }
+}
 
-    let host = cpal::default_host();
+/// The config with the fewest channels that still has all of `needed`, in the
+/// best sample format. Recording at the highest rate is resampled down later.
+fn pick_config(device: &cpal::Device, needed: u16) -> AppResult<SupportedStreamConfig> {
+    let configs: Vec<SupportedStreamConfigRange> = device
+        .supported_input_configs()
+        .map_err(|e| AppError::Device(format!("Error getting supported configs: {}", e)))?
+        .collect();
+
+    configs
+        .into_iter()
+        .filter_map(|c| format_rank(c.sample_format()).map(|rank| (c, rank)))
+        .min_by_key(|(c, rank)| (c.channels() < needed, c.channels(), *rank))
+        .map(|(c, _)| c.with_max_sample_rate())
+        .ok_or_else(|| AppError::Device("No supported input config".to_string()))
+}
 
-    // Get selected device or fall back to default
-    let selected_id = {
-        let selected = state.selected_device_id.lock().unwrap();
-        selected.clone()
-    };
+/// Channel indices to keep, or every channel when none are selected or none exist
+fn channels_to_mix(selected: &[u16], available: usize) -> Vec<usize> {
+    let mut channels: Vec<usize> = selected.iter().map(|&c| c as usize).filter(|&c| c < available).collect();
+    if channels.len() < selected.len() {
+        log::warn!("Ignoring input channels the device doesn't have (it has {})", available);
+    }
+    if channels.is_empty() {
+        channels = (0..available).collect();
+    }
+    channels
+}
 
-    let device = if let Some(device_id) = selected_id {
-        // Find the device by name/ID
-        host.input_devices()
-            .map_err(|e| format!("Failed to enumerate input devices: {}", e))?
-            .find(|d| d.name().map(|n| n == device_id).unwrap_or(false))
-            .ok_or_else(|| format!("Selected device '{}' not found", device_id))?
-    } else {
-        // Use default device
-        host.default_input_device()
-            .ok_or("No input device available")?
-    };
+/// Average the chosen channels of interleaved `data` into mono f32 samples
+fn mix_frames<T>(data: &[T], channels: usize, mix: &[usize], out: &mut Vec<f32>)
+where
+    T: Sample,
+    f32: FromSample<T>,
+{
+    let scale = 1.0 / mix.len() as f32;
+    for frame in data.chunks_exact(channels) {
+        let sum: f32 = mix.iter().map(|&c| frame[c].to_sample::<f32>()).sum();
+        out.push(sum * scale);
+    }
+}
 
-    // Get supported config closest to 16kHz mono
-    let supported_config = device
-        .supported_input_configs()
-        .map_err(|e| format!("Error getting supported configs: {}", e))?
-        .find(|c| c.channels() == 1)
-        .or_else(|| {
-            device
-                .supported_input_configs()
-                .ok()?
-                .next()
+fn build_stream<T>(
+    device: &cpal::Device,
+    config: &StreamConfig,
+    mix: Vec<usize>,
+    state: &RecordingState,
+    on_error: ErrorCallback,
+) -> Result<cpal::Stream, cpal::BuildStreamError>
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+{
+    let samples = Arc::clone(&state.samples);
+    let channels = config.channels as usize;
+
+    device.build_input_stream(
+        config,
+        move |data: &[T], _: &cpal::InputCallbackInfo| {
+            mix_frames(data, channels, &mix, &mut samples.lock().unwrap());
+        },
+        move |err| {
+            log::warn!("Audio stream error: {}", err);
+            on_error(err.to_string());
+        },
+        None,
+    )
+}
+
+/// Open a capture stream that appends mono samples to `state.samples`.
+/// `on_error` is called if the stream errors, so the recording thread can replace it.
+fn open_stream(device: &cpal::Device, state: &RecordingState, on_error: ErrorCallback) -> AppResult<(cpal::Stream, u32)> {
+    let selected = state.input_channels.lock().unwrap().clone();
+    let needed = selected.iter().max().map_or(1, |&c| c.saturating_add(1));
+    let supported_config = pick_config(device, needed)?;
+    let format = supported_config.sample_format();
+    let config: StreamConfig = supported_config.into();
+    let mix = channels_to_mix(&selected, config.channels as usize);
+    log::debug!(
+        "Capturing {} Hz {} with {} channels, mixing {:?}",
+        config.sample_rate.0,
+        format,
+        config.channels,
+        mix
+    );
+
+    let stream = match format {
+        SampleFormat::F32 => build_stream::<f32>(device, &config, mix, state, on_error),
+        SampleFormat::I16 => build_stream::<i16>(device, &config, mix, state, on_error),
+        SampleFormat::I32 => build_stream::<i32>(device, &config, mix, state, on_error),
+        SampleFormat::U16 => build_stream::<u16>(device, &config, mix, state, on_error),
+        SampleFormat::I8 => build_stream::<i8>(device, &config, mix, state, on_error),
+        SampleFormat::U8 => build_stream::<u8>(device, &config, mix, state, on_error),
+        SampleFormat::F64 => build_stream::<f64>(device, &config, mix, state, on_error),
+        SampleFormat::U32 => build_stream::<u32>(device, &config, mix, state, on_error),
+        SampleFormat::I64 => build_stream::<i64>(device, &config, mix, state, on_error),
+        SampleFormat::U64 => build_stream::<u64>(device, &config, mix, state, on_error),
+        other => return Err(AppError::Device(format!("Unsupported sample format {}", other))),
+    }
+    .map_err(|e| AppError::Device(format!("Failed to build input stream: {}", e)))?;
+
+    stream
+        .play()
+        .map_err(|e| AppError::Device(format!("Failed to start stream: {}", e)))?;
+
+    Ok((stream, config.sample_rate.0))
+}
+
+/// Called with a description when a source stops delivering on its own
+pub type ErrorCallback = Box<dyn Fn(String) + Send + 'static>;
+
+/// A running capture. Once it has been dropped no more samples arrive.
+pub trait CaptureStream {}
+
+// Dropping a cpal stream joins or stops its callback thread
+impl CaptureStream for cpal::Stream {}
+
+pub struct OpenedSource {
+    pub stream: Box<dyn CaptureStream>,
+    pub device: String,
+    pub sample_rate: u32,
+}
+
+/// Where recordings come from
+pub trait AudioSource: Send + 'static {
+    /// Start appending mono samples to `state.samples`
+    fn open(&mut self, state: &RecordingState, on_error: ErrorCallback) -> AppResult<OpenedSource>;
+}
+
+/// Microphones through cpal, honouring the device, host and channel settings
+pub struct DeviceSource;
+
+impl AudioSource for DeviceSource {
+    fn open(&mut self, state: &RecordingState, on_error: ErrorCallback) -> AppResult<OpenedSource> {
+        let host = selected_host(state);
+        let (device, name) = choose_device(&host, state)?;
+        let (stream, sample_rate) = open_stream(&device, state, on_error)?;
+        Ok(OpenedSource {
+            stream: Box::new(stream),
+            device: name,
+            sample_rate,
         })
-        .ok_or("No supported input config")?;
+    }
+}
 
-    let config = supported_config.with_max_sample_rate();
+/// Plays a WAV file in real time in place of a microphone. Used when the
+/// `YAP_AUDIO_SOURCE` environment variable names a file, to run the pipeline on
+/// machines without one.
+pub struct FileSource {
+    path: PathBuf,
+}
 
-    {
-        let mut sample_rate = state.sample_rate.lock().unwrap();
-        *sample_rate = config.sample_rate().0;
+impl FileSource {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
     }
+}
 
-    let samples_clone = Arc::clone(&state.samples);
-    let is_recording_for_callback = Arc::clone(&state.is_recording);
-    let is_recording_for_loop = Arc::clone(&state.is_recording);
-    let channels = config.channels() as usize;
+impl AudioSource for FileSource {
+    fn open(&mut self, state: &RecordingState, _on_error: ErrorCallback) -> AppResult<OpenedSource> {
+        let (samples, sample_rate) = read_wav_mono(&self.path)?;
+        Ok(OpenedSource {
+            stream: Box::new(FakeStream::play(samples, sample_rate, Arc::clone(&state.samples))),
+            device: self.path.display().to_string(),
+            sample_rate,
+        })
+    }
+}
 
-    // Set recording flag before starting
-    state.is_recording.store(true, Ordering::SeqCst);
+/// Feeds prerecorded samples in 10 ms chunks from its own thread, like an audio callback
+pub struct FakeStream {
+    stop: Arc<AtomicBool>,
+    thread: Option<std::thread::JoinHandle<()>>,
+}
 
-    // Build and start stream in a separate thread
-    std::thread::spawn(move || {
-        let stream = device
-            .build_input_stream(
-                &config.into(),
-                move |data: &[f32], _: &cpal::InputCallbackInfo| {
-                    if !is_recording_for_callback.load(Ordering::SeqCst) {
-                        return;
+impl FakeStream {
+    pub fn play(samples: Vec<f32>, sample_rate: u32, out: Arc<Mutex<Vec<f32>>>) -> Self {
+        let chunk = (sample_rate as usize / 100).max(1);
+        Self::feed(samples, chunk, Duration::from_millis(10), out, None)
+    }
+
+    /// Push `samples` in chunks of `chunk` every `interval`, sending on `done` once
+    /// the last one has landed
+    fn feed(
+        samples: Vec<f32>,
+        chunk: usize,
+        interval: Duration,
+        out: Arc<Mutex<Vec<f32>>>,
+        done: Option<Sender<()>>,
+    ) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopped = Arc::clone(&stop);
+        let thread = std::thread::spawn(move || {
+            for chunk in samples.chunks(chunk) {
+                if stopped.load(Ordering::SeqCst) {
+                    return;
+                }
+                out.lock().unwrap().extend_from_slice(chunk);
+                std::thread::sleep(interval);
+            }
+            if let Some(done) = done {
+                let _ = done.send(());
+            }
+        });
+        Self {
+            stop,
+            thread: Some(thread),
+        }
+    }
+}
+
+impl Drop for FakeStream {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl CaptureStream for FakeStream {}
+
+/// A WAV file as mono f32 samples and its sample rate
+fn read_wav_mono(path: &Path) -> AppResult<(Vec<f32>, u32)> {
+    let mut reader = hound::WavReader::open(path)
+        .map_err(|e| AppError::Decode(format!("Failed to read {}: {}", path.display(), e)))?;
+    let spec = reader.spec();
+    let samples: Vec<f32> = match spec.sample_format {
+        hound::SampleFormat::Float => reader.samples::<f32>().filter_map(|s| s.ok()).collect(),
+        hound::SampleFormat::Int => {
+            let max_val = (1i64 << (spec.bits_per_sample - 1)) as f32;
+            reader
+                .samples::<i32>()
+                .filter_map(|s| s.ok())
+                .map(|s| s as f32 / max_val)
+                .collect()
+        }
+    };
+    let channels = spec.channels.max(1) as usize;
+    let mix: Vec<usize> = (0..channels).collect();
+    let mut mono = Vec::with_capacity(samples.len() / channels);
+    mix_frames(&samples, channels, &mix, &mut mono);
+    Ok((mono, spec.sample_rate))
+}
+
+/// What the capture thread listens for
+enum Signal {
+    Stop,
+    /// The stream from the given attempt died, usually because the device went away
+    Failed { attempt: u64, error: String },
+}
+
+/// The thread that owns the running stream
+pub struct CaptureThread {
+    control: Sender<Signal>,
+    handle: std::thread::JoinHandle<()>,
+}
+
+/// How long to wait between attempts to replace a lost device
+const RECOVERY_RETRY: Duration = Duration::from_millis(500);
+/// How often levels are reported while recording
+const METER_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Start recording from the selected input device, falling back to the
+/// configured alternatives and then the default. Returns once the stream is
+/// running; recording happens in the background.
+pub fn start_recording(state: &RecordingState) -> AppResult<()> {
+    match std::env::var_os("YAP_AUDIO_SOURCE") {
+        Some(path) => start_recording_from(state, FileSource::new(path)),
+        None => start_recording_from(state, DeviceSource),
+    }
+}
+
+pub fn start_recording_from(state: &RecordingState, mut source: impl AudioSource) -> AppResult<()> {
+    let mut capture = state.capture.lock().unwrap();
+    if capture.is_some() {
+        return Err(AppError::InvalidInput("Already recording".to_string()));
+    }
+
+    // Clear previous samples
+    state.samples.lock().unwrap().clear();
+
+    let recording = state.clone();
+    let (control, signals) = mpsc::channel();
+    let failures = control.clone();
+    let on_error = move |attempt: u64| -> ErrorCallback {
+        let failures = failures.clone();
+        Box::new(move |error| {
+            let _ = failures.send(Signal::Failed { attempt, error });
+        })
+    };
+
+    // cpal streams can't move between threads on every platform, so the
+    // stream lives and dies on its own thread, which reports whether it started
+    let (started_tx, started_rx) = mpsc::channel();
+    let handle = std::thread::spawn(move || {
+        let mut attempt = 0;
+        let opened = match source.open(&recording, on_error(attempt)) {
+            Ok(opened) => opened,
+            Err(e) => {
+                let _ = started_tx.send(Err(e));
+                return;
+            }
+        };
+        *recording.sample_rate.lock().unwrap() = opened.sample_rate;
+        let _ = started_tx.send(Ok(()));
+        let mut device_name = opened.device;
+        let mut stream = Some(opened.stream);
+
+        let mut meter = LevelMeter::new();
+        let mut retry_at = Instant::now();
+
+        // Keep stream alive until told to stop, replacing it if the device goes
+        // away, and report levels every interval in between
+        loop {
+            match signals.recv_timeout(METER_INTERVAL) {
+                Ok(Signal::Stop) | Err(RecvTimeoutError::Disconnected) => break,
+                // Errors queued by a stream that has already been replaced
+                Ok(Signal::Failed { attempt: from, .. }) if from != attempt => continue,
+                Ok(Signal::Failed { error, .. }) => {
+                    if stream.take().is_some() {
+                        recording.emit(AudioEvent::StreamInterrupted {
+                            device: device_name.clone(),
+                            error,
+                        });
                     }
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+            }
 
-                    let mut samples = samples_clone.lock().unwrap();
-                    // If stereo, convert to mono by averaging channels
-                    if channels > 1 {
-                        for chunk in data.chunks(channels) {
-                            let mono = chunk.iter().sum::<f32>() / channels as f32;
-                            samples.push(mono);
+            if stream.is_some() {
+                let level = meter.measure(&recording.samples.lock().unwrap());
+                if let Some(level) = level {
+                    recording.emit(AudioEvent::Level(level));
+                }
+                match meter.warning() {
+                    Some(LevelWarning::Clipping) => recording.emit(AudioEvent::Clipping),
+                    Some(LevelWarning::TooQuiet) => recording.emit(AudioEvent::TooQuiet {
+                        device: device_name.clone(),
+                    }),
+                    None => {}
+                }
+                continue;
+            }
+
+            if Instant::now() < retry_at {
+                continue;
+            }
+            retry_at = Instant::now() + RECOVERY_RETRY;
+            attempt += 1;
+            match source.open(&recording, on_error(attempt)) {
+                Ok(opened) => {
+                    // Keep one rate for the whole recording
+                    let mut rate = recording.sample_rate.lock().unwrap();
+                    if *rate != opened.sample_rate {
+                        let mut samples = recording.samples.lock().unwrap();
+                        if !samples.is_empty() {
+                            *samples = resample(&samples, *rate, opened.sample_rate);
                         }
-                    } else {
-                        samples.extend_from_slice(data);
+                        *rate = opened.sample_rate;
                     }
-                },
-                |err| {
-                    eprintln!("Audio stream error: {}", err);
-                },
-                None,
-            )
-            .expect("Failed to build input stream");
-
-        stream.play().expect("Failed to start stream");
-
-        // Keep stream alive while recording
-        while is_recording_for_loop.load(Ordering::SeqCst) {
-            std::thread::sleep(std::time::Duration::from_millis(10));
+                    log::info!("Recording continues on '{}'", opened.device);
+                    recording.emit(AudioEvent::StreamRecovered {
+                        device: opened.device.clone(),
+                    });
+                    device_name = opened.device;
+                    stream = Some(opened.stream);
+                }
+                // Try again shortly; whatever was captured so far is kept
+                Err(e) => log::debug!("No input device to recover with: {}", e),
+            }
         }
 
-        // Stream is automatically dropped here
+        // Every sample the stream delivered is in `samples` once this returns
+        drop(stream);
     });
 
-    Ok(())
+    match started_rx.recv() {
+        Ok(Ok(())) => {
+            *capture = Some(CaptureThread { control, handle });
+            state.is_recording.store(true, Ordering::SeqCst);
+            Ok(())
+        }
+        Ok(Err(e)) => {
+            let _ = handle.join();
+            Err(e)
+        }
+        Err(_) => {
+            let _ = handle.join();
+            Err(AppError::Device("Audio thread exited before the stream started".to_string()))
+        }
+    }
 }
 
-/// Stop recording and return WAV data
-pub fn stop_recording(state: &RecordingState) -> Result<Vec<u8>, String> {
-    if !state.is_recording.load(Ordering::SeqCst) {
-        return Err("Not recording".to_string());
+/// Stop the capture thread and wait until its stream is torn down. Returns
+/// false if nothing was recording.
+fn stop_capture(state: &RecordingState) -> bool {
+    let Some(capture) = state.capture.lock().unwrap().take() else {
+        return false;
+    };
+    let _ = capture.control.send(Signal::Stop);
+    if capture.handle.join().is_err() {
+        log::error!("Audio capture thread panicked");
     }
-
-    // Signal to stop recording
     state.is_recording.store(false, Ordering::SeqCst);
+    true
+}
 
-    // Wait a bit for the recording thread to finish
-    std::thread::sleep(std::time::Duration::from_millis(100));
+/// Stop recording and return WAV data
+pub fn stop_recording(state: &RecordingState) -> AppResult<Vec<u8>> {
+    if !stop_capture(state) {
+        return Err(AppError::InvalidInput("Not recording".to_string()));
+    }
 
     let samples = {
         let samples = state.samples.lock().unwrap();
@@ -156,7 +594,7 @@ pub fn stop_recording(state: &RecordingState) -> Result<Vec<u8>, String> {
     };
 
     if samples.is_empty() {
-        return Err("No audio recorded".to_string());
+        return Err(AppError::NoAudio("No audio recorded".to_string()));
     }
 
     let sample_rate = {
@@ -175,6 +613,15 @@ pub fn stop_recording(state: &RecordingState) -> Result<Vec<u8>, String> {
     samples_to_wav(&resampled)
 }
 
+/// Apply the DSP settings to 16 kHz samples on their way to Whisper. Recordings
+/// are returned and archived as captured, so this runs once per transcription
+/// and re-processing an archived recording doesn't condition it twice.
+pub fn condition(state: &RecordingState, mut samples: Vec<f32>) -> Vec<f32> {
+    let settings = state.dsp.lock().unwrap().clone();
+    dsp::process(&mut samples, 16000, &settings);
+    samples
+}
+
 fn resample(samples: &[f32], from_rate: u32, to_rate: u32) -> Vec<f32> {
     let ratio = to_rate as f64 / from_rate as f64;
     let new_len = (samples.len() as f64 * ratio) as usize;
@@ -194,7 +641,7 @@ fn resample(samples: &[f32], from_rate: u32, to_rate: u32) -> Vec<f32> {
     resampled
 }
 
-fn samples_to_wav(samples: &[f32]) -> Result<Vec<u8>, String> {
+pub fn samples_to_wav(samples: &[f32]) -> AppResult<Vec<u8>> {
     let spec = WavSpec {
         channels: 1,
         sample_rate: 16000,
@@ -205,45 +652,246 @@ fn samples_to_wav(samples: &[f32]) -> Result<Vec<u8>, String> {
     let mut cursor = Cursor::new(Vec::new());
     {
         let mut writer = WavWriter::new(&mut cursor, spec)
-            .map_err(|e| format!("Failed to create WAV writer: {}", e))?;
+            .map_err(|e| AppError::Decode(format!("Failed to create WAV writer: {}", e)))?;
 
         for &sample in samples {
             // Convert f32 [-1.0, 1.0] to i16
             let sample_i16 = (sample * 32767.0).clamp(-32768.0, 32767.0) as i16;
             writer
                 .write_sample(sample_i16)
-                .map_err(|e| format!("Failed to write sample: {}", e))?;
+                .map_err(|e| AppError::Decode(format!("Failed to write sample: {}", e)))?;
         }
 
         writer
             .finalize()
-            .map_err(|e| format!("Failed to finalize WAV: {}", e))?;
+            .map_err(|e| AppError::Decode(format!("Failed to finalize WAV: {}", e)))?;
     }
 
     Ok(cursor.into_inner())
 }
 
+/// Stop recording and throw the captured audio away. Returns false if nothing was recording.
+pub fn cancel_recording(state: &RecordingState) -> bool {
+    let was_recording = stop_capture(state);
+    state.samples.lock().unwrap().clear();
+    was_recording
+}
+
 pub fn is_recording(state: &RecordingState) -> bool {
     state.is_recording.load(Ordering::SeqCst)
 }
 
-/// Get list of available audio input devices
-pub fn get_input_devices() -> Vec<AudioDevice> {
-    let host = cpal::default_host();
+/// The host chosen in settings, or the platform default if it's unavailable
+fn selected_host(state: &RecordingState) -> cpal::Host {
+    let Some(wanted) = state.host_id.lock().unwrap().clone() else {
+        return cpal::default_host();
+    };
+    match find_host(&wanted).map(cpal::host_from_id) {
+        Some(Ok(host)) => host,
+        Some(Err(e)) => {
+            log::warn!("Audio host {} unavailable ({}), using the default", wanted, e);
+            cpal::default_host()
+        }
+        None => {
+            log::warn!("Audio host {} unavailable, using the default", wanted);
+            cpal::default_host()
+        }
+    }
+}
+
+fn find_host(id: &str) -> Option<cpal::HostId> {
+    cpal::available_hosts().into_iter().find(|h| h.name().eq_ignore_ascii_case(id))
+}
+
+/// Host APIs available on this machine. Linux has ALSA, plus JACK when built
+/// with the `jack` feature; PulseAudio and PipeWire are reached through their
+/// ALSA devices.
+pub fn get_audio_hosts() -> Vec<AudioHost> {
+    let default = cpal::default_host().id();
+    cpal::available_hosts()
+        .into_iter()
+        .map(|id| AudioHost {
+            id: id.name().to_string(),
+            is_default: id == default,
+        })
+        .collect()
+}
+
+/// Record through a specific host, or `None` for the platform default
+pub fn set_audio_host(state: &RecordingState, host_id: Option<String>) -> AppResult<()> {
+    if let Some(id) = host_id.as_deref() {
+        if find_host(id).is_none() {
+            return Err(AppError::InvalidInput(format!("Audio host {} is not available", id)));
+        }
+    }
+    *state.host_id.lock().unwrap() = host_id;
+    Ok(())
+}
+
+/// Input devices on `host` with their ids. cpal has no hardware ids, so a
+/// device is known by its name, and identical devices by `name#2`, `name#3`
+/// in enumeration order. ALSA names carry the card id, which survives
+/// replugging; the first of several identical mics keeps the bare name, so
+/// saved selections from before duplicates appeared still match.
+fn enumerate(host: &cpal::Host) -> AppResult<Vec<(cpal::Device, AudioDevice)>> {
+    let default_name = host.default_input_device().and_then(|d| d.name().ok());
+    let host_name = host.id().name().to_string();
+    let mut seen: HashMap<String, usize> = HashMap::new();
     let mut devices = Vec::new();
 
-    if let Ok(input_devices) = host.input_devices() {
-        for device in input_devices {
-            if let Ok(name) = device.name() {
-                devices.push(AudioDevice {
-                    id: name.clone(),
-                    name,
-                });
-            }
+    let input_devices = host
+        .input_devices()
+        .map_err(|e| AppError::Device(format!("Failed to enumerate input devices: {}", e)))?;
+    for device in input_devices {
+        let Ok(name) = device.name() else {
+            continue;
+        };
+        if !is_capture_candidate(&name) {
+            continue;
         }
+        let count = seen.entry(name.clone()).or_insert(0);
+        *count += 1;
+        let (id, label) = if *count == 1 {
+            (name.clone(), display_name(&name))
+        } else {
+            (format!("{}#{}", name, count), format!("{} ({})", display_name(&name), count))
+        };
+        let info = AudioDevice {
+            id,
+            name: label,
+            host: host_name.clone(),
+            // Only the first of identical devices can be told apart from the default
+            is_default: *count == 1 && default_name.as_deref() == Some(name.as_str()),
+        };
+        devices.push((device, info));
+    }
+
+    // The sound server entries follow the system's chosen input, so list them first
+    #[cfg(target_os = "linux")]
+    devices.sort_by_key(|(_, d)| !matches!(d.id.as_str(), "pipewire" | "pulse" | "default"));
+
+    Ok(devices)
+}
+
+/// Get list of available audio input devices on the selected host
+pub fn get_input_devices(state: &RecordingState) -> Vec<AudioDevice> {
+    match enumerate(&selected_host(state)) {
+        Ok(devices) => devices.into_iter().map(|(_, info)| info).collect(),
+        Err(e) => {
+            log::warn!("{}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// Supported formats of one device, by id
+pub fn get_device_capabilities(state: &RecordingState, device_id: &str) -> AppResult<DeviceCapabilities> {
+    let (device, info) = enumerate(&selected_host(state))?
+        .into_iter()
+        .find(|(_, info)| info.id == device_id)
+        .ok_or_else(|| AppError::Device(format!("Device '{}' not found", device_id)))?;
+
+    let formats = device
+        .supported_input_configs()
+        .map_err(|e| AppError::Device(format!("Error getting supported configs: {}", e)))?
+        .map(|c| SupportedFormat {
+            channels: c.channels(),
+            min_sample_rate: c.min_sample_rate().0,
+            max_sample_rate: c.max_sample_rate().0,
+            sample_format: c.sample_format().to_string(),
+        })
+        .collect();
+    let default_format = device.default_input_config().ok().map(|c| SupportedFormat {
+        channels: c.channels(),
+        min_sample_rate: c.sample_rate().0,
+        max_sample_rate: c.sample_rate().0,
+        sample_format: c.sample_format().to_string(),
+    });
+
+    Ok(DeviceCapabilities {
+        device_id: info.id,
+        formats,
+        default_format,
+    })
+}
+
+/// ALSA lists every plugin and output alias as an input device. Hide the ones
+/// that can't capture from a microphone.
+#[cfg(target_os = "linux")]
+fn is_capture_candidate(name: &str) -> bool {
+    const SKIPPED: &[&str] = &[
+        "null", "surround", "front:", "rear:", "center_lfe:", "side:", "iec958:", "spdif", "hdmi:", "dmix",
+        "upmix", "vdownmix", "lavrate", "samplerate", "speexrate", "jack", "oss", "usbstream",
+    ];
+    !SKIPPED.iter().any(|prefix| name.starts_with(prefix))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn is_capture_candidate(_name: &str) -> bool {
+    true
+}
+
+/// ALSA ids like `sysdefault:CARD=Microphone` become the card name
+#[cfg(target_os = "linux")]
+fn display_name(name: &str) -> String {
+    match name {
+        "pipewire" => "PipeWire (system input)".to_string(),
+        "pulse" => "PulseAudio (system input)".to_string(),
+        "default" => "Default".to_string(),
+        _ => match name.split_once("CARD=") {
+            Some((kind, card)) => {
+                let card = card.split(',').next().unwrap_or(card);
+                match kind.trim_end_matches(':') {
+                    "sysdefault" | "" => card.to_string(),
+                    kind => format!("{} ({})", card, kind),
+                }
+            }
+            None => name.to_string(),
+        },
     }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn display_name(name: &str) -> String {
+    name.to_string()
+}
+
+/// Set the conditioning applied to recordings from the next stop on
+pub fn set_dsp(state: &RecordingState, settings: DspSettings) {
+    *state.dsp.lock().unwrap() = settings;
+}
+
+/// Choose which input channels are recorded, e.g. `[1]` for the second input
+/// of an audio interface. Empty mixes all channels.
+pub fn set_input_channels(state: &RecordingState, channels: Vec<u16>) {
+    *state.input_channels.lock().unwrap() = channels;
+}
 
-    devices
+/// Set the devices to try, in order, when the selected one is missing
+pub fn set_fallback_devices(state: &RecordingState, device_ids: Vec<String>) {
+    *state.fallback_device_ids.lock().unwrap() = device_ids;
+}
+
+/// Poll for devices being plugged in or removed and report changes through
+/// the event sink. cpal has no hot-plug notifications.
+pub fn watch_devices(state: &RecordingState) {
+    let state = state.clone();
+    std::thread::spawn(move || {
+        let mut known: Option<Vec<String>> = None;
+        loop {
+            // ALSA opens devices to enumerate them, which can disturb a live
+            // stream; an unplug mid-recording shows up as a stream error anyway
+            if !state.is_recording.load(Ordering::SeqCst) {
+                let devices = get_input_devices(&state);
+                let ids: Vec<String> = devices.iter().map(|d| d.id.clone()).collect();
+                if known.as_ref().is_some_and(|known| *known != ids) {
+                    state.emit(AudioEvent::DevicesChanged { devices });
+                }
+                known = Some(ids);
+            }
+            std::thread::sleep(Duration::from_secs(2));
+        }
+    });
 }
 
 /// Set the selected input device by ID
@@ -257,3 +905,110 @@ pub fn get_selected_device(state: &RecordingState) -> Option<String> {
     let selected = state.selected_device_id.lock().unwrap();
     selected.clone()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHUNK: usize = 160;
+
+    /// Counts up from zero so the position of every sample can be checked
+    fn ramp(len: usize) -> Vec<f32> {
+        (0..len).map(|i| i as f32).collect()
+    }
+
+    /// Delivers a fixed number of chunks of a ramp at once, reports when they are
+    /// all in, then pushes `tail` more samples while being torn down
+    struct ChunkSource {
+        chunks: usize,
+        tail: usize,
+        done: mpsc::Sender<()>,
+    }
+
+    impl ChunkSource {
+        fn new(chunks: usize, tail: usize) -> (Self, mpsc::Receiver<()>) {
+            let (done, delivered) = mpsc::channel();
+            (Self { chunks, tail, done }, delivered)
+        }
+    }
+
+    impl AudioSource for ChunkSource {
+        fn open(
+            &mut self,
+            state: &RecordingState,
+            _on_error: ErrorCallback,
+        ) -> AppResult<OpenedSource> {
+            let mut samples = ramp(self.chunks * CHUNK + self.tail);
+            let tail = samples.split_off(self.chunks * CHUNK);
+            let out = Arc::clone(&state.samples);
+            let stream = FakeStream::feed(
+                samples,
+                CHUNK,
+                Duration::ZERO,
+                Arc::clone(&out),
+                Some(self.done.clone()),
+            );
+            Ok(OpenedSource {
+                stream: Box::new(TailStream {
+                    stream: Some(stream),
+                    tail,
+                    out,
+                }),
+                device: "chunks".to_string(),
+                sample_rate: 16000,
+            })
+        }
+    }
+
+    /// Like a stream whose last callback is still running when it is dropped
+    struct TailStream {
+        stream: Option<FakeStream>,
+        tail: Vec<f32>,
+        out: Arc<Mutex<Vec<f32>>>,
+    }
+
+    impl Drop for TailStream {
+        fn drop(&mut self) {
+            self.stream.take();
+            self.out.lock().unwrap().extend_from_slice(&self.tail);
+        }
+    }
+
+    impl CaptureStream for TailStream {}
+
+    #[test]
+    fn stop_keeps_every_delivered_chunk() {
+        let state = RecordingState::new();
+        let (source, delivered) = ChunkSource::new(25, 0);
+        start_recording_from(&state, source).unwrap();
+        delivered.recv().unwrap();
+        assert!(stop_capture(&state));
+
+        assert_eq!(*state.samples.lock().unwrap(), ramp(25 * CHUNK));
+        assert!(!is_recording(&state));
+    }
+
+    #[test]
+    fn stop_waits_for_audio_delivered_during_teardown() {
+        let state = RecordingState::new();
+        let (source, delivered) = ChunkSource::new(10, 80);
+        start_recording_from(&state, source).unwrap();
+        delivered.recv().unwrap();
+        assert_eq!(state.samples.lock().unwrap().len(), 10 * CHUNK);
+
+        // The tail only lands as the stream is dropped, so it is there only if stop
+        // waited for the teardown
+        assert!(stop_capture(&state));
+        assert_eq!(*state.samples.lock().unwrap(), ramp(10 * CHUNK + 80));
+    }
+
+    #[test]
+    fn stop_without_recording_reports_nothing() {
+        let state = RecordingState::new();
+        assert!(!stop_capture(&state));
+        assert!(matches!(
+            stop_recording(&state),
+            Err(AppError::InvalidInput(_))
+        ));
+    }
+}
@@ -0,0 +1,113 @@
+use std::path::Path;
+
+use crate::error::AppResult;
+use crate::profile::{self, ConflictStrategy, ImportOptions, Profile};
+use crate::settings::{self, SettingsStore};
+
+const USAGE: &str = "Usage:
+  yap-to-text profile export <file> [--name <name>]
+  yap-to-text profile import <file> [--keep-existing] [--dry-run]
+
+Import overwrites conflicting settings unless --keep-existing is given.
+Restart the app afterwards if it is running.";
+
+/// Handle command-line subcommands. Returns the exit code if the arguments were a
+/// CLI invocation, or `None` to start the GUI.
+pub fn run(args: &[String]) -> Option<i32> {
+    if args.first().map(String::as_str) != Some("profile") {
+        return None;
+    }
+    #[cfg(windows)]
+    attach_console();
+
+    let result = match (args.get(1).map(String::as_str), args.get(2)) {
+        (Some("export"), Some(file)) => export(Path::new(file), flag_value(&args[3..], "--name")),
+        (Some("import"), Some(file)) => import(
+            Path::new(file),
+            has_flag(&args[3..], "--keep-existing"),
+            has_flag(&args[3..], "--dry-run"),
+        ),
+        _ => {
+            eprintln!("{}", USAGE);
+            return Some(2);
+        }
+    };
+
+    match result {
+        Ok(()) => Some(0),
+        Err(e) => {
+            eprintln!("error: {}", e);
+            if let Some(hint) = e.hint() {
+                eprintln!("hint: {}", hint);
+            }
+            Some(1)
+        }
+    }
+}
+
+/// Release builds use the Windows GUI subsystem and start without a console, so
+/// borrow the one the command was run from. The shell doesn't wait for a GUI
+/// program, so output can land after its next prompt.
+#[cfg(windows)]
+fn attach_console() {
+    use windows_sys::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+fn export(path: &Path, name: Option<String>) -> AppResult<()> {
+    // Only reads, so an unreadable settings file is reported rather than backed up
+    let store = SettingsStore::read(settings::get_settings_path())?;
+    profile::write_profile(path, &Profile::from_settings(store.get(), name))?;
+    println!("Exported profile to {}", path.display());
+    Ok(())
+}
+
+fn import(path: &Path, keep_existing: bool, dry_run: bool) -> AppResult<()> {
+    let profile = profile::read_profile(path)?;
+    let settings_path = settings::get_settings_path();
+    let mut store = if dry_run {
+        SettingsStore::read(settings_path)?
+    } else {
+        SettingsStore::load(settings_path)
+    };
+
+    let strategy = if keep_existing {
+        ConflictStrategy::KeepExisting
+    } else {
+        ConflictStrategy::Overwrite
+    };
+    let report = profile::import(&profile, store.get(), &ImportOptions::new(strategy))?;
+
+    for field in &report.applied {
+        println!("  set   {}", field);
+    }
+    for field in &report.kept {
+        println!("  kept  {}", field);
+    }
+    for warning in &report.warnings {
+        println!("warning: {}", warning);
+    }
+
+    if dry_run {
+        println!("Dry run, nothing saved");
+        return Ok(());
+    }
+    if report.applied.is_empty() {
+        println!("Nothing to change");
+        return Ok(());
+    }
+
+    store.set(report.settings)?;
+    println!("Imported {} setting(s) from {}", report.applied.len(), path.display());
+    Ok(())
+}
+
+fn has_flag(args: &[String], flag: &str) -> bool {
+    args.iter().any(|a| a == flag)
+}
+
+fn flag_value(args: &[String], flag: &str) -> Option<String> {
+    args.iter().position(|a| a == flag).and_then(|i| args.get(i + 1)).cloned()
+}
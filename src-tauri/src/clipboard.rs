@@ -0,0 +1,201 @@
+use arboard::{Clipboard, ImageData};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::error::{AppError, AppResult};
+use crate::markdown::{self, Formatted};
+use crate::modes::TranscriptionMode;
+
+/// What happens to the user's clipboard around an automatic paste
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClipboardSettings {
+    /// Put the previous contents back after pasting. Off leaves the transcript on the clipboard.
+    pub restore: bool,
+    /// Wait after the paste keystroke before restoring. Apps read the clipboard
+    /// asynchronously, so restoring too soon pastes the old contents.
+    pub restore_delay_ms: u64,
+}
+
+impl Default for ClipboardSettings {
+    fn default() -> Self {
+        Self {
+            restore: true,
+            restore_delay_ms: 600,
+        }
+    }
+}
+
+impl ClipboardSettings {
+    pub fn validate(&self) -> AppResult<()> {
+        if !(100..=10_000).contains(&self.restore_delay_ms) {
+            return Err(AppError::InvalidInput("Clipboard restore delay must be between 100 and 10000 ms".to_string()));
+        }
+        Ok(())
+    }
+}
+
+/// A transcript as it goes on the clipboard
+pub enum ClipboardText {
+    Plain(String),
+    /// Plain text plus HTML and (on macOS) RTF, for modes that produce Markdown
+    Rich(Formatted),
+}
+
+impl ClipboardText {
+    pub fn for_mode(text: &str, mode: Option<&str>) -> Self {
+        match mode.and_then(TranscriptionMode::from_id) {
+            Some(mode) if mode.rich_output() => Self::Rich(markdown::render(text)),
+            _ => Self::Plain(text.to_string()),
+        }
+    }
+
+    /// What plain-text targets get
+    pub fn plain(&self) -> &str {
+        match self {
+            Self::Plain(text) => text,
+            Self::Rich(formatted) => &formatted.plain,
+        }
+    }
+
+    pub fn write(&self, clipboard: &mut Clipboard) -> AppResult<()> {
+        let result = match self {
+            Self::Plain(text) => clipboard.set_text(text),
+            #[cfg(target_os = "macos")]
+            Self::Rich(formatted) => return macos::write_flavors(formatted),
+            #[cfg(not(target_os = "macos"))]
+            Self::Rich(formatted) => clipboard.set_html(&formatted.html, Some(&formatted.plain)),
+        };
+        result.map_err(|e| AppError::Clipboard(format!("Failed to copy to clipboard: {}", e)))
+    }
+}
+
+/// The clipboard contents, as far as arboard can read them. Only the richest
+/// format is kept, except HTML which keeps its plain-text alternative.
+pub enum ClipboardSnapshot {
+    Empty,
+    Text(String),
+    Html { html: String, alt_text: Option<String> },
+    Image(ImageData<'static>),
+    Files(Vec<PathBuf>),
+}
+
+impl ClipboardSnapshot {
+    pub fn capture(clipboard: &mut Clipboard) -> Self {
+        // Copied files usually come with a text form of their paths, so check them first
+        if let Ok(files) = clipboard.get().file_list() {
+            if !files.is_empty() {
+                return Self::Files(files);
+            }
+        }
+        if let Ok(html) = clipboard.get().html() {
+            return Self::Html {
+                html,
+                alt_text: clipboard.get_text().ok(),
+            };
+        }
+        if let Ok(image) = clipboard.get_image() {
+            return Self::Image(image);
+        }
+        match clipboard.get_text() {
+            Ok(text) => Self::Text(text),
+            Err(_) => Self::Empty,
+        }
+    }
+
+    pub fn restore(self, clipboard: &mut Clipboard) -> AppResult<()> {
+        let result = match self {
+            Self::Empty => clipboard.clear(),
+            Self::Text(text) => clipboard.set_text(text),
+            Self::Html { html, alt_text } => clipboard.set_html(html, alt_text),
+            Self::Image(image) => clipboard.set_image(image),
+            Self::Files(files) => clipboard.set().file_list(&files),
+        };
+        result.map_err(|e| AppError::Clipboard(format!("Failed to restore the clipboard: {}", e)))
+    }
+}
+
+/// A transcript placed on the clipboard for pasting, remembering what it replaced
+pub struct ClipboardTransaction {
+    /// Kept open until the transaction ends. On X11 this handle serves the
+    /// contents, which vanish when it is dropped unless a clipboard manager takes them.
+    clipboard: Clipboard,
+    snapshot: ClipboardSnapshot,
+    text: String,
+}
+
+impl ClipboardTransaction {
+    /// Snapshot the clipboard, then replace its contents with `text`
+    pub fn begin(text: &ClipboardText) -> AppResult<Self> {
+        let mut clipboard = open()?;
+        let snapshot = ClipboardSnapshot::capture(&mut clipboard);
+        text.write(&mut clipboard)?;
+        Ok(Self {
+            clipboard,
+            snapshot,
+            text: text.plain().to_string(),
+        })
+    }
+
+    /// Put the snapshot back after `delay` on a background thread. Skipped if
+    /// something else was copied in the meantime.
+    pub fn restore_after(self, delay: Duration) {
+        let Self { mut clipboard, snapshot, text } = self;
+        std::thread::spawn(move || {
+            std::thread::sleep(delay);
+            if clipboard.get_text().ok().as_deref() != Some(text.as_str()) {
+                log::debug!("Clipboard changed since pasting, not restoring");
+                return;
+            }
+            if let Err(e) = snapshot.restore(&mut clipboard) {
+                log::warn!("{}", e);
+            }
+        });
+    }
+
+    /// Leave the transcript on the clipboard, keeping the handle open for `delay`
+    /// so the paste can read it before it is handed to a clipboard manager
+    pub fn keep_for(self, delay: Duration) {
+        std::thread::spawn(move || {
+            std::thread::sleep(delay);
+            drop(self);
+        });
+    }
+}
+
+pub fn open() -> AppResult<Clipboard> {
+    Clipboard::new().map_err(|e| AppError::Clipboard(format!("Failed to access clipboard: {}", e)))
+}
+
+#[cfg(target_os = "macos")]
+mod macos {
+    use cocoa::appkit::{NSPasteboard, NSPasteboardTypeHTML, NSPasteboardTypeRTF, NSPasteboardTypeString};
+    use cocoa::base::{nil, NO};
+    use cocoa::foundation::NSString;
+    use objc::{msg_send, sel, sel_impl};
+
+    use crate::error::{AppError, AppResult};
+    use crate::markdown::Formatted;
+
+    /// arboard can't write RTF, which many Mac editors prefer over HTML
+    pub fn write_flavors(formatted: &Formatted) -> AppResult<()> {
+        unsafe {
+            let pasteboard = NSPasteboard::generalPasteboard(nil);
+            pasteboard.clearContents();
+            for (flavor, contents) in [
+                (NSPasteboardTypeHTML, &formatted.html),
+                (NSPasteboardTypeRTF, &formatted.rtf),
+                (NSPasteboardTypeString, &formatted.plain),
+            ] {
+                let string = NSString::alloc(nil).init_str(contents);
+                let written = pasteboard.setString_forType(string, flavor);
+                let _: () = msg_send![string, release];
+                if written == NO {
+                    return Err(AppError::Clipboard("Failed to copy to clipboard".to_string()));
+                }
+            }
+        }
+        Ok(())
+    }
+}
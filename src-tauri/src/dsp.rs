@@ -0,0 +1,366 @@
+use serde::{Deserialize, Serialize};
+use std::f32::consts::PI;
+
+use crate::error::{AppError, AppResult};
+
+/// Conditioning applied to a recording between capture and transcription
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DspSettings {
+    /// Off sends the microphone signal to Whisper untouched
+    pub enabled: bool,
+    /// Cut rumble, hum and desk thumps below `high_pass_hz`
+    pub high_pass: bool,
+    pub high_pass_hz: f32,
+    /// Attenuate steady background noise such as fans and air conditioning
+    pub noise_suppression: bool,
+    /// Bring speech to `target_level_db` so quiet speakers are heard
+    pub auto_gain: bool,
+    /// Speech RMS after gain, in dBFS
+    pub target_level_db: f32,
+}
+
+impl Default for DspSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            high_pass: true,
+            high_pass_hz: 80.0,
+            noise_suppression: true,
+            auto_gain: true,
+            target_level_db: -20.0,
+        }
+    }
+}
+
+impl DspSettings {
+    pub fn validate(&self) -> AppResult<()> {
+        if !(20.0..=300.0).contains(&self.high_pass_hz) {
+            return Err(AppError::InvalidInput("High-pass cutoff must be between 20 and 300 Hz".to_string()));
+        }
+        if !(-40.0..=-6.0).contains(&self.target_level_db) {
+            return Err(AppError::InvalidInput("Target level must be between -40 and -6 dBFS".to_string()));
+        }
+        Ok(())
+    }
+}
+
+/// Run the enabled stages in order: high-pass, noise suppression, gain
+pub fn process(samples: &mut [f32], sample_rate: u32, settings: &DspSettings) {
+    if !settings.enabled || samples.is_empty() {
+        return;
+    }
+    if settings.high_pass {
+        high_pass(samples, sample_rate, settings.high_pass_hz);
+    }
+    if settings.noise_suppression {
+        suppress_noise(samples, sample_rate);
+    }
+    if settings.auto_gain {
+        normalize(samples, sample_rate, settings.target_level_db);
+    }
+}
+
+/// Second-order Butterworth high-pass (RBJ cookbook biquad)
+fn high_pass(samples: &mut [f32], sample_rate: u32, cutoff_hz: f32) {
+    let w0 = 2.0 * PI * cutoff_hz / sample_rate as f32;
+    let alpha = w0.sin() / (2.0 * std::f32::consts::FRAC_1_SQRT_2);
+    let cos = w0.cos();
+    let a0 = 1.0 + alpha;
+    let b0 = (1.0 + cos) / 2.0 / a0;
+    let b1 = -(1.0 + cos) / a0;
+    let b2 = b0;
+    let a1 = -2.0 * cos / a0;
+    let a2 = (1.0 - alpha) / a0;
+
+    let (mut x1, mut x2, mut y1, mut y2) = (0.0, 0.0, 0.0, 0.0);
+    for sample in samples.iter_mut() {
+        let x = *sample;
+        let y = b0 * x + b1 * x1 + b2 * x2 - a1 * y1 - a2 * y2;
+        x2 = x1;
+        x1 = x;
+        y2 = y1;
+        y1 = y;
+        *sample = y;
+    }
+}
+
+/// Noise power is scaled by this before subtracting; above 1 removes more noise and more speech
+const OVER_SUBTRACTION: f32 = 2.0;
+/// Least gain applied to a bin, about -20 dB, so noise is lowered rather than gated to silence
+const GAIN_FLOOR: f32 = 0.1;
+/// Bins whose magnitude sits at or below this percentile over the recording are taken as noise
+const NOISE_PERCENTILE: f32 = 0.2;
+/// How far a bin's gain may fall per frame. Slower release avoids "musical" noise.
+const GAIN_RELEASE: f32 = 0.6;
+
+/// Spectral subtraction. The whole recording is available, so the noise
+/// spectrum is estimated from the quietest frames of each bin instead of
+/// tracked over time as a live denoiser has to.
+fn suppress_noise(samples: &mut [f32], sample_rate: u32) {
+    // About 32 ms frames with 50% overlap
+    let size = ((sample_rate as f32 * 0.032) as usize).next_power_of_two();
+    let hop = size / 2;
+    let bins = size / 2 + 1;
+    // Too short to tell noise from speech
+    if samples.len() < size * 8 {
+        return;
+    }
+
+    // sqrt-Hann on analysis and synthesis overlap-adds back to unity at 50% overlap
+    let window: Vec<f32> = (0..size)
+        .map(|i| (0.5 - 0.5 * (2.0 * PI * i as f32 / size as f32).cos()).sqrt())
+        .collect();
+
+    // Pad so every sample is covered by two frames
+    let mut padded = vec![0.0; hop];
+    padded.extend_from_slice(samples);
+    padded.resize(padded.len() + size, 0.0);
+    let frames = (padded.len() - size) / hop + 1;
+
+    let spectra: Vec<(Vec<f32>, Vec<f32>)> = (0..frames)
+        .map(|f| {
+            let start = f * hop;
+            let mut re: Vec<f32> = padded[start..start + size].iter().zip(&window).map(|(s, w)| s * w).collect();
+            let mut im = vec![0.0; size];
+            fft(&mut re, &mut im, false);
+            (re, im)
+        })
+        .collect();
+
+    let noise_power: Vec<f32> = (0..bins)
+        .map(|bin| {
+            let mut powers: Vec<f32> = spectra.iter().map(|(re, im)| re[bin] * re[bin] + im[bin] * im[bin]).collect();
+            let k = ((powers.len() as f32 * NOISE_PERCENTILE) as usize).min(powers.len() - 1);
+            // Noise power in a bin is roughly exponentially distributed, so a low
+            // percentile underestimates its mean by this factor
+            *powers.select_nth_unstable_by(k, f32::total_cmp).1 / -(1.0 - NOISE_PERCENTILE).ln()
+        })
+        .collect();
+
+    let mut output = vec![0.0; padded.len()];
+    let mut gains = vec![1.0f32; bins];
+    for (f, (mut re, mut im)) in spectra.into_iter().enumerate() {
+        for bin in 0..bins {
+            let power = re[bin] * re[bin] + im[bin] * im[bin];
+            let target = if power > 0.0 {
+                (1.0 - OVER_SUBTRACTION * noise_power[bin] / power).max(0.0).sqrt()
+            } else {
+                0.0
+            };
+            gains[bin] = target.max(gains[bin] * GAIN_RELEASE).max(GAIN_FLOOR);
+        }
+        // Real input has a mirrored spectrum; keep it mirrored so the output stays real
+        for i in 0..size {
+            let bin = if i < bins { i } else { size - i };
+            re[i] *= gains[bin];
+            im[i] *= gains[bin];
+        }
+        fft(&mut re, &mut im, true);
+        let start = f * hop;
+        for i in 0..size {
+            output[start + i] += re[i] * window[i];
+        }
+    }
+
+    samples.copy_from_slice(&output[hop..hop + samples.len()]);
+}
+
+/// In-place radix-2 FFT; `re.len()` must be a power of two. The inverse is scaled by 1/n.
+fn fft(re: &mut [f32], im: &mut [f32], inverse: bool) {
+    let n = re.len();
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+
+    let sign = if inverse { 1.0 } else { -1.0 };
+    let mut len = 2;
+    while len <= n {
+        let angle = sign * 2.0 * PI / len as f32;
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let (w_im, w_re) = (angle * k as f32).sin_cos();
+                let (a, b) = (start + k, start + k + len / 2);
+                let t_re = re[b] * w_re - im[b] * w_im;
+                let t_im = re[b] * w_im + im[b] * w_re;
+                re[b] = re[a] - t_re;
+                im[b] = im[a] - t_im;
+                re[a] += t_re;
+                im[a] += t_im;
+            }
+        }
+        len <<= 1;
+    }
+
+    if inverse {
+        let scale = 1.0 / n as f32;
+        re.iter_mut().chain(im.iter_mut()).for_each(|v| *v *= scale);
+    }
+}
+
+/// Most gain applied to quiet speech, +30 dB
+const MAX_GAIN: f32 = 31.6;
+/// Peaks are kept under -1 dBFS
+const PEAK_LIMIT: f32 = 0.89;
+/// Frames quieter than this (-60 dBFS) are never counted as speech
+const SILENCE_RMS: f32 = 0.001;
+
+/// Scale the recording so its speech, not its pauses, sits at `target_db`.
+/// One gain for the whole recording, so loudness doesn't pump between words.
+fn normalize(samples: &mut [f32], sample_rate: u32, target_db: f32) {
+    let frame = (sample_rate as usize / 50).max(1);
+    let frame_rms: Vec<f32> = samples
+        .chunks(frame)
+        .map(|c| (c.iter().map(|s| s * s).sum::<f32>() / c.len() as f32).sqrt())
+        .collect();
+
+    // Speech frames stand well clear of the background
+    let mut sorted = frame_rms.clone();
+    sorted.sort_by(f32::total_cmp);
+    let background = sorted[sorted.len() / 5];
+    let threshold = (background * 3.0).max(SILENCE_RMS);
+    let speech: Vec<f32> = frame_rms.into_iter().filter(|&rms| rms > threshold).collect();
+    if speech.is_empty() {
+        return;
+    }
+    let speech_rms = (speech.iter().map(|r| r * r).sum::<f32>() / speech.len() as f32).sqrt();
+
+    let target = 10f32.powf(target_db / 20.0);
+    let peak = samples.iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+    let gain = (target / speech_rms).min(MAX_GAIN).min(PEAK_LIMIT / peak);
+    samples.iter_mut().for_each(|s| *s *= gain);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 16000;
+
+    fn tone(freq: f32, amplitude: f32, len: usize) -> Vec<f32> {
+        (0..len)
+            .map(|i| amplitude * (2.0 * PI * freq * i as f32 / RATE as f32).sin())
+            .collect()
+    }
+
+    /// Amplitude of the `freq` component, by correlating with a sine and cosine
+    fn amplitude_at(samples: &[f32], freq: f32) -> f32 {
+        let (mut re, mut im) = (0.0, 0.0);
+        for (i, s) in samples.iter().enumerate() {
+            let phase = 2.0 * PI * freq * i as f32 / RATE as f32;
+            re += s * phase.cos();
+            im += s * phase.sin();
+        }
+        2.0 * (re * re + im * im).sqrt() / samples.len() as f32
+    }
+
+    fn rms(samples: &[f32]) -> f32 {
+        (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+    }
+
+    /// Deterministic white noise in [-amplitude, amplitude]
+    fn noise(amplitude: f32, len: usize) -> Vec<f32> {
+        let mut state: u32 = 0x2545_f491;
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                amplitude * (state as f32 / u32::MAX as f32 * 2.0 - 1.0)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn high_pass_removes_rumble_and_keeps_speech() {
+        let mut samples: Vec<f32> = tone(30.0, 0.3, RATE as usize)
+            .iter()
+            .zip(tone(1000.0, 0.3, RATE as usize))
+            .map(|(a, b)| a + b)
+            .collect();
+        high_pass(&mut samples, RATE, 80.0);
+
+        // Skip the filter's settling time
+        let settled = &samples[RATE as usize / 4..];
+        assert!(amplitude_at(settled, 30.0) < 0.3 * 0.2, "30 Hz at {}", amplitude_at(settled, 30.0));
+        assert!(amplitude_at(settled, 1000.0) > 0.3 * 0.95, "1 kHz at {}", amplitude_at(settled, 1000.0));
+    }
+
+    #[test]
+    fn noise_suppression_lowers_the_floor_and_keeps_speech() {
+        // Two seconds of 300 ms voiced bursts with 200 ms pauses over steady hiss
+        let len = 2 * RATE as usize;
+        let burst = |i: usize| i % 8000 < 4800;
+        let voice: Vec<f32> = (0..len)
+            .map(|i| {
+                let t = i as f32 / RATE as f32;
+                let voiced = (2.0 * PI * 220.0 * t).sin() + 0.5 * (2.0 * PI * 440.0 * t).sin() + 0.25 * (2.0 * PI * 660.0 * t).sin();
+                if burst(i) { 0.15 * voiced } else { 0.0 }
+            })
+            .collect();
+        let hiss = noise(0.02, len);
+        let mut samples: Vec<f32> = voice.iter().zip(&hiss).map(|(v, n)| v + n).collect();
+
+        suppress_noise(&mut samples, RATE);
+
+        // The middle of each pause, and of each burst, away from the edges
+        let gaps: Vec<f32> = (0..len).filter(|&i| (5200..7600).contains(&(i % 8000))).map(|i| samples[i]).collect();
+        let gap_noise: Vec<f32> = (0..len).filter(|&i| (5200..7600).contains(&(i % 8000))).map(|i| hiss[i]).collect();
+        assert!(rms(&gaps) < rms(&gap_noise) * 0.5, "floor {} from {}", rms(&gaps), rms(&gap_noise));
+
+        let bursts: Vec<usize> = (0..len).filter(|&i| (800..4000).contains(&(i % 8000))).collect();
+        let kept: Vec<f32> = bursts.iter().map(|&i| samples[i]).collect();
+        let clean: Vec<f32> = bursts.iter().map(|&i| voice[i]).collect();
+        assert!(amplitude_at(&kept, 220.0) > amplitude_at(&clean, 220.0) * 0.85);
+        assert!(rms(&kept) > rms(&clean) * 0.85, "speech {} from {}", rms(&kept), rms(&clean));
+    }
+
+    #[test]
+    fn normalize_reaches_the_target_level() {
+        // Quiet speech with pauses in between
+        let mut samples: Vec<f32> = tone(300.0, 0.01, RATE as usize)
+            .into_iter()
+            .enumerate()
+            .map(|(i, s)| if i % 8000 < 5000 { s } else { 0.0 })
+            .collect();
+        normalize(&mut samples, RATE, -20.0);
+
+        let speech: Vec<f32> = samples.iter().enumerate().filter(|(i, _)| i % 8000 < 5000).map(|(_, s)| *s).collect();
+        let level_db = 20.0 * rms(&speech).log10();
+        assert!((level_db + 20.0).abs() < 0.5, "level {} dBFS", level_db);
+        assert!(samples.iter().all(|s| s.abs() <= PEAK_LIMIT));
+    }
+
+    #[test]
+    fn normalize_never_exceeds_the_peak_limit() {
+        // Quiet speech with a loud click that would clip at the target gain
+        let mut samples = tone(300.0, 0.01, RATE as usize);
+        samples[4000] = 0.5;
+        normalize(&mut samples, RATE, -6.0);
+        let peak = samples.iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+        assert!(peak <= PEAK_LIMIT + 1e-6, "peak {}", peak);
+
+        // Near silence is not pumped up to the target
+        let mut hiss = noise(0.0001, RATE as usize);
+        normalize(&mut hiss, RATE, -20.0);
+        assert!(hiss.iter().all(|s| s.abs() <= 0.0001 * MAX_GAIN));
+    }
+
+    #[test]
+    fn disabled_processing_leaves_samples_alone() {
+        let original = tone(50.0, 0.2, 4000);
+        let mut samples = original.clone();
+        process(&mut samples, RATE, &DspSettings::default());
+        assert_eq!(samples, original);
+    }
+}
@@ -0,0 +1,177 @@
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+use std::fmt;
+
+/// Errors surfaced by the audio → Whisper → Ollama pipeline and the Tauri commands.
+///
+/// Serialized to the frontend as `{ code, message, hint }` so the UI can branch on
+/// `code` and show `hint` as a remediation step.
+#[derive(Debug, Clone)]
+pub enum AppError {
+    /// No usable input device, or the device failed while capturing
+    Device(String),
+    /// The OS denied access to the microphone or accessibility APIs
+    Permission(String),
+    /// Nothing (or only silence) was captured
+    NoAudio(String),
+    /// The Whisper model file is missing or not loaded
+    ModelMissing(String),
+    /// The Whisper model exists but could not be loaded
+    ModelLoad(String),
+    /// Audio bytes could not be decoded or encoded
+    Decode(String),
+    /// Whisper ran but produced nothing usable
+    Transcription(String),
+    /// Ollama is not reachable
+    LlmUnavailable(String),
+    /// Ollama is running but the requested model is not installed
+    LlmModelMissing(String),
+    /// Ollama answered with an error or an unusable response
+    Llm(String),
+    /// Ollama answered, but the output failed validation (answered, translated, refused...)
+    LlmRejected(String),
+    /// A request took longer than allowed
+    Timeout(String),
+    /// The operation was cancelled by the user or superseded by a newer one
+    Cancelled(String),
+    Clipboard(String),
+    Paste(String),
+    Hotkey(String),
+    Io(String),
+    InvalidInput(String),
+    Internal(String),
+}
+
+impl AppError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Device(_) => "device",
+            Self::Permission(_) => "permission",
+            Self::NoAudio(_) => "no_audio",
+            Self::ModelMissing(_) => "model_missing",
+            Self::ModelLoad(_) => "model_load",
+            Self::Decode(_) => "decode",
+            Self::Transcription(_) => "transcription",
+            Self::LlmUnavailable(_) => "llm_unavailable",
+            Self::LlmModelMissing(_) => "llm_model_missing",
+            Self::Llm(_) => "llm",
+            Self::LlmRejected(_) => "llm_rejected",
+            Self::Timeout(_) => "timeout",
+            Self::Cancelled(_) => "cancelled",
+            Self::Clipboard(_) => "clipboard",
+            Self::Paste(_) => "paste",
+            Self::Hotkey(_) => "hotkey",
+            Self::Io(_) => "io",
+            Self::InvalidInput(_) => "invalid_input",
+            Self::Internal(_) => "internal",
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            Self::Device(m)
+            | Self::Permission(m)
+            | Self::NoAudio(m)
+            | Self::ModelMissing(m)
+            | Self::ModelLoad(m)
+            | Self::Decode(m)
+            | Self::Transcription(m)
+            | Self::LlmUnavailable(m)
+            | Self::LlmModelMissing(m)
+            | Self::Llm(m)
+            | Self::LlmRejected(m)
+            | Self::Timeout(m)
+            | Self::Cancelled(m)
+            | Self::Clipboard(m)
+            | Self::Paste(m)
+            | Self::Hotkey(m)
+            | Self::Io(m)
+            | Self::InvalidInput(m)
+            | Self::Internal(m) => m,
+        }
+    }
+
+    /// A short, user-facing suggestion for fixing the problem
+    pub fn hint(&self) -> Option<&'static str> {
+        match self {
+            Self::Device(_) => Some("Check that a microphone is connected and selected in settings."),
+            Self::Permission(_) if cfg!(target_os = "linux") => Some("Check that your user can open the audio device (the `audio` group or your sandbox's permissions), then restart the app."),
+            Self::Permission(_) => Some("Grant access in System Settings > Privacy & Security, then restart the app."),
+            Self::NoAudio(_) if cfg!(target_os = "linux") => Some("Check the input device and its volume in your sound settings, and speak louder or longer."),
+            Self::NoAudio(_) => Some("Check microphone permissions in System Settings > Privacy & Security > Microphone, and speak louder or longer."),
+            Self::ModelMissing(_) => Some("Download a Whisper model into the models directory and load it in settings."),
+            Self::ModelLoad(_) => Some("The model file may be corrupt. Download it again."),
+            Self::Decode(_) => None,
+            Self::Transcription(_) => Some("Try speaking louder or longer."),
+            Self::LlmUnavailable(_) => Some("Start Ollama or disable AI cleanup."),
+            Self::LlmModelMissing(_) => Some("Pull the model from settings or run `ollama pull <model>`."),
+            Self::Llm(_) => None,
+            Self::LlmRejected(_) => Some("Try a larger cleanup model, or disable AI cleanup for this mode."),
+            Self::Timeout(_) => Some("Ollama may be loading the model. Try again in a moment."),
+            Self::Cancelled(_) => None,
+            Self::Clipboard(_) => None,
+            Self::Paste(_) if cfg!(target_os = "linux") => Some("Install wtype on Wayland, or libXtst on X11, so the app can paste for you."),
+            Self::Paste(_) => Some("Grant accessibility access so the app can paste for you."),
+            Self::Hotkey(_) => Some("The shortcut may be taken by another app. Try a different one."),
+            Self::Io(_) => None,
+            Self::InvalidInput(_) => None,
+            Self::Internal(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("AppError", 3)?;
+        s.serialize_field("code", self.code())?;
+        s.serialize_field("message", self.message())?;
+        s.serialize_field("hint", &self.hint())?;
+        s.end()
+    }
+}
+
+impl From<std::io::Error> for AppError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e.to_string())
+    }
+}
+
+pub type AppResult<T> = Result<T, AppError>;
+
+/// A non-fatal problem the pipeline recovered from, reported instead of hidden
+#[derive(Debug, Clone, Serialize)]
+pub struct PipelineWarning {
+    pub code: &'static str,
+    pub message: String,
+}
+
+impl PipelineWarning {
+    pub fn new(code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+
+    /// Cleanup failed or was rejected and the raw transcript was used instead
+    pub fn cleanup_fallback(err: &AppError) -> Self {
+        match err {
+            AppError::LlmRejected(_) => Self::new(
+                "cleanup_rejected",
+                format!("{}, using the raw transcript", err),
+            ),
+            _ => Self::new(
+                "cleanup_fallback",
+                format!("AI cleanup failed ({}), using the raw transcript", err),
+            ),
+        }
+    }
+}
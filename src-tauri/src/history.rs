@@ -0,0 +1,558 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::error::{AppError, AppResult};
+
+/// A finished transcript as stored on disk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub id: u64,
+    /// Unix time in milliseconds
+    pub created_at: u64,
+    pub raw_text: String,
+    pub cleaned_text: String,
+    pub mode: String,
+    pub language: String,
+    /// Ollama model used for cleanup, if cleanup ran
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub whisper_model: Option<String>,
+    #[serde(default)]
+    pub audio_duration_ms: Option<u64>,
+    #[serde(default)]
+    pub transcribe_ms: Option<u64>,
+    #[serde(default)]
+    pub cleanup_ms: Option<u64>,
+    /// The raw text cleaned up again in other modes, at most one per mode
+    #[serde(default)]
+    pub variants: Vec<TranscriptVariant>,
+}
+
+/// An alternative cleanup of an entry's raw text
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranscriptVariant {
+    pub mode: String,
+    pub text: String,
+    #[serde(default)]
+    pub model: Option<String>,
+    pub created_at: u64,
+    #[serde(default)]
+    pub cleanup_ms: Option<u64>,
+}
+
+/// Fields supplied by the caller when recording a transcript
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct NewHistoryEntry {
+    pub raw_text: String,
+    pub cleaned_text: String,
+    pub mode: String,
+    pub language: String,
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub whisper_model: Option<String>,
+    #[serde(default)]
+    pub audio_duration_ms: Option<u64>,
+    #[serde(default)]
+    pub transcribe_ms: Option<u64>,
+    #[serde(default)]
+    pub cleanup_ms: Option<u64>,
+    /// Keep an existing timestamp when importing; defaults to now
+    #[serde(default)]
+    pub created_at: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct HistoryQuery {
+    /// Whitespace-separated terms; every term must appear in the raw or cleaned text
+    #[serde(default)]
+    pub search: Option<String>,
+    #[serde(default)]
+    pub mode: Option<String>,
+    #[serde(default)]
+    pub offset: usize,
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HistoryPage {
+    /// Newest first
+    pub entries: Vec<HistoryEntry>,
+    /// Number of matching entries before pagination
+    pub total: usize,
+}
+
+/// Limits applied after every insert. `None` means unlimited, the default.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetentionPolicy {
+    pub max_entries: Option<usize>,
+    pub max_age_days: Option<u32>,
+}
+
+const DEFAULT_PAGE_SIZE: usize = 50;
+
+/// Append-only JSON-lines store. New entries are appended; deletions and retention
+/// rewrite the file through a temp file so a crash never leaves it half-written.
+pub struct HistoryStore {
+    path: PathBuf,
+    /// Oldest first, mirroring the file
+    entries: Vec<HistoryEntry>,
+    next_id: u64,
+    retention: RetentionPolicy,
+    /// The file on disk couldn't be read, so this store doesn't know every id
+    recovered: bool,
+    /// The unreadable file couldn't be moved aside either; writes fail rather than replace it
+    read_only: bool,
+}
+
+impl HistoryStore {
+    pub fn open(path: PathBuf, retention: RetentionPolicy) -> AppResult<Self> {
+        let mut entries = Vec::new();
+
+        if path.exists() {
+            let file = File::open(&path)?;
+            for (i, line) in BufReader::new(file).lines().enumerate() {
+                let line = line?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                match serde_json::from_str::<HistoryEntry>(&line) {
+                    Ok(entry) => entries.push(entry),
+                    // A torn final write shouldn't cost the user their whole history
+                    Err(e) => log::warn!("Skipping malformed history line {}: {}", i + 1, e),
+                }
+            }
+        }
+
+        let next_id = entries.iter().map(|e| e.id).max().map_or(1, |id| id + 1);
+
+        Ok(Self {
+            path,
+            entries,
+            next_id,
+            retention,
+            recovered: false,
+            read_only: false,
+        })
+    }
+
+    /// An empty store writing to `path`, once the unreadable file there has been moved aside
+    pub fn empty(path: PathBuf, retention: RetentionPolicy) -> Self {
+        Self {
+            path,
+            entries: Vec::new(),
+            next_id: 1,
+            retention,
+            recovered: true,
+            read_only: false,
+        }
+    }
+
+    /// An empty store that refuses to write, for an unreadable file that couldn't be moved aside
+    pub fn read_only(path: PathBuf, retention: RetentionPolicy) -> Self {
+        Self {
+            read_only: true,
+            ..Self::empty(path, retention)
+        }
+    }
+
+    /// Never hand out `last_used` or a lower id again, e.g. one an archived recording still carries
+    pub fn reserve_ids(&mut self, last_used: u64) {
+        self.next_id = self.next_id.max(last_used + 1);
+    }
+
+    /// Whether entries may exist that this store couldn't read
+    pub fn is_recovered(&self) -> bool {
+        self.recovered
+    }
+
+    pub fn add(&mut self, new: NewHistoryEntry) -> AppResult<HistoryEntry> {
+        let entry = HistoryEntry {
+            id: self.next_id,
+            created_at: new.created_at.unwrap_or_else(now_ms),
+            raw_text: new.raw_text,
+            cleaned_text: new.cleaned_text,
+            mode: new.mode,
+            language: new.language,
+            model: new.model,
+            whisper_model: new.whisper_model,
+            audio_duration_ms: new.audio_duration_ms,
+            transcribe_ms: new.transcribe_ms,
+            cleanup_ms: new.cleanup_ms,
+            variants: Vec::new(),
+        };
+
+        self.check_writable()?;
+        append_line(&self.path, &entry)?;
+        self.next_id += 1;
+        self.entries.push(entry.clone());
+
+        self.apply_retention()?;
+        Ok(entry)
+    }
+
+    pub fn get(&self, id: u64) -> Option<&HistoryEntry> {
+        self.entries.iter().find(|e| e.id == id)
+    }
+
+    pub fn ids(&self) -> HashSet<u64> {
+        self.entries.iter().map(|e| e.id).collect()
+    }
+
+    /// Modify an entry in place and persist the change
+    pub fn update<F>(&mut self, id: u64, f: F) -> AppResult<HistoryEntry>
+    where
+        F: FnOnce(&mut HistoryEntry),
+    {
+        let entry = self
+            .entries
+            .iter_mut()
+            .find(|e| e.id == id)
+            .ok_or_else(|| AppError::InvalidInput(format!("No history entry with id {}", id)))?;
+        f(entry);
+        let updated = entry.clone();
+        self.rewrite()?;
+        Ok(updated)
+    }
+
+    /// Store a variant, replacing any earlier one for the same mode
+    pub fn set_variant(&mut self, id: u64, variant: TranscriptVariant) -> AppResult<HistoryEntry> {
+        self.update(id, |entry| {
+            entry.variants.retain(|v| v.mode != variant.mode);
+            entry.variants.push(variant);
+        })
+    }
+
+    pub fn remove_variant(&mut self, id: u64, mode: &str) -> AppResult<HistoryEntry> {
+        self.update(id, |entry| entry.variants.retain(|v| v.mode != mode))
+    }
+
+    pub fn query(&self, query: &HistoryQuery) -> HistoryPage {
+        let terms: Vec<String> = query
+            .search
+            .as_deref()
+            .unwrap_or("")
+            .split_whitespace()
+            .map(str::to_lowercase)
+            .collect();
+
+        let matches: Vec<&HistoryEntry> = self
+            .entries
+            .iter()
+            .rev()
+            .filter(|e| query.mode.as_deref().is_none_or(|m| e.mode == m))
+            .filter(|e| {
+                if terms.is_empty() {
+                    return true;
+                }
+                let mut haystack = format!("{}\n{}", e.raw_text, e.cleaned_text);
+                for variant in &e.variants {
+                    haystack.push('\n');
+                    haystack.push_str(&variant.text);
+                }
+                let haystack = haystack.to_lowercase();
+                terms.iter().all(|t| haystack.contains(t.as_str()))
+            })
+            .collect();
+
+        let total = matches.len();
+        let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+        let entries = matches
+            .into_iter()
+            .skip(query.offset)
+            .take(limit)
+            .cloned()
+            .collect();
+
+        HistoryPage { entries, total }
+    }
+
+    /// Delete entries by id, returning how many were removed
+    pub fn delete(&mut self, ids: &[u64]) -> AppResult<usize> {
+        let before = self.entries.len();
+        self.entries.retain(|e| !ids.contains(&e.id));
+        let removed = before - self.entries.len();
+        if removed > 0 {
+            self.rewrite()?;
+        }
+        Ok(removed)
+    }
+
+    pub fn clear(&mut self) -> AppResult<()> {
+        self.entries.clear();
+        self.rewrite()
+    }
+
+    pub fn retention(&self) -> &RetentionPolicy {
+        &self.retention
+    }
+
+    pub fn set_retention(&mut self, retention: RetentionPolicy) -> AppResult<usize> {
+        self.retention = retention;
+        self.apply_retention()
+    }
+
+    /// Drop entries outside the retention policy, returning how many were removed
+    fn apply_retention(&mut self) -> AppResult<usize> {
+        let before = self.entries.len();
+
+        if let Some(days) = self.retention.max_age_days {
+            let cutoff = now_ms().saturating_sub(days as u64 * 24 * 60 * 60 * 1000);
+            self.entries.retain(|e| e.created_at >= cutoff);
+        }
+
+        if let Some(max) = self.retention.max_entries {
+            if self.entries.len() > max {
+                let excess = self.entries.len() - max;
+                self.entries.drain(..excess);
+            }
+        }
+
+        let removed = before - self.entries.len();
+        if removed > 0 {
+            self.rewrite()?;
+        }
+        Ok(removed)
+    }
+
+    fn check_writable(&self) -> AppResult<()> {
+        if self.read_only {
+            return Err(AppError::Internal(format!(
+                "History at {} couldn't be read or moved aside; not saving over it",
+                self.path.display()
+            )));
+        }
+        Ok(())
+    }
+
+    fn rewrite(&self) -> AppResult<()> {
+        self.check_writable()?;
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let tmp_path = self.path.with_extension("jsonl.tmp");
+        {
+            let mut file = File::create(&tmp_path)?;
+            for entry in &self.entries {
+                write_entry(&mut file, entry)?;
+            }
+            file.sync_all()?;
+        }
+        fs::rename(&tmp_path, &self.path)?;
+        Ok(())
+    }
+}
+
+fn append_line(path: &Path, entry: &HistoryEntry) -> AppResult<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    write_entry(&mut file, entry)
+}
+
+fn write_entry(file: &mut File, entry: &HistoryEntry) -> AppResult<()> {
+    let line = serde_json::to_string(entry)
+        .map_err(|e| AppError::Internal(format!("Failed to serialize history entry: {}", e)))?;
+    writeln!(file, "{}", line)?;
+    Ok(())
+}
+
+pub fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Get the default history file path
+pub fn get_history_path() -> PathBuf {
+    let mut path = dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."));
+    path.push("yap-to-text");
+    path.push("history.jsonl");
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A path for `history.jsonl` in a fresh directory
+    fn history_path(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("yap-history-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir.join("history.jsonl")
+    }
+
+    fn transcript(text: &str, mode: &str) -> NewHistoryEntry {
+        NewHistoryEntry {
+            raw_text: text.to_string(),
+            cleaned_text: text.to_string(),
+            mode: mode.to_string(),
+            language: "en".to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn append_line_raw(path: &Path, line: &str) {
+        let mut file = OpenOptions::new().append(true).open(path).unwrap();
+        writeln!(file, "{}", line).unwrap();
+    }
+
+    fn texts(page: &HistoryPage) -> Vec<&str> {
+        page.entries.iter().map(|e| e.raw_text.as_str()).collect()
+    }
+
+    #[test]
+    fn added_entries_survive_reopening() {
+        let path = history_path("reopen");
+        let mut store = HistoryStore::open(path.clone(), RetentionPolicy::default()).unwrap();
+        let first = store.add(transcript("first", "default")).unwrap();
+        let second = store.add(transcript("second", "email")).unwrap();
+        assert_eq!((first.id, second.id), (1, 2));
+
+        let mut reopened = HistoryStore::open(path, RetentionPolicy::default()).unwrap();
+        assert_eq!(reopened.get(2).map(|e| e.mode.as_str()), Some("email"));
+        assert_eq!(reopened.add(transcript("third", "default")).unwrap().id, 3);
+    }
+
+    #[test]
+    fn malformed_lines_are_skipped() {
+        let path = history_path("malformed");
+        let mut store = HistoryStore::open(path.clone(), RetentionPolicy::default()).unwrap();
+        store.add(transcript("kept", "default")).unwrap();
+        append_line_raw(&path, "{ torn");
+
+        let reopened = HistoryStore::open(path, RetentionPolicy::default()).unwrap();
+        assert_eq!(texts(&reopened.query(&HistoryQuery::default())), ["kept"]);
+    }
+
+    #[test]
+    fn query_filters_by_mode_and_every_term() {
+        let path = history_path("query");
+        let mut store = HistoryStore::open(path, RetentionPolicy::default()).unwrap();
+        store.add(transcript("Buy milk and eggs", "default")).unwrap();
+        store.add(transcript("Email the team about milk", "email")).unwrap();
+        store.add(transcript("Eggs for breakfast", "default")).unwrap();
+
+        let query = |search: &str, mode: Option<&str>| HistoryQuery {
+            search: Some(search.to_string()),
+            mode: mode.map(str::to_string),
+            ..Default::default()
+        };
+        assert_eq!(
+            texts(&store.query(&query("MILK", None))),
+            ["Email the team about milk", "Buy milk and eggs"]
+        );
+        assert_eq!(texts(&store.query(&query("milk eggs", None))), ["Buy milk and eggs"]);
+        assert_eq!(texts(&store.query(&query("milk", Some("email")))), ["Email the team about milk"]);
+    }
+
+    #[test]
+    fn query_pages_newest_first() {
+        let path = history_path("pages");
+        let mut store = HistoryStore::open(path, RetentionPolicy::default()).unwrap();
+        for i in 1..=5 {
+            store.add(transcript(&format!("entry {}", i), "default")).unwrap();
+        }
+
+        let page = store.query(&HistoryQuery {
+            offset: 1,
+            limit: Some(2),
+            ..Default::default()
+        });
+        assert_eq!(page.total, 5);
+        assert_eq!(texts(&page), ["entry 4", "entry 3"]);
+
+        let past_end = store.query(&HistoryQuery {
+            offset: 10,
+            ..Default::default()
+        });
+        assert_eq!(past_end.total, 5);
+        assert!(past_end.entries.is_empty());
+    }
+
+    #[test]
+    fn retention_drops_the_oldest_entries_from_the_file() {
+        let path = history_path("retention");
+        let mut store = HistoryStore::open(path.clone(), RetentionPolicy::default()).unwrap();
+        let day_ms = 24 * 60 * 60 * 1000;
+        store
+            .add(NewHistoryEntry {
+                created_at: Some(now_ms() - 10 * day_ms),
+                ..transcript("stale", "default")
+            })
+            .unwrap();
+        for text in ["a", "b", "c"] {
+            store.add(transcript(text, "default")).unwrap();
+        }
+
+        let removed = store
+            .set_retention(RetentionPolicy {
+                max_age_days: Some(7),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(removed, 1);
+
+        store
+            .set_retention(RetentionPolicy {
+                max_entries: Some(2),
+                ..Default::default()
+            })
+            .unwrap();
+        store.add(transcript("d", "default")).unwrap();
+
+        let reopened = HistoryStore::open(path, RetentionPolicy::default()).unwrap();
+        assert_eq!(texts(&reopened.query(&HistoryQuery::default())), ["d", "c"]);
+    }
+
+    #[test]
+    fn rewrites_persist_updates_and_deletions() {
+        let path = history_path("rewrite");
+        let mut store = HistoryStore::open(path.clone(), RetentionPolicy::default()).unwrap();
+        for text in ["one", "two", "three"] {
+            store.add(transcript(text, "default")).unwrap();
+        }
+        store.update(2, |e| e.cleaned_text = "Two.".to_string()).unwrap();
+        assert_eq!(store.delete(&[1, 99]).unwrap(), 1);
+        assert!(store.update(99, |_| {}).is_err());
+
+        let reopened = HistoryStore::open(path.clone(), RetentionPolicy::default()).unwrap();
+        assert_eq!(reopened.ids(), HashSet::from([2, 3]));
+        assert_eq!(reopened.get(2).unwrap().cleaned_text, "Two.");
+        assert!(!path.with_extension("jsonl.tmp").exists());
+    }
+
+    #[test]
+    fn reserved_ids_are_never_reused() {
+        let path = history_path("reserve");
+        let mut store = HistoryStore::empty(path, RetentionPolicy::default());
+        store.reserve_ids(41);
+        assert_eq!(store.add(transcript("new", "default")).unwrap().id, 42);
+        // A lower floor doesn't move ids backwards
+        store.reserve_ids(3);
+        assert_eq!(store.add(transcript("next", "default")).unwrap().id, 43);
+    }
+
+    #[test]
+    fn read_only_store_leaves_the_file_alone() {
+        let path = history_path("read-only");
+        fs::write(&path, "unreadable").unwrap();
+        let mut store = HistoryStore::read_only(path.clone(), RetentionPolicy::default());
+
+        assert!(store.add(transcript("new", "default")).is_err());
+        assert!(store.clear().is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "unreadable");
+    }
+}
@@ -0,0 +1,395 @@
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+use crate::error::{AppError, AppResult};
+use crate::keys;
+use crate::modes::TranscriptionMode;
+
+/// What a global shortcut does when pressed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum HotkeyAction {
+    /// Hold to record, release to transcribe. `mode` overrides the selected mode.
+    Record {
+        #[serde(default)]
+        mode: Option<String>,
+    },
+    /// Press to start recording, press again to stop
+    ToggleRecording {
+        #[serde(default)]
+        mode: Option<String>,
+    },
+    /// Discard the current recording and any cleanup in progress
+    Cancel,
+    /// Paste the most recent transcript again
+    RepasteLast,
+    /// Switch to the next transcription mode
+    CycleMode,
+}
+
+impl HotkeyAction {
+    fn mode(&self) -> Option<&str> {
+        match self {
+            Self::Record { mode } | Self::ToggleRecording { mode } => mode.as_deref(),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HotkeyBinding {
+    pub key: String,
+    #[serde(default)]
+    pub modifiers: Vec<String>,
+    pub action: HotkeyAction,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl HotkeyBinding {
+    pub fn record(key: &str, modifiers: &[&str]) -> Self {
+        Self {
+            key: key.to_string(),
+            modifiers: modifiers.iter().map(|m| m.to_string()).collect(),
+            action: HotkeyAction::Record { mode: None },
+            enabled: true,
+        }
+    }
+
+    pub fn validate(&self) -> AppResult<()> {
+        if keys::parse_code(&self.key).is_none() {
+            return Err(AppError::InvalidInput(format!("Unknown hotkey key: {}", self.key)));
+        }
+        // Anything `keys::parse_modifier` can register, aliases like "ctrl" and "cmd" included
+        if let Some(m) = self.modifiers.iter().find(|m| keys::parse_modifier(m).is_none()) {
+            return Err(AppError::InvalidInput(format!("Unknown hotkey modifier: {}", m)));
+        }
+        if let Some(mode) = self.action.mode() {
+            if TranscriptionMode::from_id(mode).is_none() {
+                return Err(AppError::InvalidInput(format!("Unknown mode: {}", mode)));
+            }
+        }
+        Ok(())
+    }
+
+    /// Order- and case-insensitive form of the key combination, used to spot duplicates
+    pub fn combo(&self) -> String {
+        // Aliases name the same keys: "ctrl" is "Control", "A" is "KeyA"
+        if let Ok(shortcut) = keys::shortcut_from_parts(&self.key, &self.modifiers) {
+            return keys::describe(&shortcut).accelerator.to_lowercase();
+        }
+        let mut modifiers: Vec<String> = self.modifiers.iter().map(|m| m.to_lowercase()).collect();
+        modifiers.sort();
+        modifiers.dedup();
+        // "A" and "KeyA" are the same key
+        let key = match keys::parse_code(&self.key) {
+            Some(code) => keys::code_name(code),
+            None => &self.key,
+        };
+        modifiers.push(key.to_lowercase());
+        modifiers.join("+")
+    }
+}
+
+pub fn default_bindings() -> Vec<HotkeyBinding> {
+    // Super+Shift+Space switches input sources on GNOME and Windows
+    let modifier = if cfg!(target_os = "macos") { "Meta" } else { "Control" };
+    vec![HotkeyBinding::record("Space", &[modifier, "Shift"])]
+}
+
+/// Two enabled bindings that use the same key combination
+#[derive(Debug, Clone, Serialize)]
+pub struct HotkeyConflict {
+    pub combo: String,
+    /// Indexes into the binding list, in order
+    pub bindings: Vec<usize>,
+}
+
+pub fn find_conflicts(bindings: &[HotkeyBinding]) -> Vec<HotkeyConflict> {
+    let mut conflicts: Vec<HotkeyConflict> = Vec::new();
+
+    for (i, binding) in bindings.iter().enumerate().filter(|(_, b)| b.enabled) {
+        let combo = binding.combo();
+        match conflicts.iter_mut().find(|c| c.combo == combo) {
+            Some(conflict) => conflict.bindings.push(i),
+            None => conflicts.push(HotkeyConflict { combo, bindings: vec![i] }),
+        }
+    }
+
+    conflicts.retain(|c| c.bindings.len() > 1);
+    conflicts
+}
+
+pub fn validate_bindings(bindings: &[HotkeyBinding]) -> AppResult<()> {
+    for binding in bindings {
+        binding.validate()?;
+    }
+    if let Some(conflict) = find_conflicts(bindings).first() {
+        return Err(AppError::Hotkey(format!(
+            "{} is bound to {} actions",
+            conflict.combo,
+            conflict.bindings.len()
+        )));
+    }
+    Ok(())
+}
+
+/// Result of registering one binding with the OS
+#[derive(Debug, Clone, Serialize)]
+pub struct HotkeyStatus {
+    pub index: usize,
+    pub registered: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordingStyle {
+    /// Record while the key is held
+    Hold,
+    /// Press once to start, again to stop
+    Toggle,
+}
+
+/// How `Record` hotkeys behave
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RecordingSettings {
+    pub style: RecordingStyle,
+    /// In hold style, a quick tap followed by a press keeps recording after release
+    pub double_tap_lock: bool,
+    /// Longest press that counts as a tap, and the window for the second tap
+    pub double_tap_ms: u64,
+    /// Recording stops on its own after this long, however it was started
+    pub max_duration_secs: u32,
+    /// Escape discards the recording. Escape is taken from other apps while recording.
+    pub escape_cancels: bool,
+}
+
+impl Default for RecordingSettings {
+    fn default() -> Self {
+        Self {
+            style: RecordingStyle::Hold,
+            double_tap_lock: true,
+            double_tap_ms: 300,
+            max_duration_secs: 600,
+            escape_cancels: true,
+        }
+    }
+}
+
+impl RecordingSettings {
+    pub fn validate(&self) -> AppResult<()> {
+        if !(100..=1000).contains(&self.double_tap_ms) {
+            return Err(AppError::InvalidInput("Double-tap window must be between 100 and 1000 ms".to_string()));
+        }
+        if self.max_duration_secs == 0 {
+            return Err(AppError::InvalidInput("Maximum recording duration must be at least one second".to_string()));
+        }
+        Ok(())
+    }
+}
+
+/// What a press or release of a `Record` hotkey means
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Gesture {
+    Start,
+    Stop,
+    /// Second tap of a double tap: keep recording until the next press
+    Lock,
+    /// A quick tap ended; stop unless a second tap arrives within the window
+    StopAfter(Duration),
+    Ignore,
+}
+
+/// Turns raw key presses and releases into recording gestures. Releases are
+/// matched against the tracker's own presses rather than whether audio is
+/// recording yet, since a quick tap can be released before the start lands.
+#[derive(Debug, Default)]
+pub struct GestureTracker {
+    /// Set while the key that started a recording is held
+    pressed_at: Option<Instant>,
+    /// Release time of a quick tap that may become a double tap
+    pending_tap: Option<Instant>,
+    locked: bool,
+    /// Bumped whenever a pending delayed stop becomes stale
+    generation: u64,
+}
+
+impl GestureTracker {
+    pub fn press(&mut self, now: Instant, recording: bool, settings: &RecordingSettings) -> Gesture {
+        if settings.style == RecordingStyle::Toggle {
+            return if recording {
+                self.reset();
+                Gesture::Stop
+            } else {
+                Gesture::Start
+            };
+        }
+
+        if self.locked {
+            self.reset();
+            return Gesture::Stop;
+        }
+
+        let window = Duration::from_millis(settings.double_tap_ms);
+        // The recording this tap started may still be starting
+        if let Some(tap) = self.pending_tap.take() {
+            if now.duration_since(tap) <= window {
+                self.locked = true;
+                self.generation += 1;
+                return Gesture::Lock;
+            }
+        }
+
+        // Already recording from the window or another shortcut
+        if recording {
+            return Gesture::Ignore;
+        }
+        self.pressed_at = Some(now);
+        Gesture::Start
+    }
+
+    pub fn release(&mut self, now: Instant, settings: &RecordingSettings) -> Gesture {
+        if settings.style == RecordingStyle::Toggle || self.locked {
+            return Gesture::Ignore;
+        }
+        // The press didn't start anything, e.g. a recording was already running
+        let Some(pressed_at) = self.pressed_at.take() else {
+            return Gesture::Ignore;
+        };
+
+        let window = Duration::from_millis(settings.double_tap_ms);
+        if settings.double_tap_lock && now.duration_since(pressed_at) <= window {
+            self.pending_tap = Some(now);
+            self.generation += 1;
+            return Gesture::StopAfter(window);
+        }
+        Gesture::Stop
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// A delayed stop fired: returns true if no second tap arrived in the meantime
+    pub fn confirm_stop(&mut self, generation: u64) -> bool {
+        if self.generation == generation && self.pending_tap.is_some() {
+            self.pending_tap = None;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Forget any gesture in progress, e.g. when recording stops some other way
+    pub fn reset(&mut self) {
+        self.pressed_at = None;
+        self.pending_tap = None;
+        self.locked = false;
+        self.generation += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn modifiers_validate_in_any_case() {
+        assert!(HotkeyBinding::record("Space", &["control", "SHIFT"]).validate().is_ok());
+        assert!(HotkeyBinding::record("k", &["meta", "Alt"]).validate().is_ok());
+        assert!(HotkeyBinding::record("k", &["ctrl", "Cmd", "option"]).validate().is_ok());
+        assert!(HotkeyBinding::record("Space", &["Hyper"]).validate().is_err());
+        assert!(HotkeyBinding::record("Nope", &["Control"]).validate().is_err());
+    }
+
+    fn hold(double_tap_lock: bool) -> RecordingSettings {
+        RecordingSettings {
+            style: RecordingStyle::Hold,
+            double_tap_lock,
+            double_tap_ms: 300,
+            ..RecordingSettings::default()
+        }
+    }
+
+    #[test]
+    fn release_before_the_recording_starts_still_stops_it() {
+        let settings = hold(false);
+        let mut tracker = GestureTracker::default();
+        let start = Instant::now();
+        assert_eq!(tracker.press(start, false, &settings), Gesture::Start);
+        // Released before the frontend has started recording
+        assert_eq!(tracker.release(start + Duration::from_millis(20), &settings), Gesture::Stop);
+    }
+
+    #[test]
+    fn quick_tap_before_the_start_lands_waits_for_a_double_tap() {
+        let settings = hold(true);
+        let mut tracker = GestureTracker::default();
+        let start = Instant::now();
+        assert_eq!(tracker.press(start, false, &settings), Gesture::Start);
+        let released = start + Duration::from_millis(30);
+        assert_eq!(
+            tracker.release(released, &settings),
+            Gesture::StopAfter(Duration::from_millis(300))
+        );
+        let generation = tracker.generation();
+
+        // The second tap locks even if the recording is still starting
+        assert_eq!(tracker.press(released + Duration::from_millis(100), false, &settings), Gesture::Lock);
+        assert!(!tracker.confirm_stop(generation));
+        assert_eq!(tracker.release(released + Duration::from_millis(150), &settings), Gesture::Ignore);
+        assert_eq!(tracker.press(released + Duration::from_secs(5), true, &settings), Gesture::Stop);
+    }
+
+    #[test]
+    fn single_quick_tap_stops_after_the_window() {
+        let settings = hold(true);
+        let mut tracker = GestureTracker::default();
+        let start = Instant::now();
+        tracker.press(start, false, &settings);
+        tracker.release(start + Duration::from_millis(30), &settings);
+        assert!(tracker.confirm_stop(tracker.generation()));
+    }
+
+    #[test]
+    fn long_hold_stops_on_release() {
+        let settings = hold(true);
+        let mut tracker = GestureTracker::default();
+        let start = Instant::now();
+        tracker.press(start, false, &settings);
+        assert_eq!(tracker.release(start + Duration::from_secs(2), &settings), Gesture::Stop);
+    }
+
+    #[test]
+    fn keys_that_started_nothing_release_nothing() {
+        let settings = hold(true);
+        let mut tracker = GestureTracker::default();
+        let now = Instant::now();
+        // Recording was started from the window
+        assert_eq!(tracker.press(now, true, &settings), Gesture::Ignore);
+        assert_eq!(tracker.release(now + Duration::from_millis(50), &settings), Gesture::Ignore);
+        assert_eq!(tracker.release(now + Duration::from_millis(60), &settings), Gesture::Ignore);
+    }
+
+    #[test]
+    fn combos_ignore_case_and_order() {
+        let a = HotkeyBinding::record("Space", &["Control", "Shift"]);
+        let b = HotkeyBinding::record("space", &["shift", "control"]);
+        assert_eq!(a.combo(), b.combo());
+        assert_eq!(find_conflicts(&[a, b]).len(), 1);
+    }
+
+    #[test]
+    fn combos_resolve_modifier_aliases() {
+        let a = HotkeyBinding::record("Space", &["Control", "Meta"]);
+        let b = HotkeyBinding::record("Space", &["ctrl", "cmd"]);
+        assert_eq!(a.combo(), b.combo());
+        assert_eq!(find_conflicts(&[a, b]).len(), 1);
+    }
+}
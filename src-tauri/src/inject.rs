@@ -0,0 +1,522 @@
+use serde::{Deserialize, Serialize};
+
+use crate::error::AppResult;
+
+/// How a finished transcript gets into the focused app
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InjectionMethod {
+    /// Put the text on the clipboard and press the paste shortcut
+    #[default]
+    Paste,
+    /// Send the text as keystrokes. Slower, but works where pasting is blocked.
+    Type,
+}
+
+/// Sends keyboard input to whichever window has focus
+pub trait TextInjector {
+    /// Backend name, for logs
+    fn name(&self) -> &'static str;
+
+    /// Press the platform paste shortcut
+    fn paste(&mut self) -> AppResult<()>;
+
+    /// Type `text` as keystrokes. Newlines are sent as Return.
+    fn type_text(&mut self, text: &str) -> AppResult<()>;
+}
+
+/// Insert `text` into the focused app. With `Paste` the caller must already have
+/// put `text` on the clipboard.
+pub fn insert(injector: &mut dyn TextInjector, text: &str, method: InjectionMethod) -> AppResult<()> {
+    match method {
+        InjectionMethod::Paste => injector.paste(),
+        InjectionMethod::Type => injector.type_text(text),
+    }
+}
+
+/// The injector for the current platform and display server
+pub fn system_injector() -> AppResult<Box<dyn TextInjector>> {
+    #[cfg(target_os = "macos")]
+    {
+        Ok(Box::new(macos::MacInjector))
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        Ok(Box::new(windows::WindowsInjector))
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        use crate::error::AppError;
+        use crate::platform::{self, DisplayServer};
+
+        match platform::display_server() {
+            DisplayServer::Wayland => Ok(Box::new(wayland::WaylandInjector)),
+            DisplayServer::X11 => Ok(Box::new(x11::X11Injector::new()?)),
+            _ => Err(AppError::Paste("No X11 or Wayland display to send input to".to_string())),
+        }
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+    {
+        Err(crate::error::AppError::Paste("Inserting text is not supported on this platform".to_string()))
+    }
+}
+
+/// One key press needed to type some text
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Keystroke {
+    Char(char),
+    /// A line break. Apps treat a typed newline character differently from Enter.
+    Return,
+}
+
+/// The key presses that type `text`. `\r\n`, `\n` and a lone `\r` are each one Return.
+pub fn keystrokes(text: &str) -> Vec<Keystroke> {
+    let mut keys = Vec::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\r' => {
+                chars.next_if_eq(&'\n');
+                keys.push(Keystroke::Return);
+            }
+            '\n' => keys.push(Keystroke::Return),
+            c => keys.push(Keystroke::Char(c)),
+        }
+    }
+    keys
+}
+
+#[cfg(target_os = "macos")]
+mod macos {
+    use std::process::Command;
+
+    use super::TextInjector;
+    use crate::error::{AppError, AppResult};
+
+    /// Drives System Events through AppleScript; needs accessibility access
+    pub struct MacInjector;
+
+    impl TextInjector for MacInjector {
+        fn name(&self) -> &'static str {
+            "macos"
+        }
+
+        fn paste(&mut self) -> AppResult<()> {
+            osascript("tell application \"System Events\" to keystroke \"v\" using command down")
+        }
+
+        fn type_text(&mut self, text: &str) -> AppResult<()> {
+            // `keystroke` can't send a newline, so press Return (key code 36) between lines
+            let lines: Vec<String> = text
+                .split('\n')
+                .map(|line| format!("keystroke \"{}\"", line.replace('\\', "\\\\").replace('"', "\\\"")))
+                .collect();
+            osascript(&format!(
+                "tell application \"System Events\"\n{}\nend tell",
+                lines.join("\nkey code 36\n")
+            ))
+        }
+    }
+
+    fn osascript(script: &str) -> AppResult<()> {
+        let output = Command::new("osascript")
+            .arg("-e")
+            .arg(script)
+            .output()
+            .map_err(|e| AppError::Paste(format!("Failed to run osascript: {}", e)))?;
+        if !output.status.success() {
+            return Err(AppError::Paste(format!(
+                "System Events refused the keystrokes: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod windows {
+    use windows_sys::Win32::UI::Input::KeyboardAndMouse::{
+        SendInput, INPUT, INPUT_0, INPUT_KEYBOARD, KEYBDINPUT, KEYBD_EVENT_FLAGS, KEYEVENTF_KEYUP, KEYEVENTF_UNICODE,
+        VIRTUAL_KEY, VK_CONTROL, VK_RETURN, VK_V,
+    };
+
+    use super::{keystrokes, Keystroke, TextInjector};
+    use crate::error::{AppError, AppResult};
+
+    /// Synthesizes keyboard events with `SendInput`
+    pub struct WindowsInjector;
+
+    impl TextInjector for WindowsInjector {
+        fn name(&self) -> &'static str {
+            "windows"
+        }
+
+        fn paste(&mut self) -> AppResult<()> {
+            send(&[
+                key(VK_CONTROL, 0, 0),
+                key(VK_V, 0, 0),
+                key(VK_V, 0, KEYEVENTF_KEYUP),
+                key(VK_CONTROL, 0, KEYEVENTF_KEYUP),
+            ])
+        }
+
+        fn type_text(&mut self, text: &str) -> AppResult<()> {
+            let mut inputs = Vec::new();
+            for keystroke in keystrokes(text) {
+                match keystroke {
+                    Keystroke::Return => {
+                        inputs.push(key(VK_RETURN, 0, 0));
+                        inputs.push(key(VK_RETURN, 0, KEYEVENTF_KEYUP));
+                    }
+                    Keystroke::Char(c) => {
+                        let mut units = [0u16; 2];
+                        for &unit in c.encode_utf16(&mut units).iter() {
+                            inputs.push(key(0, unit, KEYEVENTF_UNICODE));
+                            inputs.push(key(0, unit, KEYEVENTF_UNICODE | KEYEVENTF_KEYUP));
+                        }
+                    }
+                }
+            }
+            send(&inputs)
+        }
+    }
+
+    fn key(vk: VIRTUAL_KEY, scan: u16, flags: KEYBD_EVENT_FLAGS) -> INPUT {
+        INPUT {
+            r#type: INPUT_KEYBOARD,
+            Anonymous: INPUT_0 {
+                ki: KEYBDINPUT {
+                    wVk: vk,
+                    wScan: scan,
+                    dwFlags: flags,
+                    time: 0,
+                    dwExtraInfo: 0,
+                },
+            },
+        }
+    }
+
+    fn send(inputs: &[INPUT]) -> AppResult<()> {
+        if inputs.is_empty() {
+            return Ok(());
+        }
+        let sent = unsafe { SendInput(inputs.len() as u32, inputs.as_ptr(), std::mem::size_of::<INPUT>() as i32) };
+        if sent as usize != inputs.len() {
+            // SendInput is blocked when the focused window runs elevated
+            return Err(AppError::Paste(format!(
+                "Only {} of {} key events were delivered",
+                sent,
+                inputs.len()
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod x11 {
+    use std::os::raw::{c_int, c_uint, c_ulong};
+    use std::time::Duration;
+    use x11_dl::{keysym, xlib, xtest};
+
+    use super::{keystrokes, Keystroke, TextInjector};
+    use crate::error::{AppError, AppResult};
+
+    /// How long a borrowed keycode keeps its symbol after the key event is sent.
+    /// The focused client looks the keysym up when it handles the event, which can
+    /// be after the server has processed the restore.
+    const REMAP_SETTLE: Duration = Duration::from_millis(25);
+
+    /// Fakes key events with the XTest extension. The libraries are loaded at
+    /// runtime so the app still starts on systems without X11.
+    pub struct X11Injector {
+        xlib: xlib::Xlib,
+        xtest: xtest::Xf86vmode,
+        display: *mut xlib::Display,
+    }
+
+    impl X11Injector {
+        pub fn new() -> AppResult<Self> {
+            let xlib = xlib::Xlib::open().map_err(|e| AppError::Paste(format!("libX11 is not available: {}", e)))?;
+            let xtest = xtest::Xf86vmode::open().map_err(|e| AppError::Paste(format!("libXtst is not available: {}", e)))?;
+            let display = unsafe { (xlib.XOpenDisplay)(std::ptr::null()) };
+            if display.is_null() {
+                return Err(AppError::Paste("Failed to open the X display".to_string()));
+            }
+            Ok(Self { xlib, xtest, display })
+        }
+
+        fn keycode(&self, keysym: c_ulong) -> u8 {
+            unsafe { (self.xlib.XKeysymToKeycode)(self.display, keysym) }
+        }
+
+        fn key(&self, keycode: u8, pressed: bool) {
+            unsafe {
+                (self.xtest.XTestFakeKeyEvent)(self.display, keycode as c_uint, pressed as c_int, 0);
+            }
+        }
+
+        fn sync(&self) {
+            unsafe {
+                (self.xlib.XSync)(self.display, xlib::False);
+            }
+        }
+
+        /// The modifier keys that select `keysym` on `keycode`, from the column it sits
+        /// in of the core keyboard map. `None` if it is only in another layout group or
+        /// a modifier it needs has no key.
+        fn level_modifiers(&self, keycode: u8, keysym: c_ulong) -> Option<Vec<u8>> {
+            let mut per_keycode = 0;
+            let column = unsafe {
+                let map = (self.xlib.XGetKeyboardMapping)(self.display, keycode, 1, &mut per_keycode);
+                if map.is_null() {
+                    return None;
+                }
+                let syms = std::slice::from_raw_parts(map, per_keycode.max(0) as usize);
+                let column = syms.iter().position(|&sym| sym == keysym);
+                (self.xlib.XFree)(map.cast());
+                column?
+            };
+
+            let modifiers: &[c_uint] = match column {
+                0 => &[],
+                1 => &[keysym::XK_Shift_L],
+                // Columns 2 and 3 are the second group; 4 and 5 are AltGr and AltGr+Shift
+                4 => &[keysym::XK_ISO_Level3_Shift],
+                5 => &[keysym::XK_ISO_Level3_Shift, keysym::XK_Shift_L],
+                _ => return None,
+            };
+            modifiers
+                .iter()
+                .map(|&sym| Some(self.keycode(sym as c_ulong)).filter(|&code| code != 0))
+                .collect()
+        }
+
+        fn type_keysym(&self, keysym: c_ulong) -> AppResult<()> {
+            let keycode = self.keycode(keysym);
+            let modifiers = match keycode {
+                0 => None,
+                _ => self.level_modifiers(keycode, keysym),
+            };
+            if let Some(modifiers) = modifiers {
+                for &modifier in &modifiers {
+                    self.key(modifier, true);
+                }
+                self.key(keycode, true);
+                self.key(keycode, false);
+                for &modifier in modifiers.iter().rev() {
+                    self.key(modifier, false);
+                }
+                return Ok(());
+            }
+
+            // Not reachable on the current layout: borrow an unused keycode, the way xdotool does
+            let scratch = self
+                .scratch_keycode()
+                .ok_or_else(|| AppError::Paste("No free keycode to type an unmapped character".to_string()))?;
+            let mut syms = [keysym, keysym];
+            unsafe {
+                (self.xlib.XChangeKeyboardMapping)(self.display, scratch as c_int, 2, syms.as_mut_ptr(), 1);
+            }
+            self.sync();
+            self.key(scratch, true);
+            self.key(scratch, false);
+            self.sync();
+            std::thread::sleep(REMAP_SETTLE);
+            let mut none = [0, 0];
+            unsafe {
+                (self.xlib.XChangeKeyboardMapping)(self.display, scratch as c_int, 2, none.as_mut_ptr(), 1);
+            }
+            self.sync();
+            Ok(())
+        }
+
+        /// Highest keycode with no symbols bound to it
+        fn scratch_keycode(&self) -> Option<u8> {
+            let (mut min, mut max) = (0, 0);
+            let mut per_keycode = 0;
+            unsafe {
+                (self.xlib.XDisplayKeycodes)(self.display, &mut min, &mut max);
+                let map = (self.xlib.XGetKeyboardMapping)(self.display, min as u8, max - min + 1, &mut per_keycode);
+                if map.is_null() {
+                    return None;
+                }
+                let per = per_keycode.max(0) as usize;
+                let mapping = std::slice::from_raw_parts(map, (max - min + 1) as usize * per);
+                let free = (min..=max)
+                    .rev()
+                    .find(|&code| mapping[(code - min) as usize * per..][..per].iter().all(|&sym| sym == 0));
+                (self.xlib.XFree)(map.cast());
+                free.map(|code| code as u8)
+            }
+        }
+    }
+
+    impl TextInjector for X11Injector {
+        fn name(&self) -> &'static str {
+            "x11"
+        }
+
+        fn paste(&mut self) -> AppResult<()> {
+            let control = self.keycode(keysym::XK_Control_L as c_ulong);
+            let v = self.keycode(keysym::XK_v as c_ulong);
+            if control == 0 || v == 0 {
+                return Err(AppError::Paste("The keyboard map has no Control or V key".to_string()));
+            }
+            self.key(control, true);
+            self.key(v, true);
+            self.key(v, false);
+            self.key(control, false);
+            self.sync();
+            Ok(())
+        }
+
+        fn type_text(&mut self, text: &str) -> AppResult<()> {
+            for keystroke in keystrokes(text) {
+                self.type_keysym(keystroke_keysym(keystroke))?;
+            }
+            self.sync();
+            Ok(())
+        }
+    }
+
+    impl Drop for X11Injector {
+        fn drop(&mut self) {
+            unsafe {
+                (self.xlib.XCloseDisplay)(self.display);
+            }
+        }
+    }
+
+    fn keystroke_keysym(keystroke: Keystroke) -> c_ulong {
+        match keystroke {
+            Keystroke::Return => keysym::XK_Return as c_ulong,
+            Keystroke::Char('\t') => keysym::XK_Tab as c_ulong,
+            // Printable Latin-1 keysyms equal their code points
+            Keystroke::Char(c @ (' '..='~' | '\u{a0}'..='\u{ff}')) => c as c_ulong,
+            Keystroke::Char(c) => 0x0100_0000 | c as c_ulong,
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod wayland {
+    use std::io::ErrorKind;
+    use std::process::Command;
+
+    use super::TextInjector;
+    use crate::error::{AppError, AppResult};
+
+    /// Wayland has no global input injection API; `wtype` speaks the virtual-keyboard
+    /// protocol, which wlroots-based compositors and KDE support
+    pub struct WaylandInjector;
+
+    impl TextInjector for WaylandInjector {
+        fn name(&self) -> &'static str {
+            "wayland"
+        }
+
+        fn paste(&mut self) -> AppResult<()> {
+            wtype(&["-M", "ctrl", "-k", "v", "-m", "ctrl"])
+        }
+
+        fn type_text(&mut self, text: &str) -> AppResult<()> {
+            wtype(&["--", text])
+        }
+    }
+
+    fn wtype(args: &[&str]) -> AppResult<()> {
+        let output = Command::new("wtype").args(args).output().map_err(|e| match e.kind() {
+            ErrorKind::NotFound => AppError::Paste("wtype is not installed; install it to insert text on Wayland".to_string()),
+            _ => AppError::Paste(format!("Failed to run wtype: {}", e)),
+        })?;
+        if !output.status.success() {
+            return Err(AppError::Paste(format!(
+                "wtype failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// One thing a `RecordingInjector` was asked to send
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    enum Injected {
+        Paste,
+        Key(Keystroke),
+    }
+
+    /// Remembers the input it was asked to send instead of sending it
+    #[derive(Debug, Default)]
+    struct RecordingInjector {
+        events: Vec<Injected>,
+    }
+
+    impl TextInjector for RecordingInjector {
+        fn name(&self) -> &'static str {
+            "recording"
+        }
+
+        fn paste(&mut self) -> AppResult<()> {
+            self.events.push(Injected::Paste);
+            Ok(())
+        }
+
+        fn type_text(&mut self, text: &str) -> AppResult<()> {
+            self.events.extend(keystrokes(text).into_iter().map(Injected::Key));
+            Ok(())
+        }
+    }
+
+    fn typed(text: &str) -> Vec<Injected> {
+        let mut injector = RecordingInjector::default();
+        insert(&mut injector, text, InjectionMethod::Type).unwrap();
+        injector.events
+    }
+
+    fn chars(text: &str) -> Vec<Injected> {
+        text.chars().map(|c| Injected::Key(Keystroke::Char(c))).collect()
+    }
+
+    #[test]
+    fn paste_presses_the_shortcut_only() {
+        let mut injector = RecordingInjector::default();
+        insert(&mut injector, "hello world", InjectionMethod::Paste).unwrap();
+        assert_eq!(injector.events, vec![Injected::Paste]);
+    }
+
+    #[test]
+    fn types_plain_text_a_character_at_a_time() {
+        assert_eq!(typed("Hi, there!"), chars("Hi, there!"));
+        assert_eq!(typed(""), vec![]);
+    }
+
+    #[test]
+    fn types_newlines_as_return() {
+        let mut expected = chars("one");
+        expected.push(Injected::Key(Keystroke::Return));
+        expected.extend(chars("two"));
+        expected.push(Injected::Key(Keystroke::Return));
+        expected.push(Injected::Key(Keystroke::Return));
+        expected.extend(chars("\tthree"));
+        expected.push(Injected::Key(Keystroke::Return));
+        assert_eq!(typed("one\ntwo\r\n\r\tthree\n"), expected);
+    }
+
+    #[test]
+    fn types_non_ascii_characters_whole() {
+        assert_eq!(typed("café über"), chars("café über"));
+        assert_eq!(typed("日本語"), chars("日本語"));
+        // Outside the BMP, and a base letter with a combining accent
+        assert_eq!(typed("🎤 e\u{301}"), chars("🎤 e\u{301}"));
+    }
+}
@@ -0,0 +1,253 @@
+use serde::Serialize;
+use tauri_plugin_global_shortcut::{Code, Modifiers, Shortcut};
+
+use crate::error::{AppError, AppResult};
+
+/// Every physical key a shortcut can use, named as in the W3C `KeyboardEvent.code`
+/// spec (the same names the frontend gets from `event.code`)
+const KEYS: &[(Code, &str)] = &[
+    (Code::Backquote, "Backquote"), (Code::Backslash, "Backslash"), (Code::BracketLeft, "BracketLeft"), (Code::BracketRight, "BracketRight"),
+    (Code::Comma, "Comma"), (Code::Digit0, "Digit0"), (Code::Digit1, "Digit1"), (Code::Digit2, "Digit2"),
+    (Code::Digit3, "Digit3"), (Code::Digit4, "Digit4"), (Code::Digit5, "Digit5"), (Code::Digit6, "Digit6"),
+    (Code::Digit7, "Digit7"), (Code::Digit8, "Digit8"), (Code::Digit9, "Digit9"), (Code::Equal, "Equal"),
+    (Code::IntlBackslash, "IntlBackslash"), (Code::IntlRo, "IntlRo"), (Code::IntlYen, "IntlYen"), (Code::KeyA, "KeyA"),
+    (Code::KeyB, "KeyB"), (Code::KeyC, "KeyC"), (Code::KeyD, "KeyD"), (Code::KeyE, "KeyE"),
+    (Code::KeyF, "KeyF"), (Code::KeyG, "KeyG"), (Code::KeyH, "KeyH"), (Code::KeyI, "KeyI"),
+    (Code::KeyJ, "KeyJ"), (Code::KeyK, "KeyK"), (Code::KeyL, "KeyL"), (Code::KeyM, "KeyM"),
+    (Code::KeyN, "KeyN"), (Code::KeyO, "KeyO"), (Code::KeyP, "KeyP"), (Code::KeyQ, "KeyQ"),
+    (Code::KeyR, "KeyR"), (Code::KeyS, "KeyS"), (Code::KeyT, "KeyT"), (Code::KeyU, "KeyU"),
+    (Code::KeyV, "KeyV"), (Code::KeyW, "KeyW"), (Code::KeyX, "KeyX"), (Code::KeyY, "KeyY"),
+    (Code::KeyZ, "KeyZ"), (Code::Minus, "Minus"), (Code::Period, "Period"), (Code::Quote, "Quote"),
+    (Code::Semicolon, "Semicolon"), (Code::Slash, "Slash"), (Code::AltLeft, "AltLeft"), (Code::AltRight, "AltRight"),
+    (Code::Backspace, "Backspace"), (Code::CapsLock, "CapsLock"), (Code::ContextMenu, "ContextMenu"), (Code::ControlLeft, "ControlLeft"),
+    (Code::ControlRight, "ControlRight"), (Code::Enter, "Enter"), (Code::MetaLeft, "MetaLeft"), (Code::MetaRight, "MetaRight"),
+    (Code::ShiftLeft, "ShiftLeft"), (Code::ShiftRight, "ShiftRight"), (Code::Space, "Space"), (Code::Tab, "Tab"),
+    (Code::Convert, "Convert"), (Code::KanaMode, "KanaMode"), (Code::Lang1, "Lang1"), (Code::Lang2, "Lang2"),
+    (Code::Lang3, "Lang3"), (Code::Lang4, "Lang4"), (Code::Lang5, "Lang5"), (Code::NonConvert, "NonConvert"),
+    (Code::Delete, "Delete"), (Code::End, "End"), (Code::Help, "Help"), (Code::Home, "Home"),
+    (Code::Insert, "Insert"), (Code::PageDown, "PageDown"), (Code::PageUp, "PageUp"), (Code::ArrowDown, "ArrowDown"),
+    (Code::ArrowLeft, "ArrowLeft"), (Code::ArrowRight, "ArrowRight"), (Code::ArrowUp, "ArrowUp"), (Code::NumLock, "NumLock"),
+    (Code::Numpad0, "Numpad0"), (Code::Numpad1, "Numpad1"), (Code::Numpad2, "Numpad2"), (Code::Numpad3, "Numpad3"),
+    (Code::Numpad4, "Numpad4"), (Code::Numpad5, "Numpad5"), (Code::Numpad6, "Numpad6"), (Code::Numpad7, "Numpad7"),
+    (Code::Numpad8, "Numpad8"), (Code::Numpad9, "Numpad9"), (Code::NumpadAdd, "NumpadAdd"), (Code::NumpadBackspace, "NumpadBackspace"),
+    (Code::NumpadClear, "NumpadClear"), (Code::NumpadClearEntry, "NumpadClearEntry"), (Code::NumpadComma, "NumpadComma"), (Code::NumpadDecimal, "NumpadDecimal"),
+    (Code::NumpadDivide, "NumpadDivide"), (Code::NumpadEnter, "NumpadEnter"), (Code::NumpadEqual, "NumpadEqual"), (Code::NumpadHash, "NumpadHash"),
+    (Code::NumpadMemoryAdd, "NumpadMemoryAdd"), (Code::NumpadMemoryClear, "NumpadMemoryClear"), (Code::NumpadMemoryRecall, "NumpadMemoryRecall"), (Code::NumpadMemoryStore, "NumpadMemoryStore"),
+    (Code::NumpadMemorySubtract, "NumpadMemorySubtract"), (Code::NumpadMultiply, "NumpadMultiply"), (Code::NumpadParenLeft, "NumpadParenLeft"), (Code::NumpadParenRight, "NumpadParenRight"),
+    (Code::NumpadStar, "NumpadStar"), (Code::NumpadSubtract, "NumpadSubtract"), (Code::Escape, "Escape"), (Code::Fn, "Fn"),
+    (Code::FnLock, "FnLock"), (Code::PrintScreen, "PrintScreen"), (Code::ScrollLock, "ScrollLock"), (Code::Pause, "Pause"),
+    (Code::BrowserBack, "BrowserBack"), (Code::BrowserFavorites, "BrowserFavorites"), (Code::BrowserForward, "BrowserForward"), (Code::BrowserHome, "BrowserHome"),
+    (Code::BrowserRefresh, "BrowserRefresh"), (Code::BrowserSearch, "BrowserSearch"), (Code::BrowserStop, "BrowserStop"), (Code::Eject, "Eject"),
+    (Code::LaunchApp1, "LaunchApp1"), (Code::LaunchApp2, "LaunchApp2"), (Code::LaunchMail, "LaunchMail"), (Code::MediaPlayPause, "MediaPlayPause"),
+    (Code::MediaSelect, "MediaSelect"), (Code::MediaStop, "MediaStop"), (Code::MediaTrackNext, "MediaTrackNext"), (Code::MediaTrackPrevious, "MediaTrackPrevious"),
+    (Code::Power, "Power"), (Code::Sleep, "Sleep"), (Code::AudioVolumeDown, "AudioVolumeDown"), (Code::AudioVolumeMute, "AudioVolumeMute"),
+    (Code::AudioVolumeUp, "AudioVolumeUp"), (Code::WakeUp, "WakeUp"), (Code::Hyper, "Hyper"), (Code::Super, "Super"),
+    (Code::Turbo, "Turbo"), (Code::Abort, "Abort"), (Code::Resume, "Resume"), (Code::Suspend, "Suspend"),
+    (Code::Again, "Again"), (Code::Copy, "Copy"), (Code::Cut, "Cut"), (Code::Find, "Find"),
+    (Code::Open, "Open"), (Code::Paste, "Paste"), (Code::Props, "Props"), (Code::Select, "Select"),
+    (Code::Undo, "Undo"), (Code::Hiragana, "Hiragana"), (Code::Katakana, "Katakana"), (Code::F1, "F1"),
+    (Code::F2, "F2"), (Code::F3, "F3"), (Code::F4, "F4"), (Code::F5, "F5"),
+    (Code::F6, "F6"), (Code::F7, "F7"), (Code::F8, "F8"), (Code::F9, "F9"),
+    (Code::F10, "F10"), (Code::F11, "F11"), (Code::F12, "F12"), (Code::F13, "F13"),
+    (Code::F14, "F14"), (Code::F15, "F15"), (Code::F16, "F16"), (Code::F17, "F17"),
+    (Code::F18, "F18"), (Code::F19, "F19"), (Code::F20, "F20"), (Code::F21, "F21"),
+    (Code::F22, "F22"), (Code::F23, "F23"), (Code::F24, "F24"), (Code::F25, "F25"),
+    (Code::F26, "F26"), (Code::F27, "F27"), (Code::F28, "F28"), (Code::F29, "F29"),
+    (Code::F30, "F30"), (Code::F31, "F31"), (Code::F32, "F32"), (Code::F33, "F33"),
+    (Code::F34, "F34"), (Code::F35, "F35"), (Code::BrightnessDown, "BrightnessDown"), (Code::BrightnessUp, "BrightnessUp"),
+    (Code::DisplayToggleIntExt, "DisplayToggleIntExt"), (Code::KeyboardLayoutSelect, "KeyboardLayoutSelect"), (Code::LaunchAssistant, "LaunchAssistant"), (Code::LaunchControlPanel, "LaunchControlPanel"),
+    (Code::LaunchScreenSaver, "LaunchScreenSaver"), (Code::MailForward, "MailForward"), (Code::MailReply, "MailReply"), (Code::MailSend, "MailSend"),
+    (Code::MediaFastForward, "MediaFastForward"), (Code::MediaPause, "MediaPause"), (Code::MediaPlay, "MediaPlay"), (Code::MediaRecord, "MediaRecord"),
+    (Code::MediaRewind, "MediaRewind"), (Code::MicrophoneMuteToggle, "MicrophoneMuteToggle"), (Code::PrivacyScreenToggle, "PrivacyScreenToggle"), (Code::SelectTask, "SelectTask"),
+    (Code::ShowAllWindows, "ShowAllWindows"), (Code::ZoomToggle, "ZoomToggle"),
+];
+
+/// Friendlier names accepted when parsing, on top of the names in `KEYS`
+const ALIASES: &[(&str, Code)] = &[
+    ("esc", Code::Escape), ("return", Code::Enter), ("del", Code::Delete), ("ins", Code::Insert),
+    ("up", Code::ArrowUp), ("down", Code::ArrowDown), ("left", Code::ArrowLeft), ("right", Code::ArrowRight),
+    ("pgup", Code::PageUp), ("pgdn", Code::PageDown), ("pagedown", Code::PageDown), ("pageup", Code::PageUp),
+    ("capslock", Code::CapsLock), ("numlock", Code::NumLock), ("scrolllock", Code::ScrollLock),
+    ("printscreen", Code::PrintScreen), ("prtsc", Code::PrintScreen), ("pausebreak", Code::Pause),
+    ("menu", Code::ContextMenu), ("volumeup", Code::AudioVolumeUp), ("volumedown", Code::AudioVolumeDown),
+    ("volumemute", Code::AudioVolumeMute), ("mute", Code::AudioVolumeMute), ("playpause", Code::MediaPlayPause),
+    ("nexttrack", Code::MediaTrackNext), ("prevtrack", Code::MediaTrackPrevious), ("previoustrack", Code::MediaTrackPrevious),
+    ("numpadplus", Code::NumpadAdd), ("numpadminus", Code::NumpadSubtract), ("numpadmultiply", Code::NumpadMultiply),
+    ("rightcommand", Code::MetaRight), ("leftcommand", Code::MetaLeft), ("rightoption", Code::AltRight), ("leftoption", Code::AltLeft),
+    ("`", Code::Backquote), ("\\", Code::Backslash), ("[", Code::BracketLeft), ("]", Code::BracketRight),
+    (",", Code::Comma), ("=", Code::Equal), ("-", Code::Minus), (".", Code::Period),
+    ("'", Code::Quote), (";", Code::Semicolon), ("/", Code::Slash),
+];
+
+/// Modifier names in the order they are written, using the names `HotkeyBinding` stores
+const MODIFIERS: &[(Modifiers, &str)] = &[
+    (Modifiers::CONTROL, "Control"),
+    (Modifiers::ALT, "Alt"),
+    (Modifiers::SHIFT, "Shift"),
+    (Modifiers::SUPER, "Meta"),
+];
+
+/// A shortcut split into the key and modifier names the settings store
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Accelerator {
+    pub key: String,
+    pub modifiers: Vec<String>,
+    /// Canonical string form, e.g. "Control+Shift+Space"
+    pub accelerator: String,
+}
+
+/// Look up a key by its `KEYS` name, an alias, or a single letter or digit.
+/// Matching is case-insensitive.
+pub fn parse_code(name: &str) -> Option<Code> {
+    if let Some((code, _)) = KEYS.iter().find(|(_, n)| n.eq_ignore_ascii_case(name)) {
+        return Some(*code);
+    }
+
+    let lower = name.to_ascii_lowercase();
+    if let Some((_, code)) = ALIASES.iter().find(|(alias, _)| *alias == lower) {
+        return Some(*code);
+    }
+
+    // "a" -> KeyA, "7" -> Digit7, "F5" was handled by the table
+    let mut chars = name.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if c.is_ascii_alphabetic() => parse_code(&format!("Key{}", c.to_ascii_uppercase())),
+        (Some(c), None) if c.is_ascii_digit() => parse_code(&format!("Digit{}", c)),
+        _ => None,
+    }
+}
+
+/// The canonical name of a key, as accepted by `parse_code`
+pub fn code_name(code: Code) -> &'static str {
+    KEYS.iter()
+        .find(|(c, _)| *c == code)
+        .map_or("Unidentified", |(_, name)| *name)
+}
+
+pub fn parse_modifier(name: &str) -> Option<Modifiers> {
+    match name.to_ascii_lowercase().as_str() {
+        "ctrl" | "control" => Some(Modifiers::CONTROL),
+        "alt" | "option" | "opt" => Some(Modifiers::ALT),
+        "shift" => Some(Modifiers::SHIFT),
+        "meta" | "cmd" | "command" | "super" | "win" | "windows" => Some(Modifiers::SUPER),
+        // Follows the platform convention for "the primary modifier"
+        "cmdorctrl" | "commandorcontrol" | "cmdorcontrol" | "commandorctrl" => Some(if cfg!(target_os = "macos") {
+            Modifiers::SUPER
+        } else {
+            Modifiers::CONTROL
+        }),
+        _ => None,
+    }
+}
+
+/// Build a shortcut from a key name and modifier names, as stored in settings
+pub fn shortcut_from_parts(key: &str, modifiers: &[String]) -> AppResult<Shortcut> {
+    let code = parse_code(key).ok_or_else(|| AppError::InvalidInput(format!("Unknown key: {}", key)))?;
+
+    let mut mods = Modifiers::empty();
+    for m in modifiers {
+        mods |= parse_modifier(m).ok_or_else(|| AppError::InvalidInput(format!("Unknown modifier: {}", m)))?;
+    }
+
+    // Use None for modifiers if empty, Some otherwise
+    if mods.is_empty() {
+        Ok(Shortcut::new(None, code))
+    } else {
+        Ok(Shortcut::new(Some(mods), code))
+    }
+}
+
+/// Parse an accelerator string such as "Ctrl+Shift+Space" or "cmd+k".
+/// Modifiers come first and exactly one key comes last.
+pub fn parse_accelerator(accelerator: &str) -> AppResult<Shortcut> {
+    let tokens: Vec<&str> = accelerator.split('+').map(str::trim).collect();
+    let (key, modifiers) = tokens
+        .split_last()
+        .filter(|(key, _)| !key.is_empty())
+        .ok_or_else(|| AppError::InvalidInput(format!("Missing key in shortcut: {}", accelerator)))?;
+
+    if let Some(token) = modifiers.iter().find(|m| parse_modifier(m).is_none()) {
+        return Err(AppError::InvalidInput(if token.is_empty() {
+            format!("Empty part in shortcut: {}", accelerator)
+        } else {
+            format!("Expected a modifier before the key, got '{}' in {}", token, accelerator)
+        }));
+    }
+
+    let modifiers: Vec<String> = modifiers.iter().map(|m| m.to_string()).collect();
+    shortcut_from_parts(key, &modifiers)
+}
+
+/// Split a shortcut back into its canonical key and modifier names
+pub fn describe(shortcut: &Shortcut) -> Accelerator {
+    let key = code_name(shortcut.key).to_string();
+    let modifiers: Vec<String> = MODIFIERS
+        .iter()
+        .filter(|(m, _)| shortcut.mods.contains(*m))
+        .map(|(_, name)| name.to_string())
+        .collect();
+
+    let mut parts = modifiers.clone();
+    parts.push(key.clone());
+
+    Accelerator {
+        key,
+        modifiers,
+        accelerator: parts.join("+"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_key_round_trips_through_its_name() {
+        for (code, name) in KEYS {
+            assert_eq!(parse_code(name), Some(*code), "{}", name);
+            assert_eq!(code_name(*code), *name);
+        }
+    }
+
+    #[test]
+    fn accelerators_round_trip() {
+        for accelerator in [
+            "Space",
+            "Control+Shift+Space",
+            "Alt+KeyK",
+            "Shift+Meta+Digit7",
+            "Control+Alt+Shift+Meta+F12",
+            "Shift+NumpadEnter",
+            "Control+Backquote",
+        ] {
+            let shortcut = parse_accelerator(accelerator).unwrap();
+            let described = describe(&shortcut);
+            assert_eq!(described.accelerator, accelerator);
+            assert_eq!(parse_accelerator(&described.accelerator).unwrap(), shortcut);
+            assert_eq!(shortcut_from_parts(&described.key, &described.modifiers).unwrap(), shortcut);
+        }
+    }
+
+    #[test]
+    fn loose_spellings_describe_canonically() {
+        for (input, canonical) in [
+            ("ctrl+shift+space", "Control+Shift+Space"),
+            (" Shift + cmd + k ", "Shift+Meta+KeyK"),
+            ("option+esc", "Alt+Escape"),
+            ("win+7", "Meta+Digit7"),
+            ("ctrl+`", "Control+Backquote"),
+            ("pgdn", "PageDown"),
+        ] {
+            let described = describe(&parse_accelerator(input).unwrap());
+            assert_eq!(described.accelerator, canonical, "{}", input);
+            assert_eq!(describe(&parse_accelerator(canonical).unwrap()), described);
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_accelerators() {
+        for input in ["", "Control+", "Control++K", "K+Control", "Control+Nope", "Hyperdrive+K"] {
+            assert!(parse_accelerator(input).is_err(), "{:?}", input);
+        }
+    }
+}
@@ -0,0 +1,226 @@
+use serde::Serialize;
+
+/// Writing system a language is usually transcribed in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Script {
+    Latin,
+    Cyrillic,
+    Greek,
+    Arabic,
+    Hebrew,
+    Devanagari,
+    Bengali,
+    Gurmukhi,
+    Gujarati,
+    Tamil,
+    Telugu,
+    Kannada,
+    Malayalam,
+    Sinhala,
+    Thai,
+    Lao,
+    Khmer,
+    Myanmar,
+    Tibetan,
+    Georgian,
+    Armenian,
+    Ethiopic,
+    Han,
+    Japanese,
+    Hangul,
+}
+
+/// A language Whisper can detect
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Language {
+    /// Code as reported by Whisper (ISO 639-1 where one exists)
+    pub code: &'static str,
+    pub name: &'static str,
+    pub native_name: &'static str,
+    pub script: Script,
+    pub rtl: bool,
+}
+
+const fn lang(code: &'static str, name: &'static str, native_name: &'static str, script: Script) -> Language {
+    Language {
+        code,
+        name,
+        native_name,
+        script,
+        rtl: matches!(script, Script::Arabic | Script::Hebrew),
+    }
+}
+
+/// Every language Whisper's multilingual models detect, in Whisper's own order
+pub const LANGUAGES: &[Language] = &[
+    lang("en", "English", "English", Script::Latin),
+    lang("zh", "Chinese", "中文", Script::Han),
+    lang("de", "German", "Deutsch", Script::Latin),
+    lang("es", "Spanish", "Español", Script::Latin),
+    lang("ru", "Russian", "Русский", Script::Cyrillic),
+    lang("ko", "Korean", "한국어", Script::Hangul),
+    lang("fr", "French", "Français", Script::Latin),
+    lang("ja", "Japanese", "日本語", Script::Japanese),
+    lang("pt", "Portuguese", "Português", Script::Latin),
+    lang("tr", "Turkish", "Türkçe", Script::Latin),
+    lang("pl", "Polish", "Polski", Script::Latin),
+    lang("ca", "Catalan", "Català", Script::Latin),
+    lang("nl", "Dutch", "Nederlands", Script::Latin),
+    lang("ar", "Arabic", "العربية", Script::Arabic),
+    lang("sv", "Swedish", "Svenska", Script::Latin),
+    lang("it", "Italian", "Italiano", Script::Latin),
+    lang("id", "Indonesian", "Bahasa Indonesia", Script::Latin),
+    lang("hi", "Hindi", "हिन्दी", Script::Devanagari),
+    lang("fi", "Finnish", "Suomi", Script::Latin),
+    lang("vi", "Vietnamese", "Tiếng Việt", Script::Latin),
+    lang("he", "Hebrew", "עברית", Script::Hebrew),
+    lang("uk", "Ukrainian", "Українська", Script::Cyrillic),
+    lang("el", "Greek", "Ελληνικά", Script::Greek),
+    lang("ms", "Malay", "Bahasa Melayu", Script::Latin),
+    lang("cs", "Czech", "Čeština", Script::Latin),
+    lang("ro", "Romanian", "Română", Script::Latin),
+    lang("da", "Danish", "Dansk", Script::Latin),
+    lang("hu", "Hungarian", "Magyar", Script::Latin),
+    lang("ta", "Tamil", "தமிழ்", Script::Tamil),
+    lang("no", "Norwegian", "Norsk", Script::Latin),
+    lang("th", "Thai", "ไทย", Script::Thai),
+    lang("ur", "Urdu", "اردو", Script::Arabic),
+    lang("hr", "Croatian", "Hrvatski", Script::Latin),
+    lang("bg", "Bulgarian", "Български", Script::Cyrillic),
+    lang("lt", "Lithuanian", "Lietuvių", Script::Latin),
+    lang("la", "Latin", "Latina", Script::Latin),
+    lang("mi", "Maori", "Te Reo Māori", Script::Latin),
+    lang("ml", "Malayalam", "മലയാളം", Script::Malayalam),
+    lang("cy", "Welsh", "Cymraeg", Script::Latin),
+    lang("sk", "Slovak", "Slovenčina", Script::Latin),
+    lang("te", "Telugu", "తెలుగు", Script::Telugu),
+    lang("fa", "Persian", "فارسی", Script::Arabic),
+    lang("lv", "Latvian", "Latviešu", Script::Latin),
+    lang("bn", "Bengali", "বাংলা", Script::Bengali),
+    lang("sr", "Serbian", "Српски", Script::Cyrillic),
+    lang("az", "Azerbaijani", "Azərbaycanca", Script::Latin),
+    lang("sl", "Slovenian", "Slovenščina", Script::Latin),
+    lang("kn", "Kannada", "ಕನ್ನಡ", Script::Kannada),
+    lang("et", "Estonian", "Eesti", Script::Latin),
+    lang("mk", "Macedonian", "Македонски", Script::Cyrillic),
+    lang("br", "Breton", "Brezhoneg", Script::Latin),
+    lang("eu", "Basque", "Euskara", Script::Latin),
+    lang("is", "Icelandic", "Íslenska", Script::Latin),
+    lang("hy", "Armenian", "Հայերեն", Script::Armenian),
+    lang("ne", "Nepali", "नेपाली", Script::Devanagari),
+    lang("mn", "Mongolian", "Монгол", Script::Cyrillic),
+    lang("bs", "Bosnian", "Bosanski", Script::Latin),
+    lang("kk", "Kazakh", "Қазақ тілі", Script::Cyrillic),
+    lang("sq", "Albanian", "Shqip", Script::Latin),
+    lang("sw", "Swahili", "Kiswahili", Script::Latin),
+    lang("gl", "Galician", "Galego", Script::Latin),
+    lang("mr", "Marathi", "मराठी", Script::Devanagari),
+    lang("pa", "Punjabi", "ਪੰਜਾਬੀ", Script::Gurmukhi),
+    lang("si", "Sinhala", "සිංහල", Script::Sinhala),
+    lang("km", "Khmer", "ខ្មែរ", Script::Khmer),
+    lang("sn", "Shona", "ChiShona", Script::Latin),
+    lang("yo", "Yoruba", "Yorùbá", Script::Latin),
+    lang("so", "Somali", "Soomaali", Script::Latin),
+    lang("af", "Afrikaans", "Afrikaans", Script::Latin),
+    lang("oc", "Occitan", "Occitan", Script::Latin),
+    lang("ka", "Georgian", "ქართული", Script::Georgian),
+    lang("be", "Belarusian", "Беларуская", Script::Cyrillic),
+    lang("tg", "Tajik", "Тоҷикӣ", Script::Cyrillic),
+    lang("sd", "Sindhi", "سنڌي", Script::Arabic),
+    lang("gu", "Gujarati", "ગુજરાતી", Script::Gujarati),
+    lang("am", "Amharic", "አማርኛ", Script::Ethiopic),
+    lang("yi", "Yiddish", "ייִדיש", Script::Hebrew),
+    lang("lo", "Lao", "ລາວ", Script::Lao),
+    lang("uz", "Uzbek", "Oʻzbekcha", Script::Latin),
+    lang("fo", "Faroese", "Føroyskt", Script::Latin),
+    lang("ht", "Haitian Creole", "Kreyòl Ayisyen", Script::Latin),
+    lang("ps", "Pashto", "پښتو", Script::Arabic),
+    lang("tk", "Turkmen", "Türkmençe", Script::Latin),
+    lang("nn", "Nynorsk", "Nynorsk", Script::Latin),
+    lang("mt", "Maltese", "Malti", Script::Latin),
+    lang("sa", "Sanskrit", "संस्कृतम्", Script::Devanagari),
+    lang("lb", "Luxembourgish", "Lëtzebuergesch", Script::Latin),
+    lang("my", "Myanmar", "မြန်မာ", Script::Myanmar),
+    lang("bo", "Tibetan", "བོད་ཡིག", Script::Tibetan),
+    lang("tl", "Tagalog", "Tagalog", Script::Latin),
+    lang("mg", "Malagasy", "Malagasy", Script::Latin),
+    lang("as", "Assamese", "অসমীয়া", Script::Bengali),
+    lang("tt", "Tatar", "Татарча", Script::Cyrillic),
+    lang("haw", "Hawaiian", "ʻŌlelo Hawaiʻi", Script::Latin),
+    lang("ln", "Lingala", "Lingála", Script::Latin),
+    lang("ha", "Hausa", "Hausa", Script::Latin),
+    lang("ba", "Bashkir", "Башҡортса", Script::Cyrillic),
+    lang("jw", "Javanese", "Basa Jawa", Script::Latin),
+    lang("su", "Sundanese", "Basa Sunda", Script::Latin),
+    lang("yue", "Cantonese", "粵語", Script::Han),
+];
+
+/// Look up a language by Whisper code, case-insensitively
+pub fn find(code: &str) -> Option<&'static Language> {
+    let code = code.trim();
+    LANGUAGES.iter().find(|l| l.code.eq_ignore_ascii_case(code))
+}
+
+/// Instructions written in the target language. Small models follow "keep it in
+/// French" far more reliably when the instruction itself is in French.
+pub struct LocalizedPrompt {
+    /// Full system prompt for the default cleanup mode
+    pub cleanup_system: &'static str,
+    /// Prefix for the user prompt in the default cleanup mode
+    pub cleanup_prompt: &'static str,
+    /// Sentence appended to other modes' prompts to pin the output language
+    pub keep_language: &'static str,
+}
+
+pub fn localized_prompt(code: &str) -> Option<&'static LocalizedPrompt> {
+    let prompt = match find(code)?.code {
+        "fr" => &LocalizedPrompt {
+            cleanup_system: "Tu nettoies des transcriptions orales sans JAMAIS les traduire. Supprime les mots de remplissage et corrige la grammaire en gardant exactement la même langue : le texte est en français et doit rester en français. Réponds UNIQUEMENT avec le texte nettoyé.",
+            cleanup_prompt: "Nettoie cette transcription en français (garde-la en français, ne la traduis PAS) :",
+            keep_language: "Réponds uniquement en français. Ne traduis pas.",
+        },
+        "es" => &LocalizedPrompt {
+            cleanup_system: "Limpias transcripciones habladas y NUNCA las traduces. Elimina las muletillas y corrige la gramática manteniendo exactamente el mismo idioma: el texto está en español y debe seguir en español. Responde SOLO con el texto limpio.",
+            cleanup_prompt: "Limpia esta transcripción en español (mantenla en español, NO la traduzcas):",
+            keep_language: "Responde solo en español. No traduzcas.",
+        },
+        "de" => &LocalizedPrompt {
+            cleanup_system: "Du bereinigst gesprochene Transkripte und übersetzt sie NIEMALS. Entferne Füllwörter und korrigiere die Grammatik, behalte aber genau dieselbe Sprache bei: Der Text ist auf Deutsch und muss auf Deutsch bleiben. Gib NUR den bereinigten Text aus.",
+            cleanup_prompt: "Bereinige dieses deutsche Transkript (auf Deutsch lassen, NICHT übersetzen):",
+            keep_language: "Antworte nur auf Deutsch. Nicht übersetzen.",
+        },
+        "it" => &LocalizedPrompt {
+            cleanup_system: "Ripulisci trascrizioni parlate e non le traduci MAI. Rimuovi le parole riempitive e correggi la grammatica mantenendo esattamente la stessa lingua: il testo è in italiano e deve restare in italiano. Rispondi SOLO con il testo ripulito.",
+            cleanup_prompt: "Ripulisci questa trascrizione in italiano (lasciala in italiano, NON tradurla):",
+            keep_language: "Rispondi solo in italiano. Non tradurre.",
+        },
+        "pt" => &LocalizedPrompt {
+            cleanup_system: "Você limpa transcrições faladas e NUNCA as traduz. Remova palavras de preenchimento e corrija a gramática mantendo exatamente o mesmo idioma: o texto está em português e deve continuar em português. Responda SOMENTE com o texto limpo.",
+            cleanup_prompt: "Limpe esta transcrição em português (mantenha em português, NÃO traduza):",
+            keep_language: "Responda apenas em português. Não traduza.",
+        },
+        "nl" => &LocalizedPrompt {
+            cleanup_system: "Je schoont gesproken transcripties op en vertaalt ze NOOIT. Verwijder stopwoorden en verbeter de grammatica, maar houd precies dezelfde taal aan: de tekst is in het Nederlands en moet in het Nederlands blijven. Geef ALLEEN de opgeschoonde tekst terug.",
+            cleanup_prompt: "Schoon deze Nederlandse transcriptie op (houd het Nederlands, NIET vertalen):",
+            keep_language: "Antwoord alleen in het Nederlands. Niet vertalen.",
+        },
+        "ru" => &LocalizedPrompt {
+            cleanup_system: "Ты очищаешь расшифровки устной речи и НИКОГДА их не переводишь. Удали слова-паразиты и исправь грамматику, сохранив тот же язык: текст на русском и должен остаться на русском. Выведи ТОЛЬКО очищенный текст.",
+            cleanup_prompt: "Очисти эту расшифровку на русском языке (оставь на русском, НЕ переводи):",
+            keep_language: "Отвечай только на русском. Не переводи.",
+        },
+        "zh" => &LocalizedPrompt {
+            cleanup_system: "你负责整理口语转录文本，绝对不要翻译。删除口头禅和语气词，修正语法，并保持完全相同的语言：文本是中文，输出也必须是中文。只输出整理后的文本。",
+            cleanup_prompt: "整理这段中文转录文本（保持中文，不要翻译）：",
+            keep_language: "只用中文回答，不要翻译。",
+        },
+        "ja" => &LocalizedPrompt {
+            cleanup_system: "あなたは話し言葉の書き起こしを整える担当で、絶対に翻訳しません。フィラーを取り除き、文法を整えつつ、同じ言語のままにしてください。テキストは日本語なので、日本語のまま出力してください。整えたテキストのみを出力してください。",
+            cleanup_prompt: "この日本語の書き起こしを整えてください（日本語のまま、翻訳しないこと）：",
+            keep_language: "日本語のみで回答し、翻訳しないでください。",
+        },
+        _ => return None,
+    };
+    Some(prompt)
+}
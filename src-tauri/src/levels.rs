@@ -0,0 +1,108 @@
+use serde::Serialize;
+use std::time::{Duration, Instant};
+
+/// Envelope points per reading, one per overlay bar
+pub const ENVELOPE_POINTS: usize = 5;
+
+/// Samples at or above this are treated as clipped
+const CLIP_LEVEL: f32 = 0.99;
+/// Clipped samples in one interval before it counts as clipping; a lone full-scale click doesn't
+const CLIP_COUNT: usize = 4;
+/// About -50 dBFS. Speech into a working mic is far louder.
+const QUIET_RMS: f32 = 0.003;
+/// How long to listen before calling the input too quiet
+const QUIET_AFTER: Duration = Duration::from_secs(3);
+
+/// Input level over one metering interval
+#[derive(Debug, Clone, Serialize)]
+pub struct InputLevel {
+    pub rms: f32,
+    pub peak: f32,
+    /// RMS in dBFS, floored at -100
+    pub rms_db: f32,
+    /// Peaks of consecutive slices of the interval, oldest first
+    pub envelope: Vec<f32>,
+    pub clipping: bool,
+}
+
+/// Something wrong with the input the user should fix
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LevelWarning {
+    Clipping,
+    TooQuiet,
+}
+
+/// Measures the samples a recording gains between calls. Each warning is
+/// raised at most once per recording.
+pub struct LevelMeter {
+    read_to: usize,
+    started: Instant,
+    loudest_rms: f32,
+    clipped: bool,
+    warned_clipping: bool,
+    warned_quiet: bool,
+}
+
+impl LevelMeter {
+    pub fn new() -> Self {
+        Self {
+            read_to: 0,
+            started: Instant::now(),
+            loudest_rms: 0.0,
+            clipped: false,
+            warned_clipping: false,
+            warned_quiet: false,
+        }
+    }
+
+    /// Measure `samples[since the last call..]`, or `None` if nothing arrived
+    pub fn measure(&mut self, samples: &[f32]) -> Option<InputLevel> {
+        // The buffer shrinks when a recording is resampled after a device change
+        let start = self.read_to.min(samples.len());
+        self.read_to = samples.len();
+        let new = &samples[start..];
+        if new.is_empty() {
+            return None;
+        }
+
+        let level = measure(new);
+        self.loudest_rms = self.loudest_rms.max(level.rms);
+        self.clipped |= level.clipping;
+        Some(level)
+    }
+
+    /// A warning that hasn't been raised yet this recording
+    pub fn warning(&mut self) -> Option<LevelWarning> {
+        if self.clipped && !self.warned_clipping {
+            self.warned_clipping = true;
+            return Some(LevelWarning::Clipping);
+        }
+        if !self.warned_quiet && self.started.elapsed() >= QUIET_AFTER && self.loudest_rms < QUIET_RMS {
+            self.warned_quiet = true;
+            return Some(LevelWarning::TooQuiet);
+        }
+        None
+    }
+}
+
+impl Default for LevelMeter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn measure(samples: &[f32]) -> InputLevel {
+    let peak_of = |slice: &[f32]| slice.iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+    let rms = (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt();
+    let slice_len = samples.len().div_ceil(ENVELOPE_POINTS);
+    let mut envelope: Vec<f32> = samples.chunks(slice_len).map(peak_of).collect();
+    envelope.resize(ENVELOPE_POINTS, 0.0);
+
+    InputLevel {
+        rms,
+        peak: peak_of(samples),
+        rms_db: (20.0 * rms.log10()).max(-100.0),
+        envelope,
+        clipping: samples.iter().filter(|s| s.abs() >= CLIP_LEVEL).count() >= CLIP_COUNT,
+    }
+}
@@ -1,17 +1,37 @@
+mod archive;
 mod audio;
+mod cli;
+mod clipboard;
+mod dsp;
+mod error;
+mod history;
+mod hotkeys;
+mod inject;
+mod keys;
+mod languages;
+mod levels;
+mod markdown;
 mod modes;
 mod ollama;
+mod platform;
+mod profile;
+mod settings;
+mod validation;
 mod whisper;
 
 use arboard::Clipboard;
+use error::{AppError, AppResult, PipelineWarning};
+use std::collections::HashMap;
 use std::path::PathBuf;
-use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tauri::{
     AppHandle, Emitter, Manager, State,
     menu::{Menu, MenuItem, PredefinedMenuItem},
     tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent},
 };
-use tauri_plugin_global_shortcut::{Code, GlobalShortcutExt, Modifiers, Shortcut, ShortcutState};
+use tauri_plugin_global_shortcut::{Code, GlobalShortcutExt, Shortcut, ShortcutState};
 
 #[cfg(target_os = "macos")]
 use cocoa::appkit::{NSColor, NSWindowCollectionBehavior};
@@ -26,6 +46,18 @@ struct AppState {
     whisper: Mutex<Option<whisper::WhisperTranscriber>>,
     ollama: Mutex<ollama::OllamaClient>,
     recent_transcripts: Mutex<Vec<String>>,
+    history: Mutex<history::HistoryStore>,
+    archive: Mutex<archive::AudioArchive>,
+    settings: Mutex<settings::SettingsStore>,
+    /// Registered shortcut ids mapped to what they do
+    hotkey_actions: Mutex<HashMap<u32, hotkeys::HotkeyAction>>,
+    gesture: Mutex<hotkeys::GestureTracker>,
+    /// Bumped on every recording start so a stale duration limit doesn't stop a newer one
+    recording_session: Mutex<u64>,
+    /// Bumped by `cancel_recording` so transcription and cleanup in flight give up
+    pipeline_generation: Arc<AtomicU64>,
+    /// Escape shortcut registered for the current recording, if it isn't a user binding
+    escape_shortcut: Mutex<Option<Shortcut>>,
     current_mode: Mutex<String>,
     overlay_mode: Mutex<String>,
 }
@@ -33,28 +65,201 @@ struct AppState {
 // ============ Audio Commands ============
 
 #[tauri::command]
-fn start_recording(state: State<'_, AppState>) -> Result<(), String> {
-    audio::start_recording(&state.recording_state)
+fn start_recording(app: AppHandle, state: State<'_, AppState>) -> AppResult<()> {
+    audio::start_recording(&state.recording_state)?;
+
+    // Safety cutoff so a locked or toggled recording can't run forever
+    let session = {
+        let mut session = state.recording_session.lock().unwrap();
+        *session += 1;
+        *session
+    };
+    let recording = state.settings.lock().unwrap().get().recording.clone();
+    if recording.escape_cancels {
+        grab_escape(&app, &state);
+    }
+    let max_duration = recording.max_duration_secs;
+    let limit_app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(Duration::from_secs(max_duration as u64)).await;
+        let state = limit_app.state::<AppState>();
+        let current = *state.recording_session.lock().unwrap();
+        if current == session && audio::is_recording(&state.recording_state) {
+            let warning = PipelineWarning::new(
+                "recording_limit",
+                format!("Recording stopped after the {} second limit", max_duration),
+            );
+            let _ = limit_app.emit("pipeline-warning", &warning);
+            let _ = limit_app.emit("record-stop", ());
+        }
+    });
+
+    // Warm up the cleanup model while the user is still talking. A cleanup still
+    // running for the previous recording is left to finish so it gets pasted.
+    let (ollama, mode) = {
+        let ollama = state.ollama.lock().unwrap();
+        let mode = state.current_mode.lock().unwrap();
+        (ollama.clone(), mode.clone())
+    };
+    if ollama.is_enabled() && ollama.circuit_open_for().is_none() {
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = ollama.preload(&mode).await {
+                log::debug!("Ollama preload skipped: {}", e);
+            }
+        });
+    }
+
+    Ok(())
 }
 
 #[tauri::command]
-fn stop_recording(state: State<'_, AppState>) -> Result<Vec<u8>, String> {
+fn stop_recording(app: AppHandle, state: State<'_, AppState>) -> AppResult<Vec<u8>> {
+    state.gesture.lock().unwrap().reset();
+    release_escape(&app, &state);
     audio::stop_recording(&state.recording_state)
 }
 
+/// Discard the current recording and abandon any transcription or cleanup in progress
+#[tauri::command]
+fn cancel_recording(app: AppHandle, state: State<'_, AppState>) {
+    cancel_pipeline(&app, &state);
+}
+
+fn cancel_pipeline(app: &AppHandle, state: &AppState) {
+    state.gesture.lock().unwrap().reset();
+    release_escape(app, state);
+    audio::cancel_recording(&state.recording_state);
+    state.pipeline_generation.fetch_add(1, Ordering::SeqCst);
+    state.ollama.lock().unwrap().cancel_in_flight();
+    let _ = hide_overlay(app.clone());
+    let _ = app.emit("recording-cancelled", ());
+}
+
+/// Returns true once the pipeline has been cancelled after this call
+fn cancellation(state: &AppState) -> impl Fn() -> bool + Clone + Send + 'static {
+    let generation = Arc::clone(&state.pipeline_generation);
+    let started = generation.load(Ordering::SeqCst);
+    move || generation.load(Ordering::SeqCst) != started
+}
+
+/// Bind a bare Escape to `Cancel` for the length of a recording
+fn grab_escape(app: &AppHandle, state: &AppState) {
+    let shortcut = Shortcut::new(None, Code::Escape);
+    let mut actions = state.hotkey_actions.lock().unwrap();
+    // The user already bound Escape to something
+    if app.global_shortcut().is_registered(shortcut) {
+        return;
+    }
+    match app.global_shortcut().register(shortcut) {
+        Ok(()) => {
+            actions.insert(shortcut.id(), hotkeys::HotkeyAction::Cancel);
+            *state.escape_shortcut.lock().unwrap() = Some(shortcut);
+        }
+        Err(e) => log::warn!("Escape to cancel unavailable: {}", e),
+    }
+}
+
+fn release_escape(app: &AppHandle, state: &AppState) {
+    let Some(shortcut) = state.escape_shortcut.lock().unwrap().take() else {
+        return;
+    };
+    state.hotkey_actions.lock().unwrap().remove(&shortcut.id());
+    let _ = app.global_shortcut().unregister(shortcut);
+}
+
 #[tauri::command]
 fn is_recording(state: State<'_, AppState>) -> bool {
     audio::is_recording(&state.recording_state)
 }
 
 #[tauri::command]
-fn get_input_devices() -> Vec<audio::AudioDevice> {
-    audio::get_input_devices()
+fn get_input_devices(state: State<'_, AppState>) -> Vec<audio::AudioDevice> {
+    audio::get_input_devices(&state.recording_state)
 }
 
 #[tauri::command]
-fn set_input_device(device_id: Option<String>, state: State<'_, AppState>) {
-    audio::set_input_device(&state.recording_state, device_id);
+async fn get_device_capabilities(app: AppHandle, device_id: String) -> AppResult<audio::DeviceCapabilities> {
+    // ALSA opens the device to query it, which can take a moment
+    tauri::async_runtime::spawn_blocking(move || {
+        let state = app.state::<AppState>();
+        audio::get_device_capabilities(&state.recording_state, &device_id)
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Device query failed: {}", e)))?
+}
+
+#[tauri::command]
+fn get_audio_hosts() -> Vec<audio::AudioHost> {
+    audio::get_audio_hosts()
+}
+
+/// Switch host API. The saved device ids belong to the old host, so the selection resets.
+#[tauri::command]
+fn set_audio_host(app: AppHandle, host_id: Option<String>, state: State<'_, AppState>) -> AppResult<()> {
+    audio::set_audio_host(&state.recording_state, host_id.clone())?;
+    audio::set_input_device(&state.recording_state, None);
+    audio::set_input_channels(&state.recording_state, Vec::new());
+    save_settings(&app, &state, |s| {
+        s.audio_host = host_id;
+        s.input_device = None;
+        s.input_channels = Vec::new();
+    })?;
+    let _ = app.emit("devices-changed", audio::get_input_devices(&state.recording_state));
+    Ok(())
+}
+
+#[tauri::command]
+fn set_input_device(app: AppHandle, device_id: Option<String>, state: State<'_, AppState>) -> AppResult<()> {
+    // Channel choices belong to the old device
+    audio::set_input_device(&state.recording_state, device_id.clone());
+    audio::set_input_channels(&state.recording_state, Vec::new());
+    save_settings(&app, &state, |s| {
+        s.input_device = device_id;
+        s.input_channels = Vec::new();
+    })?;
+    Ok(())
+}
+
+/// Device list changes go to the device picker and levels to the overlay; the rest are warnings
+fn emit_audio_event(app: &AppHandle, event: audio::AudioEvent) {
+    let warning = match event {
+        audio::AudioEvent::DevicesChanged { devices } => {
+            let _ = app.emit("devices-changed", &devices);
+            return;
+        }
+        audio::AudioEvent::Level(level) => {
+            let _ = app.emit("audio-level", &level);
+            return;
+        }
+        audio::AudioEvent::Clipping => PipelineWarning::new(
+            "input_clipping",
+            "Your microphone is clipping; lower its input volume or move back a little",
+        ),
+        audio::AudioEvent::TooQuiet { device } => PipelineWarning::new(
+            "input_too_quiet",
+            format!("Hardly any sound from '{}'; check it isn't muted", device),
+        ),
+        audio::AudioEvent::DeviceFallback { requested, using } => PipelineWarning::new(
+            "device_fallback",
+            format!("Microphone '{}' not found, recording from '{}'", requested, using),
+        ),
+        audio::AudioEvent::StreamInterrupted { device, error } => PipelineWarning::new(
+            "device_interrupted",
+            format!("Lost microphone '{}' ({}), looking for another", device, error),
+        ),
+        audio::AudioEvent::StreamRecovered { device } => {
+            PipelineWarning::new("device_recovered", format!("Recording continues from '{}'", device))
+        }
+    };
+    let _ = app.emit("pipeline-warning", &warning);
+}
+
+/// Record only some inputs of a multi-channel device. Takes effect on the next recording.
+#[tauri::command]
+fn set_input_channels(app: AppHandle, channels: Vec<u16>, state: State<'_, AppState>) -> AppResult<()> {
+    audio::set_input_channels(&state.recording_state, channels.clone());
+    save_settings(&app, &state, |s| s.input_channels = channels)?;
+    Ok(())
 }
 
 #[tauri::command]
@@ -65,8 +270,14 @@ fn get_selected_device(state: State<'_, AppState>) -> Option<String> {
 // ============ Whisper Commands ============
 
 #[tauri::command]
-fn load_whisper_model(model_path: String, state: State<'_, AppState>) -> Result<(), String> {
-    let path = PathBuf::from(&model_path);
+fn load_whisper_model(app: AppHandle, model_path: String, state: State<'_, AppState>) -> AppResult<()> {
+    load_whisper(&state, &model_path)?;
+    save_settings(&app, &state, |s| s.whisper_model_path = Some(model_path))?;
+    Ok(())
+}
+
+fn load_whisper(state: &AppState, model_path: &str) -> AppResult<()> {
+    let path = PathBuf::from(model_path);
     let mut transcriber = whisper::WhisperTranscriber::new(path);
     transcriber.load_model()?;
 
@@ -77,17 +288,21 @@ fn load_whisper_model(model_path: String, state: State<'_, AppState>) -> Result<
 }
 
 #[tauri::command]
-fn transcribe_audio(
-    wav_data: Vec<u8>,
-    state: State<'_, AppState>,
-) -> Result<whisper::TranscriptionResult, String> {
-    let whisper_state = state.whisper.lock().unwrap();
-    let transcriber = whisper_state
-        .as_ref()
-        .ok_or("Whisper model not loaded")?;
+async fn transcribe_audio(app: AppHandle, wav_data: Vec<u8>) -> AppResult<whisper::TranscriptionResult> {
+    // Off the main thread so `cancel_recording` can interrupt it
+    tauri::async_runtime::spawn_blocking(move || {
+        let state = app.state::<AppState>();
+        let cancelled = cancellation(&state);
+        let whisper_state = state.whisper.lock().unwrap();
+        let transcriber = whisper_state
+            .as_ref()
+            .ok_or_else(|| AppError::ModelMissing("Whisper model not loaded".to_string()))?;
 
-    let samples = whisper::wav_to_samples(&wav_data)?;
-    transcriber.transcribe(&samples)
+        let samples = audio::condition(&state.recording_state, whisper::wav_to_samples(&wav_data)?);
+        transcriber.transcribe_until(&samples, cancelled)
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Transcription task failed: {}", e)))?
 }
 
 #[tauri::command]
@@ -112,17 +327,32 @@ fn get_available_whisper_models() -> Vec<(String, String, u64)> {
 // ============ Ollama Commands ============
 
 #[tauri::command]
-async fn cleanup_text(text: String, language: Option<String>, mode: Option<String>, state: State<'_, AppState>) -> Result<String, String> {
+async fn cleanup_text(
+    app: AppHandle,
+    text: String,
+    language: Option<String>,
+    mode: Option<String>,
+    state: State<'_, AppState>,
+) -> AppResult<String> {
     let (ollama, current_mode) = {
         let guard = state.ollama.lock().unwrap();
         let mode_guard = state.current_mode.lock().unwrap();
         (guard.clone(), mode.unwrap_or_else(|| mode_guard.clone()))
     };
-    ollama.cleanup_text(&text, language.as_deref(), &current_mode).await
+    let result = ollama.cleanup_text(&text, language.as_deref(), &current_mode).await;
+    // The caller falls back to the raw transcript; tell the user why
+    if let Err(e) = &result {
+        if !matches!(e, AppError::Cancelled(_)) {
+            let warning = PipelineWarning::cleanup_fallback(e);
+            log::warn!("{}: {}", warning.code, warning.message);
+            let _ = app.emit("pipeline-warning", &warning);
+        }
+    }
+    result
 }
 
 #[tauri::command]
-async fn check_ollama_available(state: State<'_, AppState>) -> Result<bool, String> {
+async fn check_ollama_available(state: State<'_, AppState>) -> AppResult<bool> {
     let ollama = {
         let guard = state.ollama.lock().unwrap();
         guard.clone()
@@ -131,9 +361,54 @@ async fn check_ollama_available(state: State<'_, AppState>) -> Result<bool, Stri
 }
 
 #[tauri::command]
-fn set_ollama_enabled(enabled: bool, state: State<'_, AppState>) {
-    let mut ollama = state.ollama.lock().unwrap();
-    ollama.set_enabled(enabled);
+async fn get_ollama_status(state: State<'_, AppState>) -> AppResult<ollama::OllamaStatus> {
+    let ollama = {
+        let guard = state.ollama.lock().unwrap();
+        guard.clone()
+    };
+    Ok(ollama.check_status().await)
+}
+
+#[tauri::command]
+async fn list_ollama_models(state: State<'_, AppState>) -> AppResult<Vec<ollama::OllamaModel>> {
+    let ollama = {
+        let guard = state.ollama.lock().unwrap();
+        guard.clone()
+    };
+    ollama.list_models().await
+}
+
+#[tauri::command]
+async fn pull_ollama_model(app: AppHandle, model: String, state: State<'_, AppState>) -> AppResult<()> {
+    let ollama = {
+        let guard = state.ollama.lock().unwrap();
+        guard.clone()
+    };
+    ollama
+        .pull_model(&model, |progress| {
+            let _ = app.emit("ollama-pull-progress", &progress);
+        })
+        .await?;
+    let _ = app.emit("ollama-models-changed", &model);
+    Ok(())
+}
+
+#[tauri::command]
+async fn delete_ollama_model(app: AppHandle, model: String, state: State<'_, AppState>) -> AppResult<()> {
+    let ollama = {
+        let guard = state.ollama.lock().unwrap();
+        guard.clone()
+    };
+    ollama.delete_model(&model).await?;
+    let _ = app.emit("ollama-models-changed", &model);
+    Ok(())
+}
+
+#[tauri::command]
+fn set_ollama_enabled(app: AppHandle, enabled: bool, state: State<'_, AppState>) -> AppResult<()> {
+    state.ollama.lock().unwrap().set_enabled(enabled);
+    save_settings(&app, &state, |s| s.ollama.enabled = enabled)?;
+    Ok(())
 }
 
 #[tauri::command]
@@ -143,9 +418,89 @@ fn is_ollama_enabled(state: State<'_, AppState>) -> bool {
 }
 
 #[tauri::command]
-fn set_ollama_model(model: String, state: State<'_, AppState>) {
+fn set_ollama_model(app: AppHandle, model: String, state: State<'_, AppState>) -> AppResult<()> {
+    state.ollama.lock().unwrap().set_model(&model);
+    save_settings(&app, &state, |s| s.ollama.model = model)?;
+    Ok(())
+}
+
+#[tauri::command]
+fn set_ollama_deterministic(app: AppHandle, deterministic: bool, state: State<'_, AppState>) -> AppResult<()> {
+    state.ollama.lock().unwrap().set_deterministic(deterministic);
+    save_settings(&app, &state, |s| s.post_processing.deterministic = deterministic)?;
+    Ok(())
+}
+
+#[tauri::command]
+fn is_ollama_deterministic(state: State<'_, AppState>) -> bool {
+    let ollama = state.ollama.lock().unwrap();
+    ollama.is_deterministic()
+}
+
+#[tauri::command]
+fn get_mode_generation_options(mode: String, state: State<'_, AppState>) -> ollama::GenerationOptions {
+    let ollama = state.ollama.lock().unwrap();
+    ollama.options_for_mode(&mode)
+}
+
+#[tauri::command]
+fn set_mode_generation_options(
+    app: AppHandle,
+    mode: String,
+    options: Option<ollama::GenerationOptions>,
+    state: State<'_, AppState>,
+) -> AppResult<()> {
+    let mode = modes::TranscriptionMode::from_id(&mode)
+        .ok_or_else(|| AppError::InvalidInput(format!("Unknown mode: {}", mode)))?;
+    state.ollama.lock().unwrap().set_mode_options(mode.as_str(), options.clone());
+    save_settings(&app, &state, |s| match options {
+        Some(options) => {
+            s.post_processing.mode_options.insert(mode.as_str().to_string(), options);
+        }
+        None => {
+            s.post_processing.mode_options.remove(mode.as_str());
+        }
+    })?;
+    Ok(())
+}
+
+#[tauri::command]
+fn set_localized_prompts(app: AppHandle, enabled: bool, state: State<'_, AppState>) -> AppResult<()> {
+    state.ollama.lock().unwrap().set_localized_prompts(enabled);
+    save_settings(&app, &state, |s| s.post_processing.localized_prompts = enabled)?;
+    Ok(())
+}
+
+#[tauri::command]
+fn get_localized_prompts(state: State<'_, AppState>) -> bool {
+    let ollama = state.ollama.lock().unwrap();
+    ollama.uses_localized_prompts()
+}
+
+#[tauri::command]
+fn get_ollama_config(state: State<'_, AppState>) -> ollama::OllamaConfig {
+    let ollama = state.ollama.lock().unwrap();
+    ollama.config().clone()
+}
+
+#[tauri::command]
+fn set_ollama_config(app: AppHandle, config: ollama::OllamaConfig, state: State<'_, AppState>) -> AppResult<()> {
+    save_settings(&app, &state, |s| s.ollama.connection = config.clone())?;
     let mut ollama = state.ollama.lock().unwrap();
-    ollama.set_model(&model);
+    ollama.set_config(config);
+    Ok(())
+}
+
+#[tauri::command]
+fn cancel_cleanup(state: State<'_, AppState>) {
+    let ollama = state.ollama.lock().unwrap();
+    ollama.cancel_in_flight();
+}
+
+#[tauri::command]
+fn get_ollama_model(state: State<'_, AppState>) -> String {
+    let ollama = state.ollama.lock().unwrap();
+    ollama.model().to_string()
 }
 
 #[tauri::command]
@@ -159,9 +514,10 @@ fn get_recommended_ollama_models() -> Vec<(String, String)> {
 // ============ Mode Commands ============
 
 #[tauri::command]
-fn set_mode(mode: String, state: State<'_, AppState>) {
-    let mut current = state.current_mode.lock().unwrap();
-    *current = mode;
+fn set_mode(app: AppHandle, mode: String, state: State<'_, AppState>) -> AppResult<()> {
+    *state.current_mode.lock().unwrap() = mode.clone();
+    save_settings(&app, &state, |s| s.mode = mode)?;
+    Ok(())
 }
 
 #[tauri::command]
@@ -175,105 +531,390 @@ fn get_available_modes() -> Vec<modes::ModeInfo> {
     modes::get_available_modes()
 }
 
-// ============ Clipboard Commands ============
+// ============ Settings Commands ============
 
 #[tauri::command]
-fn copy_to_clipboard(text: String) -> Result<(), String> {
-    let mut clipboard = Clipboard::new().map_err(|e| format!("Failed to access clipboard: {}", e))?;
-    clipboard
-        .set_text(&text)
-        .map_err(|e| format!("Failed to copy to clipboard: {}", e))?;
-    Ok(())
+fn get_settings(state: State<'_, AppState>) -> settings::Settings {
+    let store = state.settings.lock().unwrap();
+    store.get().clone()
+}
+
+/// Schema of a settings file written by a newer version of the app, backed up at startup
+#[tauri::command]
+fn get_newer_settings_schema(state: State<'_, AppState>) -> Option<u32> {
+    let store = state.settings.lock().unwrap();
+    store.newer_schema()
 }
 
+/// Replace every setting at once, applying whatever changed
 #[tauri::command]
-fn simulate_paste() -> Result<(), String> {
-    // Use AppleScript to simulate Cmd+V
-    std::process::Command::new("osascript")
-        .arg("-e")
-        .arg("tell application \"System Events\" to keystroke \"v\" using command down")
-        .output()
-        .map_err(|e| format!("Failed to simulate paste: {}", e))?;
+fn update_settings(app: AppHandle, settings: settings::Settings, state: State<'_, AppState>) -> AppResult<settings::Settings> {
+    replace_settings(&app, &state, settings)
+}
+
+fn replace_settings(app: &AppHandle, state: &AppState, settings: settings::Settings) -> AppResult<settings::Settings> {
+    settings.validate()?;
+    let previous = state.settings.lock().unwrap().get().clone();
+
+    if settings.whisper_model_path != previous.whisper_model_path {
+        if let Some(path) = &settings.whisper_model_path {
+            load_whisper(state, path)?;
+        }
+    }
+    if settings.hotkeys != previous.hotkeys {
+        register_bindings(app, state, &settings.hotkeys);
+    }
+    apply_settings(state, &settings);
+    // Recordings whose entries retention just dropped, or that the archive limits no longer allow
+    if settings.archive != previous.archive || settings.history != previous.history {
+        if let Err(e) = prune_archive(state) {
+            log::warn!("Failed to prune archived recordings: {}", e);
+        }
+    }
+
+    save_settings(app, state, |s| *s = settings)
+}
+
+/// Apply a change to the persisted settings and tell every window about it
+fn save_settings<F>(app: &AppHandle, state: &AppState, f: F) -> AppResult<settings::Settings>
+where
+    F: FnOnce(&mut settings::Settings),
+{
+    let updated = {
+        let mut store = state.settings.lock().unwrap();
+        store.update(f)?.clone()
+    };
+    let _ = app.emit("settings-changed", &updated);
+    Ok(updated)
+}
+
+/// Push settings into the live state. The hotkey and Whisper model need an app
+/// handle or are slow to load, so callers handle those.
+fn apply_settings(state: &AppState, settings: &settings::Settings) {
+    {
+        let mut ollama = state.ollama.lock().unwrap();
+        ollama.set_enabled(settings.ollama.enabled);
+        ollama.set_model(&settings.ollama.model);
+        // Rebuilding the HTTP client drops its connection pool, so only when it changed
+        if *ollama.config() != settings.ollama.connection {
+            ollama.set_config(settings.ollama.connection.clone());
+        }
+        ollama.set_deterministic(settings.post_processing.deterministic);
+        ollama.set_localized_prompts(settings.post_processing.localized_prompts);
+        for mode in modes::TranscriptionMode::all_modes() {
+            let options = settings.post_processing.mode_options.get(mode.as_str()).cloned();
+            ollama.set_mode_options(mode.as_str(), options);
+        }
+    }
+    audio::set_input_device(&state.recording_state, settings.input_device.clone());
+    audio::set_fallback_devices(&state.recording_state, settings.input_device_fallbacks.clone());
+    audio::set_input_channels(&state.recording_state, settings.input_channels.clone());
+    audio::set_dsp(&state.recording_state, settings.dsp.clone());
+    state.archive.lock().unwrap().set_settings(settings.archive.clone());
+    {
+        let mut history = state.history.lock().unwrap();
+        if *history.retention() != settings.history {
+            if let Err(e) = history.set_retention(settings.history.clone()) {
+                log::warn!("Failed to apply the history retention policy: {}", e);
+            }
+        }
+    }
+    // A host saved on another machine may not exist here; the default is used instead
+    if let Err(e) = audio::set_audio_host(&state.recording_state, settings.audio_host.clone()) {
+        log::warn!("{}", e);
+    }
+    *state.current_mode.lock().unwrap() = settings.mode.clone();
+    *state.overlay_mode.lock().unwrap() = settings.mode.clone();
+}
+
+#[tauri::command]
+fn get_recording_settings(state: State<'_, AppState>) -> hotkeys::RecordingSettings {
+    let store = state.settings.lock().unwrap();
+    store.get().recording.clone()
+}
+
+#[tauri::command]
+fn set_recording_settings(app: AppHandle, recording: hotkeys::RecordingSettings, state: State<'_, AppState>) -> AppResult<()> {
+    recording.validate()?;
+    state.gesture.lock().unwrap().reset();
+    save_settings(&app, &state, |s| s.recording = recording)?;
     Ok(())
 }
 
+// ============ Profile Commands ============
+
+#[tauri::command]
+fn export_profile(path: String, name: Option<String>, state: State<'_, AppState>) -> AppResult<()> {
+    let settings = state.settings.lock().unwrap().get().clone();
+    profile::write_profile(&PathBuf::from(path), &profile::Profile::from_settings(&settings, name))
+}
+
+/// Settings a profile would change, so the user can pick what to keep
+#[tauri::command]
+fn preview_profile_import(path: String, state: State<'_, AppState>) -> AppResult<Vec<profile::Conflict>> {
+    let profile = profile::read_profile(&PathBuf::from(path))?;
+    let settings = state.settings.lock().unwrap().get().clone();
+    Ok(profile::conflicts(&profile, &settings))
+}
+
+#[tauri::command]
+fn import_profile(
+    app: AppHandle,
+    path: String,
+    options: profile::ImportOptions,
+    state: State<'_, AppState>,
+) -> AppResult<profile::ImportReport> {
+    let profile = profile::read_profile(&PathBuf::from(path))?;
+    let current = state.settings.lock().unwrap().get().clone();
+    let mut report = profile::import(&profile, &current, &options)?;
+    report.settings = replace_settings(&app, &state, report.settings)?;
+    Ok(report)
+}
+
+// ============ Language Commands ============
+
+#[tauri::command]
+fn get_languages() -> Vec<languages::Language> {
+    languages::LANGUAGES.to_vec()
+}
+
+#[tauri::command]
+fn get_language(code: String) -> Option<languages::Language> {
+    languages::find(&code).copied()
+}
+
+// ============ Clipboard Commands ============
+
+#[tauri::command]
+/// Copy a transcript. Output of structured modes also goes on as rich text.
+fn copy_to_clipboard(text: String, mode: Option<String>) -> AppResult<()> {
+    let mut clipboard = clipboard::open()?;
+    clipboard::ClipboardText::for_mode(&text, mode.as_deref()).write(&mut clipboard)
+}
+
+#[tauri::command]
+fn simulate_paste() -> AppResult<()> {
+    inject::system_injector()?.paste()
+}
+
+/// Paste or type `text` into the focused app, depending on the insertion setting
+#[tauri::command]
+async fn insert_text(app: AppHandle, text: String, mode: Option<String>) -> AppResult<()> {
+    let settings = app.state::<AppState>().settings.lock().unwrap().get().clone();
+    let text = clipboard::ClipboardText::for_mode(&text, mode.as_deref());
+    // Typing a long transcript takes a while; keep it off the main thread
+    tauri::async_runtime::spawn_blocking(move || insert_transcript(&text, &settings))
+        .await
+        .map_err(|e| AppError::Internal(format!("Text insertion task failed: {}", e)))?
+}
+
+fn insert_transcript(text: &clipboard::ClipboardText, settings: &settings::Settings) -> AppResult<()> {
+    let mut injector = inject::system_injector()?;
+    log::debug!("Inserting text with the {} backend", injector.name());
+
+    let transaction = match settings.injection {
+        inject::InjectionMethod::Paste => Some(clipboard::ClipboardTransaction::begin(text)?),
+        inject::InjectionMethod::Type => None,
+    };
+    let inserted = inject::insert(injector.as_mut(), text.plain(), settings.injection);
+
+    if let Some(transaction) = transaction {
+        let delay = Duration::from_millis(settings.clipboard.restore_delay_ms);
+        // On failure the transcript stays on the clipboard so it can be pasted by hand
+        if inserted.is_ok() && settings.clipboard.restore {
+            transaction.restore_after(delay);
+        } else {
+            transaction.keep_for(delay);
+        }
+    }
+    inserted
+}
+
 // ============ Global Hotkey Commands ============
 
 #[tauri::command]
-fn register_hotkey(app: AppHandle, key: String, modifiers: Vec<String>) -> Result<(), String> {
-    // First unregister any existing hotkey
+fn get_hotkeys(state: State<'_, AppState>) -> Vec<hotkeys::HotkeyBinding> {
+    let store = state.settings.lock().unwrap();
+    store.get().hotkeys.clone()
+}
+
+/// Replace every hotkey binding, register them and save them. Bindings the OS
+/// refuses (usually taken by another app) are reported per binding.
+#[tauri::command]
+fn set_hotkeys(
+    app: AppHandle,
+    bindings: Vec<hotkeys::HotkeyBinding>,
+    state: State<'_, AppState>,
+) -> AppResult<Vec<hotkeys::HotkeyStatus>> {
+    hotkeys::validate_bindings(&bindings)?;
+    let statuses = register_bindings(&app, &state, &bindings);
+    save_settings(&app, &state, |s| s.hotkeys = bindings)?;
+    Ok(statuses)
+}
+
+#[tauri::command]
+fn check_hotkey_conflicts(bindings: Vec<hotkeys::HotkeyBinding>) -> Vec<hotkeys::HotkeyConflict> {
+    hotkeys::find_conflicts(&bindings)
+}
+
+/// Normalize a shortcut typed by the user, e.g. "ctrl+shift+space"
+#[tauri::command]
+fn parse_accelerator(accelerator: String) -> AppResult<keys::Accelerator> {
+    keys::parse_accelerator(&accelerator).map(|shortcut| keys::describe(&shortcut))
+}
+
+/// Re-register the saved bindings, e.g. after `unregister_all_hotkeys`
+#[tauri::command]
+fn resume_hotkeys(app: AppHandle, state: State<'_, AppState>) -> Vec<hotkeys::HotkeyStatus> {
+    let bindings = state.settings.lock().unwrap().get().hotkeys.clone();
+    register_bindings(&app, &state, &bindings)
+}
+
+fn register_bindings(app: &AppHandle, state: &AppState, bindings: &[hotkeys::HotkeyBinding]) -> Vec<hotkeys::HotkeyStatus> {
     let _ = unregister_all_hotkeys(app.clone());
+    // Unregistered along with everything else
+    state.escape_shortcut.lock().unwrap().take();
+
+    let mut actions = state.hotkey_actions.lock().unwrap();
+    // Ids of replaced bindings must not keep their old actions
+    actions.clear();
+    let mut statuses = Vec::new();
+    for (index, binding) in bindings.iter().enumerate().filter(|(_, b)| b.enabled) {
+        let result = keys::shortcut_from_parts(&binding.key, &binding.modifiers).and_then(|shortcut| {
+            app.global_shortcut()
+                .register(shortcut)
+                .map_err(|e| AppError::Hotkey(format!("Failed to register hotkey: {}. Note: Modifier keys alone (like Right Command) may not work as global shortcuts on macOS.", e)))?;
+            Ok(shortcut)
+        });
+        match result {
+            Ok(shortcut) => {
+                actions.insert(shortcut.id(), binding.action.clone());
+                statuses.push(hotkeys::HotkeyStatus { index, registered: true, error: None });
+            }
+            Err(e) => {
+                log::warn!("Hotkey {} not registered: {}", binding.combo(), e);
+                statuses.push(hotkeys::HotkeyStatus { index, registered: false, error: Some(e.to_string()) });
+            }
+        }
+    }
+    statuses
+}
 
-    // Parse modifiers
-    let mut mods = Modifiers::empty();
-    for m in &modifiers {
-        match m.to_lowercase().as_str() {
-            "ctrl" | "control" => mods |= Modifiers::CONTROL,
-            "alt" | "option" => mods |= Modifiers::ALT,
-            "shift" => mods |= Modifiers::SHIFT,
-            "meta" | "cmd" | "command" | "super" => mods |= Modifiers::META,
-            _ => {}
-        }
-    }
-
-    // Parse key code (case-sensitive for special keys like MetaRight)
-    let code = match key.as_str() {
-        // Right-side modifier keys (as primary keys)
-        "MetaRight" => Code::MetaRight,
-        "MetaLeft" => Code::MetaLeft,
-        "ShiftRight" => Code::ShiftRight,
-        "ShiftLeft" => Code::ShiftLeft,
-        "AltRight" => Code::AltRight,
-        "AltLeft" => Code::AltLeft,
-        "ControlRight" => Code::ControlRight,
-        "ControlLeft" => Code::ControlLeft,
-        // Standard keys (case-insensitive)
-        _ => match key.to_lowercase().as_str() {
-            "a" => Code::KeyA, "b" => Code::KeyB, "c" => Code::KeyC, "d" => Code::KeyD,
-            "e" => Code::KeyE, "f" => Code::KeyF, "g" => Code::KeyG, "h" => Code::KeyH,
-            "i" => Code::KeyI, "j" => Code::KeyJ, "k" => Code::KeyK, "l" => Code::KeyL,
-            "m" => Code::KeyM, "n" => Code::KeyN, "o" => Code::KeyO, "p" => Code::KeyP,
-            "q" => Code::KeyQ, "r" => Code::KeyR, "s" => Code::KeyS, "t" => Code::KeyT,
-            "u" => Code::KeyU, "v" => Code::KeyV, "w" => Code::KeyW, "x" => Code::KeyX,
-            "y" => Code::KeyY, "z" => Code::KeyZ,
-            "space" => Code::Space,
-            "enter" | "return" => Code::Enter,
-            "escape" | "esc" => Code::Escape,
-            "backspace" => Code::Backspace,
-            "tab" => Code::Tab,
-            "f1" => Code::F1, "f2" => Code::F2, "f3" => Code::F3, "f4" => Code::F4,
-            "f5" => Code::F5, "f6" => Code::F6, "f7" => Code::F7, "f8" => Code::F8,
-            "f9" => Code::F9, "f10" => Code::F10, "f11" => Code::F11, "f12" => Code::F12,
-            _ => return Err(format!("Unknown key: {}", key)),
-        }
-    };
-
-    // Use None for modifiers if empty, Some otherwise
-    let shortcut = if mods.is_empty() {
-        Shortcut::new(None, code)
-    } else {
-        Shortcut::new(Some(mods), code)
+
+/// Run the action bound to a shortcut
+fn dispatch_hotkey(app: &AppHandle, shortcut: &Shortcut, pressed: bool) {
+    let Some(state) = app.try_state::<AppState>() else {
+        return;
+    };
+    let Some(action) = state.hotkey_actions.lock().unwrap().get(&shortcut.id()).cloned() else {
+        return;
     };
 
-    app.global_shortcut()
-        .register(shortcut)
-        .map_err(|e| format!("Failed to register hotkey: {}. Note: Modifier keys alone (like Right Command) may not work as global shortcuts on macOS.", e))?;
+    match action {
+        hotkeys::HotkeyAction::Record { mode } => {
+            let settings = state.settings.lock().unwrap().get().recording.clone();
+            let recording = audio::is_recording(&state.recording_state);
+            let gesture = {
+                let mut tracker = state.gesture.lock().unwrap();
+                if pressed {
+                    tracker.press(Instant::now(), recording, &settings)
+                } else {
+                    tracker.release(Instant::now(), &settings)
+                }
+            };
+            run_gesture(app, &state, gesture, mode);
+        }
+        hotkeys::HotkeyAction::ToggleRecording { mode } if pressed => {
+            if audio::is_recording(&state.recording_state) {
+                let _ = app.emit("record-stop", ());
+            } else {
+                let _ = app.emit("record-start", mode);
+            }
+        }
+        hotkeys::HotkeyAction::Cancel if pressed => cancel_pipeline(app, &state),
+        hotkeys::HotkeyAction::RepasteLast if pressed => {
+            let Some(text) = state.recent_transcripts.lock().unwrap().first().cloned() else {
+                return;
+            };
+            let settings = state.settings.lock().unwrap().get().clone();
+            let text = clipboard::ClipboardText::Plain(text);
+            // Inserting can shell out or type for a while, keep it off the event loop
+            std::thread::spawn(move || {
+                if let Err(e) = insert_transcript(&text, &settings) {
+                    log::warn!("Re-paste failed: {}", e);
+                }
+            });
+        }
+        hotkeys::HotkeyAction::CycleMode if pressed => {
+            if let Err(e) = cycle_mode(app, &state) {
+                log::warn!("Failed to switch mode: {}", e);
+            }
+        }
+        _ => {}
+    }
+}
 
+fn run_gesture(app: &AppHandle, state: &AppState, gesture: hotkeys::Gesture, mode: Option<String>) {
+    match gesture {
+        hotkeys::Gesture::Start => {
+            let _ = app.emit("record-start", mode);
+        }
+        hotkeys::Gesture::Stop => {
+            let _ = app.emit("record-stop", ());
+        }
+        hotkeys::Gesture::Lock => {
+            let _ = set_overlay_state(app.clone(), "locked".to_string());
+            let _ = app.emit("recording-locked", ());
+        }
+        hotkeys::Gesture::StopAfter(window) => {
+            // Wait to see whether this tap is the first half of a double tap
+            let generation = state.gesture.lock().unwrap().generation();
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                tokio::time::sleep(window).await;
+                let state = app.state::<AppState>();
+                if state.gesture.lock().unwrap().confirm_stop(generation) {
+                    let _ = app.emit("record-stop", ());
+                }
+            });
+        }
+        hotkeys::Gesture::Ignore => {}
+    }
+}
+
+/// Switch to the next mode, skipping AI modes while cleanup is disabled
+fn cycle_mode(app: &AppHandle, state: &State<'_, AppState>) -> AppResult<()> {
+    let ollama_enabled = state.ollama.lock().unwrap().is_enabled();
+    let modes: Vec<modes::TranscriptionMode> = modes::TranscriptionMode::all_modes()
+        .into_iter()
+        .filter(|m| ollama_enabled || *m == modes::TranscriptionMode::Default)
+        .collect();
+
+    let current = state.current_mode.lock().unwrap().clone();
+    let next = modes
+        .iter()
+        .position(|m| m.as_str() == current)
+        .map_or(0, |i| (i + 1) % modes.len());
+    let next = modes[next].as_str().to_string();
+
+    *state.current_mode.lock().unwrap() = next.clone();
+    set_overlay_mode(app.clone(), next.clone(), state.clone())?;
+    save_settings(app, state, |s| s.mode = next)?;
     Ok(())
 }
 
 #[tauri::command]
-fn unregister_all_hotkeys(app: AppHandle) -> Result<(), String> {
+fn unregister_all_hotkeys(app: AppHandle) -> AppResult<()> {
     app.global_shortcut()
         .unregister_all()
-        .map_err(|e| format!("Failed to unregister hotkeys: {}", e))?;
+        .map_err(|e| AppError::Hotkey(format!("Failed to unregister hotkeys: {}", e)))?;
     Ok(())
 }
 
 // ============ Overlay Commands ============
 
 #[tauri::command]
-fn show_overlay(app: AppHandle, overlay_state: String, mode: Option<String>) -> Result<(), String> {
+fn show_overlay(app: AppHandle, overlay_state: String, mode: Option<String>) -> AppResult<()> {
     if let Some(window) = app.get_webview_window("overlay") {
         // Directly call JavaScript to update state and mode
         let js_state = format!("if(typeof updateState === 'function') updateState('{}');", overlay_state);
@@ -284,8 +925,13 @@ fn show_overlay(app: AppHandle, overlay_state: String, mode: Option<String>) ->
             let _ = window.eval(&js_mode);
         }
 
-        // Position at bottom center of screen
-        if let Ok(Some(monitor)) = window.primary_monitor() {
+        // Position at bottom center of screen; Wayland compositors ignore this and place it themselves
+        let monitor = window
+            .primary_monitor()
+            .ok()
+            .flatten()
+            .filter(|_| platform::display_server().can_position_windows());
+        if let Some(monitor) = monitor {
             let screen_size = monitor.size();
             let scale = monitor.scale_factor();
             let window_width = 240.0 * scale;
@@ -317,13 +963,25 @@ fn show_overlay(app: AppHandle, overlay_state: String, mode: Option<String>) ->
             }
         }
 
+        // GTK drops keep-above and sticky when a window is hidden, so reapply them on every show
+        #[cfg(target_os = "linux")]
+        {
+            let _ = window.set_always_on_top(true);
+            let _ = window.set_visible_on_all_workspaces(true);
+        }
+
         let _ = window.show();
     }
     Ok(())
 }
 
 #[tauri::command]
-fn hide_overlay(app: AppHandle) -> Result<(), String> {
+fn get_platform_info() -> platform::PlatformInfo {
+    platform::info()
+}
+
+#[tauri::command]
+fn hide_overlay(app: AppHandle) -> AppResult<()> {
     if let Some(window) = app.get_webview_window("overlay") {
         let _ = window.hide();
     }
@@ -331,7 +989,7 @@ fn hide_overlay(app: AppHandle) -> Result<(), String> {
 }
 
 #[tauri::command]
-fn set_overlay_state(app: AppHandle, overlay_state: String) -> Result<(), String> {
+fn set_overlay_state(app: AppHandle, overlay_state: String) -> AppResult<()> {
     // Directly call JavaScript
     if let Some(window) = app.get_webview_window("overlay") {
         let js = format!("if(typeof updateState === 'function') updateState('{}');", overlay_state);
@@ -341,7 +999,7 @@ fn set_overlay_state(app: AppHandle, overlay_state: String) -> Result<(), String
 }
 
 #[tauri::command]
-fn set_overlay_mode(app: AppHandle, mode: String, state: State<'_, AppState>) -> Result<(), String> {
+fn set_overlay_mode(app: AppHandle, mode: String, state: State<'_, AppState>) -> AppResult<()> {
     // Store mode in state
     {
         let mut overlay_mode = state.overlay_mode.lock().unwrap();
@@ -364,7 +1022,7 @@ fn get_overlay_mode(state: State<'_, AppState>) -> String {
 // ============ Tray Menu Commands ============
 
 #[tauri::command]
-fn add_recent_transcript(app: AppHandle, text: String, state: State<'_, AppState>) -> Result<(), String> {
+fn add_recent_transcript(app: AppHandle, text: String, state: State<'_, AppState>) -> AppResult<()> {
     // Add to recent transcripts (keep max 3)
     {
         let mut transcripts = state.recent_transcripts.lock().unwrap();
@@ -378,7 +1036,7 @@ fn add_recent_transcript(app: AppHandle, text: String, state: State<'_, AppState
     Ok(())
 }
 
-fn update_tray_menu(app: &AppHandle, state: &State<'_, AppState>) -> Result<(), String> {
+fn update_tray_menu(app: &AppHandle, state: &State<'_, AppState>) -> AppResult<()> {
     let transcripts = state.recent_transcripts.lock().unwrap();
 
     // Build menu items
@@ -398,7 +1056,7 @@ fn update_tray_menu(app: &AppHandle, state: &State<'_, AppState>) -> Result<(),
             &display_text,
             true,
             None::<&str>,
-        ).map_err(|e| format!("Failed to create menu item: {}", e))?;
+        ).map_err(|e| AppError::Internal(format!("Failed to create menu item: {}", e)))?;
 
         items.push(item);
     }
@@ -406,16 +1064,16 @@ fn update_tray_menu(app: &AppHandle, state: &State<'_, AppState>) -> Result<(),
     // Create menu
     let menu = if items.is_empty() {
         let no_items = MenuItem::with_id(app, "no_items", "No transcripts yet", false, None::<&str>)
-            .map_err(|e| format!("Failed to create menu: {}", e))?;
+            .map_err(|e| AppError::Internal(format!("Failed to create menu: {}", e)))?;
         Menu::with_items(app, &[&no_items])
-            .map_err(|e| format!("Failed to create menu: {}", e))?
+            .map_err(|e| AppError::Internal(format!("Failed to create menu: {}", e)))?
     } else {
         let separator = PredefinedMenuItem::separator(app)
-            .map_err(|e| format!("Failed to create separator: {}", e))?;
+            .map_err(|e| AppError::Internal(format!("Failed to create separator: {}", e)))?;
         let quit = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)
-            .map_err(|e| format!("Failed to create quit: {}", e))?;
+            .map_err(|e| AppError::Internal(format!("Failed to create quit: {}", e)))?;
         let show = MenuItem::with_id(app, "show", "Show Window", true, None::<&str>)
-            .map_err(|e| format!("Failed to create show: {}", e))?;
+            .map_err(|e| AppError::Internal(format!("Failed to create show: {}", e)))?;
 
         let item_refs: Vec<&dyn tauri::menu::IsMenuItem<tauri::Wry>> = items.iter()
             .map(|i| i as &dyn tauri::menu::IsMenuItem<tauri::Wry>)
@@ -425,53 +1083,353 @@ fn update_tray_menu(app: &AppHandle, state: &State<'_, AppState>) -> Result<(),
             .collect();
 
         Menu::with_items(app, &item_refs)
-            .map_err(|e| format!("Failed to create menu: {}", e))?
+            .map_err(|e| AppError::Internal(format!("Failed to create menu: {}", e)))?
     };
 
     // Update tray
     if let Some(tray) = app.tray_by_id("main") {
         tray.set_menu(Some(menu))
-            .map_err(|e| format!("Failed to set tray menu: {}", e))?;
+            .map_err(|e| AppError::Internal(format!("Failed to set tray menu: {}", e)))?;
+    }
+
+    Ok(())
+}
+
+// ============ History Commands ============
+
+/// Open the history, moving an unreadable file aside rather than writing over it.
+/// Ids continue past any archived recording so none is matched to a new transcript.
+fn open_history(retention: history::RetentionPolicy, archive: &archive::AudioArchive) -> history::HistoryStore {
+    let path = history::get_history_path();
+    let mut history = history::HistoryStore::open(path.clone(), retention.clone()).unwrap_or_else(|e| {
+        log::error!("Failed to open history at {}: {}", path.display(), e);
+        match settings::back_up(&path) {
+            Ok(backup) => {
+                log::warn!("Moved the unreadable history to {}", backup.display());
+                history::HistoryStore::empty(path.clone(), retention)
+            }
+            Err(e) => {
+                log::error!("Failed to back up {}: {}", path.display(), e);
+                history::HistoryStore::read_only(path.clone(), retention)
+            }
+        }
+    });
+    match archive.max_id() {
+        Ok(Some(id)) => history.reserve_ids(id),
+        Ok(None) => {}
+        Err(e) => log::warn!("Failed to list archived recordings: {}", e),
+    }
+    history
+}
+
+#[tauri::command]
+fn add_history_entry(entry: history::NewHistoryEntry, state: State<'_, AppState>) -> AppResult<history::HistoryEntry> {
+    let mut history = state.history.lock().unwrap();
+    history.add(entry)
+}
+
+#[tauri::command]
+fn query_history(query: Option<history::HistoryQuery>, state: State<'_, AppState>) -> history::HistoryPage {
+    let history = state.history.lock().unwrap();
+    history.query(&query.unwrap_or_default())
+}
+
+#[tauri::command]
+fn get_history_entry(id: u64, state: State<'_, AppState>) -> Option<history::HistoryEntry> {
+    let history = state.history.lock().unwrap();
+    history.get(id).cloned()
+}
+
+#[tauri::command]
+fn delete_history_entries(ids: Vec<u64>, state: State<'_, AppState>) -> AppResult<usize> {
+    let removed = {
+        let mut history = state.history.lock().unwrap();
+        history.delete(&ids)?
+    };
+    let archive = state.archive.lock().unwrap();
+    archive.delete(&ids)?;
+    Ok(removed)
+}
+
+#[tauri::command]
+fn clear_history(state: State<'_, AppState>) -> AppResult<()> {
+    {
+        let mut history = state.history.lock().unwrap();
+        history.clear()?;
+    }
+    let archive = state.archive.lock().unwrap();
+    archive.clear()
+}
+
+#[tauri::command]
+fn get_history_retention(state: State<'_, AppState>) -> history::RetentionPolicy {
+    let history = state.history.lock().unwrap();
+    history.retention().clone()
+}
+
+#[tauri::command]
+fn set_history_retention(
+    app: AppHandle,
+    retention: history::RetentionPolicy,
+    state: State<'_, AppState>,
+) -> AppResult<usize> {
+    save_settings(&app, &state, |s| s.history = retention.clone())?;
+    let removed = {
+        let mut history = state.history.lock().unwrap();
+        history.set_retention(retention)?
+    };
+    prune_archive(&state)?;
+    Ok(removed)
+}
+
+/// Clean up a past transcript's raw text again in another mode and keep the result
+/// next to the original
+#[tauri::command]
+async fn rerun_cleanup(id: u64, mode: String, state: State<'_, AppState>) -> AppResult<history::TranscriptVariant> {
+    let mode = modes::TranscriptionMode::from_id(&mode)
+        .ok_or_else(|| AppError::InvalidInput(format!("Unknown mode: {}", mode)))?;
+
+    let (raw_text, language) = {
+        let history = state.history.lock().unwrap();
+        let entry = history
+            .get(id)
+            .ok_or_else(|| AppError::InvalidInput(format!("No history entry with id {}", id)))?;
+        (entry.raw_text.clone(), entry.language.clone())
+    };
+
+    let ollama_client = {
+        let ollama = state.ollama.lock().unwrap();
+        ollama.clone()
+    };
+    if !ollama_client.is_enabled() {
+        return Err(AppError::InvalidInput("AI cleanup is disabled".to_string()));
     }
 
+    let started = Instant::now();
+    let text = ollama_client
+        .cleanup_text(&raw_text, Some(&language), mode.as_str())
+        .await?;
+
+    let variant = history::TranscriptVariant {
+        mode: mode.as_str().to_string(),
+        text,
+        model: Some(ollama_client.model().to_string()),
+        created_at: history::now_ms(),
+        cleanup_ms: Some(started.elapsed().as_millis() as u64),
+    };
+
+    let mut history = state.history.lock().unwrap();
+    history.set_variant(id, variant.clone())?;
+    Ok(variant)
+}
+
+#[tauri::command]
+fn delete_variant(id: u64, mode: String, state: State<'_, AppState>) -> AppResult<history::HistoryEntry> {
+    let mut history = state.history.lock().unwrap();
+    history.remove_variant(id, &mode)
+}
+
+// ============ Audio Archive Commands ============
+
+/// Store a recording for a history entry if archiving is on, then enforce quotas
+fn archive_samples(state: &AppState, id: u64, samples: &[f32]) -> AppResult<()> {
+    {
+        let archive = state.archive.lock().unwrap();
+        if !archive.is_enabled() {
+            return Ok(());
+        }
+        archive.store(id, samples)?;
+    }
+    prune_archive(state)
+}
+
+fn prune_archive(state: &AppState) -> AppResult<()> {
+    let live_ids = {
+        let history = state.history.lock().unwrap();
+        (!history.is_recovered()).then(|| history.ids())
+    };
+    let archive = state.archive.lock().unwrap();
+    let removed = archive.prune(live_ids.as_ref())?;
+    if !removed.is_empty() {
+        log::info!("Pruned {} archived recordings", removed.len());
+    }
     Ok(())
 }
 
+#[tauri::command]
+fn get_archive_settings(state: State<'_, AppState>) -> archive::ArchiveSettings {
+    let archive = state.archive.lock().unwrap();
+    archive.settings().clone()
+}
+
+#[tauri::command]
+fn set_archive_settings(app: AppHandle, settings: archive::ArchiveSettings, state: State<'_, AppState>) -> AppResult<()> {
+    save_settings(&app, &state, |s| s.archive = settings.clone())?;
+    state.archive.lock().unwrap().set_settings(settings);
+    prune_archive(&state)
+}
+
+#[tauri::command]
+fn get_archive_usage(state: State<'_, AppState>) -> AppResult<archive::ArchiveUsage> {
+    let archive = state.archive.lock().unwrap();
+    archive.usage()
+}
+
+#[tauri::command]
+fn archive_recording(id: u64, wav_data: Vec<u8>, state: State<'_, AppState>) -> AppResult<()> {
+    let samples = whisper::wav_to_samples(&wav_data)?;
+    archive_samples(&state, id, &samples)
+}
+
+#[tauri::command]
+fn has_archived_audio(id: u64, state: State<'_, AppState>) -> bool {
+    let archive = state.archive.lock().unwrap();
+    archive.contains(id)
+}
+
+/// WAV bytes of an archived recording, for playback
+#[tauri::command]
+fn get_archived_audio(id: u64, state: State<'_, AppState>) -> AppResult<Vec<u8>> {
+    let samples = {
+        let archive = state.archive.lock().unwrap();
+        archive.load_samples(id)?
+    };
+    audio::samples_to_wav(&samples)
+}
+
+/// Re-transcribe an archived recording, optionally with a different Whisper model,
+/// and re-run cleanup in the entry's mode or a new one
+#[tauri::command]
+async fn reprocess_entry(
+    app: AppHandle,
+    id: u64,
+    model_path: Option<String>,
+    mode: Option<String>,
+    state: State<'_, AppState>,
+) -> AppResult<history::HistoryEntry> {
+    let samples = {
+        let archive = state.archive.lock().unwrap();
+        archive.load_samples(id)?
+    };
+    let entry_mode = {
+        let history = state.history.lock().unwrap();
+        history
+            .get(id)
+            .map(|e| e.mode.clone())
+            .ok_or_else(|| AppError::InvalidInput(format!("No history entry with id {}", id)))?
+    };
+
+    let transcribe_started = Instant::now();
+    // Conditioning, loading a model and transcribing take seconds; keep them off the async runtime
+    let whisper_app = app.clone();
+    let (transcription, whisper_model) = tauri::async_runtime::spawn_blocking(move || -> AppResult<_> {
+        let state = whisper_app.state::<AppState>();
+        // Archived recordings are unprocessed, so the current DSP settings apply
+        let samples = audio::condition(&state.recording_state, samples);
+        match model_path {
+            Some(path) => {
+                let mut transcriber = whisper::WhisperTranscriber::new(PathBuf::from(path));
+                transcriber.load_model()?;
+                Ok((transcriber.transcribe(&samples)?, transcriber.model_name()))
+            }
+            None => {
+                let whisper_state = state.whisper.lock().unwrap();
+                let transcriber = whisper_state
+                    .as_ref()
+                    .ok_or_else(|| AppError::ModelMissing("Whisper model not loaded".to_string()))?;
+                Ok((transcriber.transcribe(&samples)?, transcriber.model_name()))
+            }
+        }
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Transcription task failed: {}", e)))??;
+    let transcribe_ms = transcribe_started.elapsed().as_millis() as u64;
+
+    let raw_text = transcription.text.trim().to_string();
+    let mode = mode.unwrap_or(entry_mode);
+    let ollama_client = {
+        let ollama = state.ollama.lock().unwrap();
+        ollama.clone()
+    };
+
+    let cleanup_started = Instant::now();
+    let (cleaned_text, model) = if ollama_client.is_enabled() && raw_text.len() > 3 {
+        match ollama_client.cleanup_text(&raw_text, Some(&transcription.language), &mode).await {
+            Ok(cleaned) => (cleaned, Some(ollama_client.model().to_string())),
+            Err(e @ AppError::Cancelled(_)) => return Err(e),
+            Err(e) => {
+                let warning = PipelineWarning::cleanup_fallback(&e);
+                log::warn!("{}: {}", warning.code, warning.message);
+                let _ = app.emit("pipeline-warning", &warning);
+                (raw_text.clone(), None)
+            }
+        }
+    } else {
+        (raw_text.clone(), None)
+    };
+    let cleanup_ms = model.as_ref().map(|_| cleanup_started.elapsed().as_millis() as u64);
+
+    let mut history = state.history.lock().unwrap();
+    history.update(id, |entry| {
+        entry.raw_text = raw_text;
+        entry.cleaned_text = cleaned_text;
+        entry.mode = mode;
+        entry.language = transcription.language;
+        entry.model = model;
+        entry.whisper_model = Some(whisper_model);
+        entry.transcribe_ms = Some(transcribe_ms);
+        entry.cleanup_ms = cleanup_ms;
+    })
+}
+
 // ============ Combined Workflow ============
 
 #[tauri::command]
 async fn transcribe_and_cleanup(
+    app: AppHandle,
     wav_data: Vec<u8>,
     mode: Option<String>,
     state: State<'_, AppState>,
-) -> Result<TranscribeResult, String> {
+) -> AppResult<TranscribeResult> {
     // Check if we have audio data
     if wav_data.len() < 1000 {
-        return Err("No audio captured. Check microphone permissions in System Settings > Privacy & Security > Microphone.".to_string());
+        return Err(AppError::NoAudio("No audio captured.".to_string()));
     }
 
+    let cancelled = cancellation(&state);
+
     // First transcribe
-    let transcription = {
+    let transcribe_started = Instant::now();
+    let whisper_app = app.clone();
+    let transcribe_cancelled = cancelled.clone();
+    let (transcription, whisper_model, samples) = tauri::async_runtime::spawn_blocking(move || -> AppResult<_> {
+        let state = whisper_app.state::<AppState>();
         let whisper_state = state.whisper.lock().unwrap();
         let transcriber = whisper_state
             .as_ref()
-            .ok_or("Whisper model not loaded")?;
+            .ok_or_else(|| AppError::ModelMissing("Whisper model not loaded".to_string()))?;
 
         let samples = whisper::wav_to_samples(&wav_data)?;
 
         // Check if audio has any signal
         let max_amplitude: f32 = samples.iter().map(|s| s.abs()).fold(0.0, f32::max);
         if max_amplitude < 0.01 {
-            return Err("Audio too quiet - check that your microphone is working and you have granted permission.".to_string());
+            return Err(AppError::NoAudio("Audio too quiet - check that your microphone is working and you have granted permission.".to_string()));
         }
 
-        transcriber.transcribe(&samples)?
-    };
+        // The unprocessed samples are what gets archived
+        let conditioned = audio::condition(&state.recording_state, samples.clone());
+        Ok((transcriber.transcribe_until(&conditioned, transcribe_cancelled)?, transcriber.model_name(), samples))
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Transcription task failed: {}", e)))??;
+    let audio_duration_ms = samples.len() as u64 * 1000 / 16000;
+    let transcribe_ms = transcribe_started.elapsed().as_millis() as u64;
 
     // Check if transcription is meaningful
     let raw_text = transcription.text.trim();
     if raw_text.is_empty() || raw_text.len() < 2 {
-        return Err("Could not transcribe audio. Try speaking louder or longer.".to_string());
+        return Err(AppError::Transcription("Could not transcribe audio.".to_string()));
     }
 
     // Get the mode to use (from parameter or state)
@@ -488,44 +1446,122 @@ async fn transcribe_and_cleanup(
 
     let language = &transcription.language;
 
-    let cleaned_text = if ollama_enabled && raw_text.len() > 3 {
+    let mut warnings = Vec::new();
+    let cleanup_started = Instant::now();
+    let mut cleanup_ran = ollama_enabled && raw_text.len() > 3;
+
+    let cleaned_text = if cleanup_ran {
         match ollama_client.cleanup_text(raw_text, Some(language), &current_mode).await {
-            Ok(cleaned) => {
-                // If Ollama returns something that looks like an error/instruction, use raw text
-                if cleaned.contains("provide") && cleaned.contains("transcript") {
-                    raw_text.to_string()
-                } else {
-                    cleaned
-                }
-            },
-            Err(_) => raw_text.to_string(), // Fall back to raw text on error
+            Ok(cleaned) => cleaned,
+            // Only cancelling the recording discards it; skipping cleanup keeps the raw text
+            Err(e @ AppError::Cancelled(_)) if cancelled() => return Err(e),
+            Err(AppError::Cancelled(_)) => {
+                cleanup_ran = false;
+                raw_text.to_string()
+            }
+            Err(e) => {
+                warnings.push(PipelineWarning::cleanup_fallback(&e));
+                raw_text.to_string()
+            }
         }
     } else {
         raw_text.to_string()
     };
 
+    let cleanup_ms = cleanup_started.elapsed().as_millis() as u64;
+    if cancelled() {
+        return Err(AppError::Cancelled("Transcription cancelled".to_string()));
+    }
+
+    let entry = {
+        let mut history = state.history.lock().unwrap();
+        history.add(history::NewHistoryEntry {
+            raw_text: raw_text.to_string(),
+            cleaned_text: cleaned_text.clone(),
+            mode: current_mode.clone(),
+            language: transcription.language.clone(),
+            model: cleanup_ran.then(|| ollama_client.model().to_string()),
+            whisper_model: Some(whisper_model),
+            audio_duration_ms: Some(audio_duration_ms),
+            transcribe_ms: Some(transcribe_ms),
+            cleanup_ms: cleanup_ran.then_some(cleanup_ms),
+            created_at: None,
+        })
+    };
+    let id = match entry {
+        Ok(entry) => {
+            if let Err(e) = archive_samples(&state, entry.id, &samples) {
+                warnings.push(PipelineWarning::new("audio_not_archived", format!("Failed to archive recording: {}", e)));
+            }
+            Some(entry.id)
+        }
+        Err(e) => {
+            warnings.push(PipelineWarning::new("history_not_saved", format!("Failed to save to history: {}", e)));
+            None
+        }
+    };
+
+    for warning in &warnings {
+        log::warn!("{}: {}", warning.code, warning.message);
+        let _ = app.emit("pipeline-warning", warning);
+    }
+
     Ok(TranscribeResult {
+        id,
         raw_text: raw_text.to_string(),
         cleaned_text,
         language: transcription.language,
+        warnings,
     })
 }
 
 #[derive(Debug, Clone, serde::Serialize)]
 struct TranscribeResult {
+    /// History entry id, if the transcript was saved
+    id: Option<u64>,
     raw_text: String,
     cleaned_text: String,
     language: String,
+    warnings: Vec<PipelineWarning>,
 }
 
 // ============ App Entry Point ============
 
+/// Run a command-line subcommand, returning its exit code, or `None` to start the GUI
+pub fn run_cli(args: &[String]) -> Option<i32> {
+    cli::run(args)
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     let _ = env_logger::try_init();
 
+    // Settings are loaded before any window exists so the backend starts configured
+    let settings_store = settings::SettingsStore::load(settings::get_settings_path());
+    let initial_settings = settings_store.get().clone();
+    let archive = archive::AudioArchive::new(archive::get_archive_dir());
+    let history = open_history(initial_settings.history.clone(), &archive);
+    let state = AppState {
+        recording_state: audio::RecordingState::new(),
+        whisper: Mutex::new(None),
+        ollama: Mutex::new(ollama::OllamaClient::new()),
+        recent_transcripts: Mutex::new(Vec::new()),
+        history: Mutex::new(history),
+        archive: Mutex::new(archive),
+        settings: Mutex::new(settings_store),
+        hotkey_actions: Mutex::new(HashMap::new()),
+        gesture: Mutex::new(hotkeys::GestureTracker::default()),
+        recording_session: Mutex::new(0),
+        pipeline_generation: Arc::new(AtomicU64::new(0)),
+        escape_shortcut: Mutex::new(None),
+        current_mode: Mutex::new("default".to_string()),
+        overlay_mode: Mutex::new("default".to_string()),
+    };
+    apply_settings(&state, &initial_settings);
+
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
+        // A LaunchAgent on macOS, an XDG autostart entry on Linux, the Run key on Windows
         .plugin(tauri_plugin_autostart::init(
             tauri_plugin_autostart::MacosLauncher::LaunchAgent,
             Some(vec!["--minimized"]),
@@ -533,27 +1569,47 @@ pub fn run() {
         .plugin(
             tauri_plugin_global_shortcut::Builder::new()
                 .with_handler(|app, shortcut, event| {
-                    // Emit events for key press/release to frontend
-                    match event.state() {
-                        ShortcutState::Pressed => {
-                            let _ = app.emit("hotkey-pressed", shortcut.to_string());
-                        }
-                        ShortcutState::Released => {
-                            let _ = app.emit("hotkey-released", shortcut.to_string());
-                        }
-                    }
+                    dispatch_hotkey(app, shortcut, matches!(event.state(), ShortcutState::Pressed));
                 })
                 .build(),
         )
-        .manage(AppState {
-            recording_state: audio::RecordingState::new(),
-            whisper: Mutex::new(None),
-            ollama: Mutex::new(ollama::OllamaClient::new()),
-            recent_transcripts: Mutex::new(Vec::new()),
-            current_mode: Mutex::new("default".to_string()),
-            overlay_mode: Mutex::new("default".to_string()),
-        })
-        .setup(|app| {
+        .manage(state)
+        .setup(move |app| {
+            // Launched at login: start in the tray
+            if std::env::args().any(|a| a == "--minimized") {
+                if let Some(window) = app.get_webview_window("main") {
+                    let _ = window.hide();
+                }
+            }
+
+            register_bindings(app.handle(), &app.state::<AppState>(), &initial_settings.hotkeys);
+
+            let handle = app.handle().clone();
+            let state = app.state::<AppState>();
+            audio::set_event_sink(&state.recording_state, move |event| emit_audio_event(&handle, event));
+            audio::watch_devices(&state.recording_state);
+            if !platform::display_server().global_shortcuts() {
+                log::warn!("Global shortcuts only work while an XWayland window has focus under Wayland");
+            }
+
+            // Load the Whisper model off the main thread; the UI picks it up from the event
+            let model_path = initial_settings.whisper_model_path.clone().unwrap_or_else(|| {
+                whisper::get_models_dir().join("ggml-base.bin").to_string_lossy().to_string()
+            });
+            let handle = app.handle().clone();
+            std::thread::spawn(move || {
+                if !std::path::Path::new(&model_path).exists() {
+                    return;
+                }
+                let state = handle.state::<AppState>();
+                match load_whisper(&state, &model_path) {
+                    Ok(()) => {
+                        let _ = handle.emit("whisper-model-loaded", &model_path);
+                    }
+                    Err(e) => log::warn!("Failed to load Whisper model {}: {}", model_path, e),
+                }
+            });
+
             // Create tray icon with menu
             let no_items = MenuItem::with_id(app, "no_items", "No transcripts yet", false, None::<&str>)?;
             let separator = PredefinedMenuItem::separator(app)?;
@@ -589,6 +1645,7 @@ pub fn run() {
                         app.exit(0);
                     }
                 })
+                // Not emitted by AppIndicator on Linux, where the menu's Show Window does the same
                 .on_tray_icon_event(|tray, event| {
                     if let TrayIconEvent::Click { button: MouseButton::Left, button_state: MouseButtonState::Up, .. } = event {
                         let app = tray.app_handle();
@@ -606,10 +1663,15 @@ pub fn run() {
             // Audio
             start_recording,
             stop_recording,
+            cancel_recording,
             is_recording,
             get_input_devices,
+            get_device_capabilities,
+            get_audio_hosts,
+            set_audio_host,
             set_input_device,
             get_selected_device,
+            set_input_channels,
             // Whisper
             load_whisper_model,
             transcribe_audio,
@@ -619,28 +1681,79 @@ pub fn run() {
             // Ollama
             cleanup_text,
             check_ollama_available,
+            get_ollama_status,
+            list_ollama_models,
+            pull_ollama_model,
+            delete_ollama_model,
             set_ollama_enabled,
             is_ollama_enabled,
             set_ollama_model,
+            get_ollama_model,
+            set_localized_prompts,
+            get_localized_prompts,
+            get_ollama_config,
+            set_ollama_config,
+            cancel_cleanup,
+            set_ollama_deterministic,
+            is_ollama_deterministic,
+            get_mode_generation_options,
+            set_mode_generation_options,
             get_recommended_ollama_models,
             // Modes
             set_mode,
             get_mode,
             get_available_modes,
+            // Settings
+            get_settings,
+            get_newer_settings_schema,
+            update_settings,
+            get_recording_settings,
+            set_recording_settings,
+            // Profiles
+            export_profile,
+            preview_profile_import,
+            import_profile,
+            // Languages
+            get_languages,
+            get_language,
             // Clipboard
             copy_to_clipboard,
             simulate_paste,
+            insert_text,
             // Hotkeys
-            register_hotkey,
+            get_hotkeys,
+            set_hotkeys,
+            check_hotkey_conflicts,
+            parse_accelerator,
+            resume_hotkeys,
             unregister_all_hotkeys,
             // Overlay
             show_overlay,
+            get_platform_info,
             hide_overlay,
             set_overlay_state,
             set_overlay_mode,
             get_overlay_mode,
             // Tray
             add_recent_transcript,
+            // History
+            add_history_entry,
+            query_history,
+            get_history_entry,
+            delete_history_entries,
+            clear_history,
+            get_history_retention,
+            set_history_retention,
+            rerun_cleanup,
+            delete_variant,
+            // Audio archive
+            get_archive_settings,
+            set_archive_settings,
+            get_archive_usage,
+            archive_recording,
+            has_archived_audio,
+            get_archived_audio,
+            reprocess_entry,
             // Combined
             transcribe_and_cleanup,
         ])
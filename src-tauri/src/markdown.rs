@@ -0,0 +1,347 @@
+/// One transcript in every clipboard flavor
+#[derive(Debug, Clone, PartialEq)]
+pub struct Formatted {
+    /// Markers stripped, bullets as `•`
+    pub plain: String,
+    pub html: String,
+    pub rtf: String,
+}
+
+/// Render the Markdown subset cleanup models produce: headings, lists, bold,
+/// italic and inline code. Anything else passes through as text.
+pub fn render(text: &str) -> Formatted {
+    let blocks = parse_blocks(text);
+    Formatted {
+        plain: to_plain(&blocks),
+        html: to_html(&blocks),
+        rtf: to_rtf(&blocks),
+    }
+}
+
+#[derive(Debug)]
+enum Block {
+    Heading(usize, String),
+    /// Lines kept apart, as the model wrote them
+    Paragraph(Vec<String>),
+    List { ordered: bool, items: Vec<String> },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Span<'a> {
+    Text(&'a str),
+    Bold(&'a str),
+    Italic(&'a str),
+    Code(&'a str),
+}
+
+fn parse_blocks(text: &str) -> Vec<Block> {
+    let mut blocks: Vec<Block> = Vec::new();
+    let mut paragraph_open = false;
+
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() {
+            paragraph_open = false;
+            continue;
+        }
+        if let Some((level, title)) = heading(line) {
+            blocks.push(Block::Heading(level, title.to_string()));
+            paragraph_open = false;
+            continue;
+        }
+        if let Some((ordered, item)) = list_item(line) {
+            // Models often put blank lines between items; keep them in one list
+            match blocks.last_mut() {
+                Some(Block::List { ordered: o, items }) if *o == ordered => items.push(item.to_string()),
+                _ => blocks.push(Block::List {
+                    ordered,
+                    items: vec![item.to_string()],
+                }),
+            }
+            paragraph_open = false;
+            continue;
+        }
+        match blocks.last_mut() {
+            Some(Block::Paragraph(lines)) if paragraph_open => lines.push(line.to_string()),
+            _ => blocks.push(Block::Paragraph(vec![line.to_string()])),
+        }
+        paragraph_open = true;
+    }
+    blocks
+}
+
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    let title = line[level..].strip_prefix(' ')?;
+    (1..=6).contains(&level).then(|| (level, title.trim()))
+}
+
+/// `(ordered, item text)` for `- item`, `* item`, `• item` or `1. item`
+fn list_item(line: &str) -> Option<(bool, &str)> {
+    for marker in ["- ", "* ", "+ ", "• "] {
+        if let Some(item) = line.strip_prefix(marker) {
+            return Some((false, item.trim()));
+        }
+    }
+    let digits = line.chars().take_while(char::is_ascii_digit).count();
+    if digits == 0 || digits > 3 {
+        return None;
+    }
+    let item = line[digits..].strip_prefix(". ").or_else(|| line[digits..].strip_prefix(") "))?;
+    Some((true, item.trim()))
+}
+
+fn spans(text: &str) -> Vec<Span<'_>> {
+    let mut spans = Vec::new();
+    let mut rest = text;
+    'outer: while !rest.is_empty() {
+        for (i, c) in rest.char_indices() {
+            if let Some((span, len)) = span_at(rest, i, c) {
+                if i > 0 {
+                    spans.push(Span::Text(&rest[..i]));
+                }
+                spans.push(span);
+                rest = &rest[i + len..];
+                continue 'outer;
+            }
+        }
+        spans.push(Span::Text(rest));
+        break;
+    }
+    spans
+}
+
+/// A formatted span opening at byte `i`, with its length including both markers
+fn span_at<'a>(text: &'a str, i: usize, c: char) -> Option<(Span<'a>, usize)> {
+    let after = &text[i..];
+    let (marker, kind): (&str, fn(&'a str) -> Span<'a>) = match c {
+        '`' => ("`", Span::Code),
+        '*' if after.starts_with("**") => ("**", Span::Bold),
+        '*' => ("*", Span::Italic),
+        // Not inside a word, so snake_case names survive
+        '_' if text[..i].chars().next_back().is_none_or(|p| !p.is_alphanumeric()) => ("_", Span::Italic),
+        _ => return None,
+    };
+    let body = &after[marker.len()..];
+    let end = body.find(marker)?;
+    let content = &body[..end];
+    if content.is_empty() || content.starts_with(char::is_whitespace) || content.ends_with(char::is_whitespace) {
+        return None;
+    }
+    Some((kind(content), marker.len() * 2 + end))
+}
+
+fn to_plain(blocks: &[Block]) -> String {
+    let inline = |text: &str| -> String {
+        spans(text)
+            .into_iter()
+            .map(|span| match span {
+                Span::Text(s) | Span::Bold(s) | Span::Italic(s) | Span::Code(s) => s,
+            })
+            .collect()
+    };
+
+    blocks
+        .iter()
+        .map(|block| match block {
+            Block::Heading(_, title) => inline(title),
+            Block::Paragraph(lines) => lines.iter().map(|l| inline(l)).collect::<Vec<_>>().join("\n"),
+            Block::List { ordered, items } => items
+                .iter()
+                .enumerate()
+                .map(|(i, item)| {
+                    if *ordered {
+                        format!("{}. {}", i + 1, inline(item))
+                    } else {
+                        format!("• {}", inline(item))
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+fn to_html(blocks: &[Block]) -> String {
+    let inline = |text: &str| -> String {
+        spans(text)
+            .into_iter()
+            .map(|span| match span {
+                Span::Text(s) => escape_html(s),
+                Span::Bold(s) => format!("<strong>{}</strong>", escape_html(s)),
+                Span::Italic(s) => format!("<em>{}</em>", escape_html(s)),
+                Span::Code(s) => format!("<code>{}</code>", escape_html(s)),
+            })
+            .collect()
+    };
+
+    let mut html = String::new();
+    for block in blocks {
+        match block {
+            Block::Heading(level, title) => html.push_str(&format!("<h{0}>{1}</h{0}>", level, inline(title))),
+            Block::Paragraph(lines) => {
+                let lines: Vec<String> = lines.iter().map(|l| inline(l)).collect();
+                html.push_str(&format!("<p>{}</p>", lines.join("<br>")));
+            }
+            Block::List { ordered, items } => {
+                let tag = if *ordered { "ol" } else { "ul" };
+                html.push_str(&format!("<{}>", tag));
+                for item in items {
+                    html.push_str(&format!("<li>{}</li>", inline(item)));
+                }
+                html.push_str(&format!("</{}>", tag));
+            }
+        }
+    }
+    html
+}
+
+fn to_rtf(blocks: &[Block]) -> String {
+    let inline = |text: &str| -> String {
+        spans(text)
+            .into_iter()
+            .map(|span| match span {
+                Span::Text(s) => escape_rtf(s),
+                Span::Bold(s) => format!("{{\\b {}}}", escape_rtf(s)),
+                Span::Italic(s) => format!("{{\\i {}}}", escape_rtf(s)),
+                Span::Code(s) => format!("{{\\f1 {}}}", escape_rtf(s)),
+            })
+            .collect()
+    };
+
+    let mut rtf = String::from("{\\rtf1\\ansi\\deff0{\\fonttbl{\\f0\\fswiss Helvetica;}{\\f1\\fmodern Courier;}}\\fs24\n");
+    for block in blocks {
+        match block {
+            Block::Heading(level, title) => {
+                // Half-points: 18, 15 and 13 pt
+                let size = match level {
+                    1 => 36,
+                    2 => 30,
+                    _ => 26,
+                };
+                rtf.push_str(&format!("\\pard\\sa120{{\\b\\fs{} {}}}\\par\n", size, inline(title)));
+            }
+            Block::Paragraph(lines) => {
+                let lines: Vec<String> = lines.iter().map(|l| inline(l)).collect();
+                rtf.push_str(&format!("\\pard\\sa120 {}\\par\n", lines.join("\\line ")));
+            }
+            Block::List { ordered, items } => {
+                for (i, item) in items.iter().enumerate() {
+                    let marker = if *ordered {
+                        format!("{}.", i + 1)
+                    } else {
+                        "\\bullet".to_string()
+                    };
+                    rtf.push_str(&format!("\\pard\\li360\\fi-360\\sa60 {}\\tab {}\\par\n", marker, inline(item)));
+                }
+            }
+        }
+    }
+    rtf.push('}');
+    rtf
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn escape_rtf(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | '{' | '}' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            c if c.is_ascii() => escaped.push(c),
+            // RTF takes UTF-16 units as signed decimals, with `?` for readers that can't
+            c => {
+                let mut units = [0u16; 2];
+                for &unit in c.encode_utf16(&mut units).iter() {
+                    escaped.push_str(&format!("\\u{}?", unit as i16));
+                }
+            }
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The RTF body, without the header and closing brace
+    fn rtf_body(text: &str) -> String {
+        let rtf = render(text).rtf;
+        let start = rtf.find('\n').unwrap() + 1;
+        rtf[start..rtf.len() - 1].to_string()
+    }
+
+    #[test]
+    fn headings_render_in_every_flavor() {
+        let formatted = render("## Action items");
+        assert_eq!(formatted.plain, "Action items");
+        assert_eq!(formatted.html, "<h2>Action items</h2>");
+        assert_eq!(rtf_body("## Action items"), "\\pard\\sa120{\\b\\fs30 Action items}\\par\n");
+        // No space after the hashes, or too many of them, is just text
+        assert_eq!(render("#hashtag").html, "<p>#hashtag</p>");
+        assert_eq!(render("####### deep").html, "<p>####### deep</p>");
+    }
+
+    #[test]
+    fn lists_group_items_across_blank_lines() {
+        let formatted = render("- milk\n\n* eggs\n\n1. first\n2) second");
+        assert_eq!(formatted.plain, "• milk\n• eggs\n\n1. first\n2. second");
+        assert_eq!(formatted.html, "<ul><li>milk</li><li>eggs</li></ul><ol><li>first</li><li>second</li></ol>");
+        assert!(formatted.rtf.contains("\\bullet\\tab milk\\par"));
+        assert!(formatted.rtf.contains("2.\\tab second\\par"));
+    }
+
+    #[test]
+    fn paragraphs_keep_their_line_breaks() {
+        let formatted = render("Hi team,\nquick update.\n\nThanks");
+        assert_eq!(formatted.plain, "Hi team,\nquick update.\n\nThanks");
+        assert_eq!(formatted.html, "<p>Hi team,<br>quick update.</p><p>Thanks</p>");
+    }
+
+    #[test]
+    fn emphasis_and_code_spans() {
+        let formatted = render("**Bold**, *italic*, _also italic_ and `code`");
+        assert_eq!(formatted.plain, "Bold, italic, also italic and code");
+        assert_eq!(
+            formatted.html,
+            "<p><strong>Bold</strong>, <em>italic</em>, <em>also italic</em> and <code>code</code></p>"
+        );
+        assert_eq!(
+            rtf_body("**Bold** and `code`"),
+            "\\pard\\sa120 {\\b Bold} and {\\f1 code}\\par\n"
+        );
+    }
+
+    #[test]
+    fn stray_markers_stay_as_text() {
+        assert_eq!(render("2 * 3 * 4").plain, "2 * 3 * 4");
+        assert_eq!(render("call snake_case_name").plain, "call snake_case_name");
+        assert_eq!(render("an *unclosed marker").plain, "an *unclosed marker");
+    }
+
+    #[test]
+    fn html_escapes_markup_characters() {
+        assert_eq!(
+            render("if a < b && c > \"d\"").html,
+            "<p>if a &lt; b &amp;&amp; c &gt; &quot;d&quot;</p>"
+        );
+        assert_eq!(render("`<br>`").html, "<p><code>&lt;br&gt;</code></p>");
+    }
+
+    #[test]
+    fn rtf_escapes_control_characters_and_non_ascii() {
+        assert_eq!(escape_rtf("a\\b {c}"), "a\\\\b \\{c\\}");
+        assert_eq!(escape_rtf("café"), "caf\\u233?");
+        // Outside the BMP: a surrogate pair, each half as a signed 16-bit number
+        assert_eq!(escape_rtf("🎤"), "\\u-10180?\\u-8284?");
+    }
+}
@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use crate::ollama::GenerationOptions;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum TranscriptionMode {
@@ -25,6 +27,11 @@ impl TranscriptionMode {
         }
     }
 
+    /// Strict counterpart to `from_str` for ids coming from callers that must name a real mode
+    pub fn from_id(s: &str) -> Option<Self> {
+        Self::all_modes().into_iter().find(|m| m.as_str() == s)
+    }
+
     pub fn as_str(&self) -> &'static str {
         match self {
             Self::Default => "default",
@@ -37,6 +44,11 @@ impl TranscriptionMode {
         }
     }
 
+    /// Whether output is structured enough to paste as rich text (headings, lists)
+    pub fn rich_output(&self) -> bool {
+        matches!(self, Self::Email | Self::Bullets | Self::Summary | Self::MeetingNotes)
+    }
+
     pub fn display_name(&self) -> &'static str {
         match self {
             Self::Default => "Default",
@@ -98,6 +110,30 @@ Keep the EXACT SAME LANGUAGE as the input ({lang}). NEVER change the language. O
         }
     }
 
+    /// Default sampling options. Cleanup should stay close to the input, so every mode
+    /// runs cooler than Ollama's default of 0.8; free-form modes get a bit more room.
+    pub fn generation_options(&self) -> GenerationOptions {
+        let (temperature, num_predict) = match self {
+            Self::Default => (0.1, 1024),
+            Self::Email => (0.3, 1024),
+            Self::Bullets => (0.2, 768),
+            Self::Summary => (0.3, 512),
+            Self::Slack => (0.4, 256),
+            Self::MeetingNotes => (0.2, 1024),
+            Self::CodeComment => (0.2, 768),
+        };
+
+        GenerationOptions {
+            temperature: Some(temperature),
+            top_p: Some(0.9),
+            seed: None,
+            num_ctx: Some(4096),
+            num_predict: Some(num_predict),
+            stop: None,
+            keep_alive: None,
+        }
+    }
+
     pub fn requires_ollama(&self) -> bool {
         // All modes require Ollama for formatting, but default can fall back to raw text
         true
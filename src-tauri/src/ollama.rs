@@ -1,18 +1,101 @@
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
 
+use crate::error::{AppError, AppResult};
+use crate::languages;
 use crate::modes::TranscriptionMode;
+use crate::validation::validate_cleanup;
 
-const OLLAMA_API_URL: &str = "http://localhost:11434/api/generate";
-const DEFAULT_MODEL: &str = "gemma2:2b";
+const OLLAMA_BASE_URL: &str = "http://localhost:11434";
+pub const DEFAULT_MODEL: &str = "gemma2:2b";
+const DEFAULT_KEEP_ALIVE: &str = "10m";
+/// Fixed seed used in deterministic mode so the same transcript always cleans the same way
+const DETERMINISTIC_SEED: i64 = 42;
 
 #[derive(Debug, Serialize)]
-struct OllamaRequest {
+struct OllamaRequest<'a> {
     model: String,
     prompt: String,
     system: String,
     stream: bool,
     context: Option<Vec<i32>>, // Empty context to prevent history
+    options: ModelOptions<'a>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    keep_alive: Option<&'a str>,
+}
+
+/// Request with no prompt, which makes Ollama load the model and return immediately
+#[derive(Debug, Serialize)]
+struct PreloadRequest<'a> {
+    model: &'a str,
+    keep_alive: &'a str,
+}
+
+/// The `options` block of a generate request
+#[derive(Debug, Serialize)]
+struct ModelOptions<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    num_ctx: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    num_predict: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stop: Option<&'a [String]>,
+}
+
+/// Sampling and runtime options for a cleanup request. Unset fields use Ollama's defaults.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GenerationOptions {
+    #[serde(default)]
+    pub temperature: Option<f32>,
+    #[serde(default)]
+    pub top_p: Option<f32>,
+    #[serde(default)]
+    pub seed: Option<i64>,
+    #[serde(default)]
+    pub num_ctx: Option<u32>,
+    #[serde(default)]
+    pub num_predict: Option<i32>,
+    #[serde(default)]
+    pub stop: Option<Vec<String>>,
+    /// How long Ollama keeps the model in memory after a request, e.g. "10m" or "-1"
+    #[serde(default)]
+    pub keep_alive: Option<String>,
+}
+
+impl GenerationOptions {
+    /// Fields set in `overrides` win over the ones in `self`
+    pub fn merged(&self, overrides: &GenerationOptions) -> Self {
+        Self {
+            temperature: overrides.temperature.or(self.temperature),
+            top_p: overrides.top_p.or(self.top_p),
+            seed: overrides.seed.or(self.seed),
+            num_ctx: overrides.num_ctx.or(self.num_ctx),
+            num_predict: overrides.num_predict.or(self.num_predict),
+            stop: overrides.stop.clone().or_else(|| self.stop.clone()),
+            keep_alive: overrides.keep_alive.clone().or_else(|| self.keep_alive.clone()),
+        }
+    }
+
+    fn as_model_options(&self) -> ModelOptions<'_> {
+        ModelOptions {
+            temperature: self.temperature,
+            top_p: self.top_p,
+            seed: self.seed,
+            num_ctx: self.num_ctx,
+            num_predict: self.num_predict,
+            stop: self.stop.as_deref(),
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -21,19 +104,234 @@ struct OllamaResponse {
     done: bool,
 }
 
+#[derive(Debug, Deserialize)]
+struct TagsResponse {
+    #[serde(default)]
+    models: Vec<OllamaModel>,
+}
+
+/// A model installed in the local Ollama instance, as reported by `/api/tags`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OllamaModel {
+    pub name: String,
+    #[serde(default)]
+    pub size: u64,
+    #[serde(default)]
+    pub digest: String,
+    #[serde(default)]
+    pub modified_at: String,
+    #[serde(default)]
+    pub details: Option<OllamaModelDetails>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OllamaModelDetails {
+    #[serde(default)]
+    pub family: String,
+    #[serde(default)]
+    pub parameter_size: String,
+    #[serde(default)]
+    pub quantization_level: String,
+}
+
+/// Result of checking whether Ollama is running and the configured model is installed
+#[derive(Debug, Clone, Serialize)]
+pub struct OllamaStatus {
+    pub running: bool,
+    pub model: String,
+    pub model_installed: bool,
+    /// Seconds until cleanup resumes, while the circuit breaker is open
+    pub paused_for_secs: Option<u64>,
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct PullRequest<'a> {
+    model: &'a str,
+    stream: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct DeleteRequest<'a> {
+    model: &'a str,
+}
+
+/// One progress line streamed by `/api/pull`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PullProgress {
+    #[serde(default)]
+    pub status: String,
+    #[serde(default)]
+    pub digest: Option<String>,
+    #[serde(default)]
+    pub total: Option<u64>,
+    #[serde(default)]
+    pub completed: Option<u64>,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+/// Timeouts, retry and circuit-breaker settings for talking to Ollama
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OllamaConfig {
+    pub connect_timeout_ms: u64,
+    /// Upper bound for a single request, including generation. Model pulls are exempt.
+    pub request_timeout_ms: u64,
+    /// Extra attempts after a transient failure (connection refused, timeout, 5xx)
+    pub max_retries: u32,
+    /// Delay before the first retry; doubles on every further attempt
+    pub retry_backoff_ms: u64,
+    /// Consecutive failed cleanups before cleanup is skipped entirely
+    pub failure_threshold: u32,
+    /// How long cleanup stays skipped once the threshold is hit
+    pub cooldown_secs: u64,
+}
+
+impl Default for OllamaConfig {
+    fn default() -> Self {
+        Self {
+            connect_timeout_ms: 2_000,
+            request_timeout_ms: 30_000,
+            max_retries: 2,
+            retry_backoff_ms: 250,
+            failure_threshold: 3,
+            cooldown_secs: 60,
+        }
+    }
+}
+
+impl OllamaConfig {
+    pub fn validate(&self) -> AppResult<()> {
+        // A zero timeout fails every request before it is sent
+        if self.connect_timeout_ms == 0 || self.request_timeout_ms == 0 {
+            return Err(AppError::InvalidInput("Ollama timeouts must be greater than zero".to_string()));
+        }
+        // Zero would open the circuit breaker on the first failure, or on none at all
+        if self.failure_threshold == 0 {
+            return Err(AppError::InvalidInput("Ollama failure threshold must be at least 1".to_string()));
+        }
+        Ok(())
+    }
+
+    fn request_timeout(&self) -> Duration {
+        Duration::from_millis(self.request_timeout_ms)
+    }
+
+    fn build_client(&self) -> Client {
+        Client::builder()
+            .connect_timeout(Duration::from_millis(self.connect_timeout_ms))
+            .build()
+            .unwrap_or_default()
+    }
+}
+
+/// Circuit breaker state shared by all clones of a client
+#[derive(Debug, Default)]
+struct BreakerState {
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+}
+
 #[derive(Clone)]
 pub struct OllamaClient {
     client: Client,
     model: String,
     enabled: bool,
+    /// User overrides on top of each mode's defaults, keyed by mode id
+    mode_options: HashMap<String, GenerationOptions>,
+    deterministic: bool,
+    /// Use system prompts written in the transcript's language where available
+    localized_prompts: bool,
+    config: OllamaConfig,
+    breaker: Arc<Mutex<BreakerState>>,
+    /// Bumped to cancel every cleanup currently in flight
+    cancel_tx: Arc<watch::Sender<u64>>,
 }
 
 impl OllamaClient {
     pub fn new() -> Self {
+        let config = OllamaConfig::default();
         Self {
-            client: Client::new(),
+            client: config.build_client(),
             model: DEFAULT_MODEL.to_string(),
             enabled: true,
+            mode_options: HashMap::new(),
+            deterministic: false,
+            localized_prompts: true,
+            config,
+            breaker: Arc::new(Mutex::new(BreakerState::default())),
+            cancel_tx: Arc::new(watch::channel(0).0),
+        }
+    }
+
+    pub fn config(&self) -> &OllamaConfig {
+        &self.config
+    }
+
+    pub fn set_config(&mut self, config: OllamaConfig) {
+        self.client = config.build_client();
+        self.config = config;
+    }
+
+    /// Abort every cleanup that is currently waiting on Ollama
+    pub fn cancel_in_flight(&self) {
+        self.cancel_tx.send_modify(|generation| *generation += 1);
+    }
+
+    /// Seconds until cleanup is attempted again, if the circuit breaker is open
+    pub fn circuit_open_for(&self) -> Option<u64> {
+        let breaker = self.breaker.lock().unwrap();
+        breaker
+            .open_until
+            .and_then(|until| until.checked_duration_since(Instant::now()))
+            .map(|remaining| remaining.as_secs().max(1))
+    }
+
+    fn record_outcome<T>(&self, result: &AppResult<T>) {
+        let mut breaker = self.breaker.lock().unwrap();
+        match result {
+            Ok(_) => *breaker = BreakerState::default(),
+            // Only failures that say something about Ollama's health trip the breaker
+            Err(AppError::LlmUnavailable(_)) | Err(AppError::Timeout(_)) | Err(AppError::Llm(_)) => {
+                breaker.consecutive_failures += 1;
+                if breaker.consecutive_failures >= self.config.failure_threshold {
+                    log::warn!(
+                        "Ollama failed {} times in a row, skipping cleanup for {}s",
+                        breaker.consecutive_failures,
+                        self.config.cooldown_secs
+                    );
+                    breaker.open_until = Some(Instant::now() + Duration::from_secs(self.config.cooldown_secs));
+                    breaker.consecutive_failures = 0;
+                }
+            }
+            Err(_) => {}
+        }
+    }
+
+    /// Send a request, retrying connection failures and 5xx responses with exponential backoff
+    async fn send_with_retry<F>(&self, build: F, context: &str) -> AppResult<reqwest::Response>
+    where
+        F: Fn() -> reqwest::RequestBuilder,
+    {
+        let mut attempt = 0;
+        loop {
+            match build().send().await {
+                Ok(response) if response.status().is_success() => return Ok(response),
+                Ok(response) if response.status().is_server_error() && attempt < self.config.max_retries => {
+                    log::debug!("Ollama returned {}, retrying", response.status());
+                }
+                Ok(response) => return Err(response_error(response, Some(&self.model)).await),
+                // Anything else, e.g. a malformed request or body, fails the same way again
+                Err(e) if (e.is_connect() || e.is_timeout()) && attempt < self.config.max_retries => {
+                    log::debug!("{}: {}, retrying", context, e);
+                }
+                Err(e) => return Err(request_error(e, context)),
+            }
+
+            let backoff = self.config.retry_backoff_ms.saturating_mul(1 << attempt.min(10));
+            tokio::time::sleep(Duration::from_millis(backoff)).await;
+            attempt += 1;
         }
     }
 
@@ -41,6 +339,10 @@ impl OllamaClient {
         self.model = model.to_string();
     }
 
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
     pub fn set_enabled(&mut self, enabled: bool) {
         self.enabled = enabled;
     }
@@ -49,29 +351,178 @@ impl OllamaClient {
         self.enabled
     }
 
-    /// Check if Ollama is running and the model is available
-    pub async fn check_availability(&self) -> Result<bool, String> {
-        let url = "http://localhost:11434/api/tags";
-
-        match self.client.get(url).send().await {
-            Ok(response) => {
-                if response.status().is_success() {
-                    // Check if our model is available
-                    if let Ok(text) = response.text().await {
-                        Ok(text.contains(&self.model) || text.contains("models"))
+    pub fn set_deterministic(&mut self, deterministic: bool) {
+        self.deterministic = deterministic;
+    }
+
+    pub fn is_deterministic(&self) -> bool {
+        self.deterministic
+    }
+
+    pub fn set_localized_prompts(&mut self, localized: bool) {
+        self.localized_prompts = localized;
+    }
+
+    pub fn uses_localized_prompts(&self) -> bool {
+        self.localized_prompts
+    }
+
+    /// Replace the user overrides for a mode; `None` restores the mode's defaults
+    pub fn set_mode_options(&mut self, mode: &str, options: Option<GenerationOptions>) {
+        let key = TranscriptionMode::from_str(mode).as_str().to_string();
+        match options {
+            Some(options) => {
+                self.mode_options.insert(key, options);
+            }
+            None => {
+                self.mode_options.remove(&key);
+            }
+        }
+    }
+
+    /// The options actually sent for a mode: mode defaults, then user overrides, then
+    /// deterministic settings if enabled
+    pub fn options_for_mode(&self, mode: &str) -> GenerationOptions {
+        let transcription_mode = TranscriptionMode::from_str(mode);
+        let mut options = transcription_mode.generation_options();
+        if let Some(overrides) = self.mode_options.get(transcription_mode.as_str()) {
+            options = options.merged(overrides);
+        }
+        if self.deterministic {
+            options.temperature = Some(0.0);
+            options.seed = Some(DETERMINISTIC_SEED);
+        }
+        options
+    }
+
+    /// Load the model into memory ahead of time so the first cleanup doesn't pay the
+    /// cold-start cost
+    pub async fn preload(&self, mode: &str) -> AppResult<()> {
+        let options = self.options_for_mode(mode);
+        let keep_alive = options.keep_alive.as_deref().unwrap_or(DEFAULT_KEEP_ALIVE);
+
+        self.send_with_retry(
+            || {
+                self.client
+                    .post(format!("{}/api/generate", OLLAMA_BASE_URL))
+                    .timeout(self.config.request_timeout())
+                    .json(&PreloadRequest { model: &self.model, keep_alive })
+            },
+            "Failed to preload Ollama model",
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// List the models installed in the local Ollama instance
+    pub async fn list_models(&self) -> AppResult<Vec<OllamaModel>> {
+        let response = self
+            .client
+            .get(format!("{}/api/tags", OLLAMA_BASE_URL))
+            .timeout(self.config.request_timeout())
+            .send()
+            .await
+            .map_err(|e| request_error(e, "Failed to list Ollama models"))?;
+
+        if !response.status().is_success() {
+            return Err(response_error(response, None).await);
+        }
+
+        let tags: TagsResponse = response
+            .json()
+            .await
+            .map_err(|e| AppError::Llm(format!("Failed to parse Ollama model list: {}", e)))?;
+
+        Ok(tags.models)
+    }
+
+    /// Check if Ollama is running and whether the configured model is installed
+    pub async fn check_status(&self) -> OllamaStatus {
+        match self.list_models().await {
+            Ok(models) => {
+                let model_installed = models.iter().any(|m| model_matches(&m.name, &self.model));
+                OllamaStatus {
+                    running: true,
+                    model: self.model.clone(),
+                    model_installed,
+                    paused_for_secs: self.circuit_open_for(),
+                    message: if model_installed {
+                        None
                     } else {
-                        Ok(true) // Ollama is running, assume model is available
-                    }
-                } else {
-                    Ok(false)
+                        Some(format!(
+                            "Model '{}' is not installed. Pull it from settings or run `ollama pull {}`.",
+                            self.model, self.model
+                        ))
+                    },
                 }
             }
-            Err(_) => Ok(false),
+            Err(e) => OllamaStatus {
+                running: false,
+                model: self.model.clone(),
+                model_installed: false,
+                paused_for_secs: self.circuit_open_for(),
+                message: Some(e.to_string()),
+            },
+        }
+    }
+
+    /// Check if Ollama is running and the model is available
+    pub async fn check_availability(&self) -> AppResult<bool> {
+        let status = self.check_status().await;
+        Ok(status.running && status.model_installed)
+    }
+
+    /// Pull a model, calling `on_progress` for every progress line Ollama streams back
+    pub async fn pull_model<F>(&self, model: &str, mut on_progress: F) -> AppResult<()>
+    where
+        F: FnMut(PullProgress),
+    {
+        let mut response = self
+            .client
+            .post(format!("{}/api/pull", OLLAMA_BASE_URL))
+            .json(&PullRequest { model, stream: true })
+            .send()
+            .await
+            .map_err(|e| request_error(e, &format!("Failed to start pulling '{}'", model)))?;
+
+        if !response.status().is_success() {
+            return Err(response_error(response, None).await);
+        }
+
+        let mut buffer: Vec<u8> = Vec::new();
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| request_error(e, "Failed to read pull progress"))?
+        {
+            handle_pull_chunk(&mut buffer, &chunk, &mut on_progress)?;
+        }
+        handle_pull_line(&buffer, &mut on_progress)?;
+
+        Ok(())
+    }
+
+    /// Delete an installed model
+    pub async fn delete_model(&self, model: &str) -> AppResult<()> {
+        let response = self
+            .client
+            .delete(format!("{}/api/delete", OLLAMA_BASE_URL))
+            .timeout(self.config.request_timeout())
+            .json(&DeleteRequest { model })
+            .send()
+            .await
+            .map_err(|e| request_error(e, &format!("Failed to delete '{}'", model)))?;
+
+        if !response.status().is_success() {
+            return Err(response_error(response, Some(model)).await);
         }
+
+        Ok(())
     }
 
     /// Clean up the transcript using Ollama with the specified mode
-    pub async fn cleanup_text(&self, text: &str, language: Option<&str>, mode: &str) -> Result<String, String> {
+    pub async fn cleanup_text(&self, text: &str, language: Option<&str>, mode: &str) -> AppResult<String> {
         if !self.enabled {
             return Ok(text.to_string());
         }
@@ -80,28 +531,36 @@ impl OllamaClient {
             return Ok(text.to_string());
         }
 
+        if let Some(remaining) = self.circuit_open_for() {
+            return Err(AppError::LlmUnavailable(format!(
+                "AI cleanup paused after repeated Ollama failures, retrying in {}s",
+                remaining
+            )));
+        }
+
+        // Subscribing marks the current generation as seen, so only later cancels count
+        let mut cancel_rx = self.cancel_tx.subscribe();
+        let result = tokio::select! {
+            result = self.run_cleanup(text, language, mode) => result,
+            _ = cancel_rx.changed() => Err(AppError::Cancelled("Cleanup cancelled".to_string())),
+        };
+
+        self.record_outcome(&result);
+        result
+    }
+
+    async fn run_cleanup(&self, text: &str, language: Option<&str>, mode: &str) -> AppResult<String> {
         let transcription_mode = TranscriptionMode::from_str(mode);
 
         // Build a prompt that explicitly states the language
-        let lang_name = language.map(|l| match l {
-            "fr" => "French",
-            "es" => "Spanish",
-            "de" => "German",
-            "it" => "Italian",
-            "pt" => "Portuguese",
-            "nl" => "Dutch",
-            "ru" => "Russian",
-            "zh" => "Chinese",
-            "ja" => "Japanese",
-            "ko" => "Korean",
-            "ar" => "Arabic",
-            "en" => "English",
-            _ => l,
-        }).unwrap_or("the same language");
-
-        let system_prompt = transcription_mode.get_system_prompt(lang_name);
-
-        let prompt = match transcription_mode {
+        let lang_name = language
+            .and_then(languages::find)
+            .map(|l| l.name)
+            .unwrap_or("the same language");
+
+        let mut system_prompt = transcription_mode.get_system_prompt(lang_name);
+
+        let mut prompt = match transcription_mode {
             TranscriptionMode::Default => format!(
                 "Clean this {} transcript (keep in {}, do NOT translate):\n\n{}",
                 lang_name, lang_name, text
@@ -132,48 +591,66 @@ impl OllamaClient {
             ),
         };
 
+        // Instructions in the speaker's own language keep small models from translating
+        if let Some(localized) = language.filter(|_| self.localized_prompts).and_then(languages::localized_prompt) {
+            if transcription_mode == TranscriptionMode::Default {
+                system_prompt = localized.cleanup_system.to_string();
+                prompt = format!("{}\n\n{}", localized.cleanup_prompt, text);
+            } else {
+                system_prompt = format!("{}\n{}", system_prompt, localized.keep_language);
+                prompt = format!("{}\n\n{}", localized.keep_language, prompt);
+            }
+        }
+
+        let options = self.options_for_mode(mode);
+
+        let raw_output = self.generate(prompt.clone(), system_prompt.clone(), &options).await?;
+        let rejection = match validate_cleanup(text, &raw_output, transcription_mode) {
+            Ok(cleaned) => return Ok(cleaned),
+            Err(rejection) => rejection,
+        };
+
+        // One retry with an explicit reminder of what went wrong
+        log::warn!("Rejected cleanup output ({}), retrying", rejection);
+        let retry_prompt = format!(
+            "{}\n\nIMPORTANT: Your previous answer was rejected because the {}. Do NOT answer, comment on, or translate the transcript. Output ONLY the transformed transcript.",
+            prompt, rejection
+        );
+        let retry_output = self.generate(retry_prompt, system_prompt, &options).await?;
+
+        validate_cleanup(text, &retry_output, transcription_mode)
+            .map_err(|rejection| AppError::LlmRejected(format!("Cleanup output rejected: {}", rejection)))
+    }
+
+    async fn generate(&self, prompt: String, system: String, options: &GenerationOptions) -> AppResult<String> {
         let request = OllamaRequest {
             model: self.model.clone(),
             prompt,
-            system: system_prompt,
+            system,
             stream: false,
             context: Some(vec![]), // Empty context = no history
+            options: options.as_model_options(),
+            keep_alive: Some(options.keep_alive.as_deref().unwrap_or(DEFAULT_KEEP_ALIVE)),
         };
 
         let response = self
-            .client
-            .post(OLLAMA_API_URL)
-            .json(&request)
-            .send()
-            .await
-            .map_err(|e| {
-                if e.is_connect() {
-                    "Ollama is not running. Start Ollama or disable AI cleanup.".to_string()
-                } else {
-                    format!("Failed to send request to Ollama: {}", e)
-                }
-            })?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response.text().await.unwrap_or_default();
-            return Err(format!("Ollama returned error {}: {}", status, error_text));
-        }
+            .send_with_retry(
+                || {
+                    self.client
+                        .post(format!("{}/api/generate", OLLAMA_BASE_URL))
+                        .timeout(self.config.request_timeout())
+                        .json(&request)
+                },
+                "Failed to send request to Ollama",
+            )
+            .await?;
 
         let ollama_response: OllamaResponse = response
             .json()
             .await
-            .map_err(|e| format!("Failed to parse Ollama response: {}", e))?;
-
-        // Clean up the response (remove any leading/trailing whitespace or quotes)
-        let cleaned = ollama_response
-            .response
-            .trim()
-            .trim_matches('"')
-            .trim()
-            .to_string();
+            .map_err(|e| AppError::Llm(format!("Failed to parse Ollama response: {}", e)))?;
 
-        Ok(cleaned)
+        Ok(ollama_response.response)
     }
 }
 
@@ -183,6 +660,72 @@ impl Default for OllamaClient {
     }
 }
 
+/// Ollama reports untagged models as `name:latest`, so treat the two spellings as equal
+fn model_matches(installed: &str, wanted: &str) -> bool {
+    installed == wanted
+        || installed.strip_suffix(":latest") == Some(wanted)
+        || wanted.strip_suffix(":latest") == Some(installed)
+}
+
+/// Map a transport-level failure to the matching error kind
+fn request_error(e: reqwest::Error, context: &str) -> AppError {
+    if e.is_connect() {
+        AppError::LlmUnavailable("Ollama is not running. Start Ollama or disable AI cleanup.".to_string())
+    } else if e.is_timeout() {
+        AppError::Timeout(format!("{}: Ollama did not respond in time", context))
+    } else {
+        AppError::Llm(format!("{}: {}", context, e))
+    }
+}
+
+/// Map a non-success HTTP response to the matching error kind. `model` is set for
+/// endpoints that act on one model, where a 404 means it isn't installed.
+async fn response_error(response: reqwest::Response, model: Option<&str>) -> AppError {
+    let status = response.status();
+    let error_text = response.text().await.unwrap_or_default();
+
+    if let (reqwest::StatusCode::NOT_FOUND, Some(model)) = (status, model) {
+        AppError::LlmModelMissing(format!("Model '{}' is not installed", model))
+    } else {
+        AppError::Llm(format!("Ollama returned error {}: {}", status, error_text))
+    }
+}
+
+/// Feed a chunk of the pull response into `buffer`, handling every line it completes.
+/// The body is newline-delimited JSON and chunks may split or join lines.
+fn handle_pull_chunk<F>(buffer: &mut Vec<u8>, chunk: &[u8], on_progress: &mut F) -> AppResult<()>
+where
+    F: FnMut(PullProgress),
+{
+    buffer.extend_from_slice(chunk);
+    while let Some(pos) = buffer.iter().position(|&b| b == b'\n') {
+        let line: Vec<u8> = buffer.drain(..=pos).collect();
+        handle_pull_line(&line, on_progress)?;
+    }
+    Ok(())
+}
+
+fn handle_pull_line<F>(line: &[u8], on_progress: &mut F) -> AppResult<()>
+where
+    F: FnMut(PullProgress),
+{
+    let line = String::from_utf8_lossy(line);
+    let line = line.trim();
+    if line.is_empty() {
+        return Ok(());
+    }
+
+    let progress: PullProgress = serde_json::from_str(line)
+        .map_err(|e| AppError::Llm(format!("Failed to parse pull progress: {}", e)))?;
+
+    if let Some(error) = &progress.error {
+        return Err(AppError::Llm(format!("Ollama failed to pull model: {}", error)));
+    }
+
+    on_progress(progress);
+    Ok(())
+}
+
 /// List of recommended models for text cleanup
 pub fn get_recommended_models() -> Vec<(&'static str, &'static str)> {
     vec![
@@ -192,3 +735,227 @@ pub fn get_recommended_models() -> Vec<(&'static str, &'static str)> {
         ("grmr", "Grammar-focused (experimental)"),
     ]
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn client_with(config: OllamaConfig) -> OllamaClient {
+        let mut client = OllamaClient::new();
+        client.set_config(config);
+        client
+    }
+
+    fn failure(error: AppError) -> AppResult<()> {
+        Err(error)
+    }
+
+    #[test]
+    fn config_rejects_zero_timeouts_and_threshold() {
+        assert!(OllamaConfig::default().validate().is_ok());
+        for config in [
+            OllamaConfig {
+                connect_timeout_ms: 0,
+                ..Default::default()
+            },
+            OllamaConfig {
+                request_timeout_ms: 0,
+                ..Default::default()
+            },
+            OllamaConfig {
+                failure_threshold: 0,
+                ..Default::default()
+            },
+        ] {
+            assert!(config.validate().is_err(), "{:?}", config);
+        }
+    }
+
+    #[test]
+    fn breaker_opens_after_consecutive_failures() {
+        let client = client_with(OllamaConfig {
+            failure_threshold: 2,
+            cooldown_secs: 60,
+            ..Default::default()
+        });
+        client.record_outcome(&failure(AppError::Timeout("slow".to_string())));
+        assert_eq!(client.circuit_open_for(), None);
+
+        client.record_outcome(&failure(AppError::LlmUnavailable("down".to_string())));
+        assert!(client
+            .circuit_open_for()
+            .is_some_and(|secs| (1..=60).contains(&secs)));
+        // Every clone sees the same breaker
+        assert!(client.clone().circuit_open_for().is_some());
+    }
+
+    #[test]
+    fn success_resets_the_failure_count() {
+        let client = client_with(OllamaConfig {
+            failure_threshold: 2,
+            ..Default::default()
+        });
+        client.record_outcome(&failure(AppError::Llm("bad".to_string())));
+        client.record_outcome(&Ok(()));
+        client.record_outcome(&failure(AppError::Llm("bad".to_string())));
+        assert_eq!(client.circuit_open_for(), None);
+    }
+
+    #[test]
+    fn failures_unrelated_to_ollama_health_are_ignored() {
+        let client = client_with(OllamaConfig {
+            failure_threshold: 1,
+            ..Default::default()
+        });
+        client.record_outcome(&failure(AppError::LlmRejected(
+            "echoed the prompt".to_string(),
+        )));
+        client.record_outcome(&failure(AppError::LlmModelMissing(
+            "not installed".to_string(),
+        )));
+        client.record_outcome(&failure(AppError::Cancelled("cancelled".to_string())));
+        assert_eq!(client.circuit_open_for(), None);
+    }
+
+    /// Answer one request per status, in order, on a local port. Returns the base
+    /// URL and the number of requests answered so far.
+    fn serve(statuses: &[u16]) -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let served = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&served);
+        let statuses = statuses.to_vec();
+        std::thread::spawn(move || {
+            for status in statuses {
+                let Ok((mut stream, _)) = listener.accept() else {
+                    return;
+                };
+                let _ = stream.read(&mut [0; 4096]);
+                counter.fetch_add(1, Ordering::SeqCst);
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 {} X\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                    status
+                );
+            }
+        });
+        (url, served)
+    }
+
+    async fn send(statuses: &[u16]) -> (AppResult<reqwest::Response>, usize) {
+        let client = client_with(OllamaConfig {
+            max_retries: 2,
+            retry_backoff_ms: 1,
+            ..Default::default()
+        });
+        let (url, served) = serve(statuses);
+        let result = client
+            .send_with_retry(|| client.client.get(&url), "test")
+            .await;
+        (result, served.load(Ordering::SeqCst))
+    }
+
+    #[tokio::test]
+    async fn server_errors_are_retried() {
+        let (result, served) = send(&[503, 500, 200]).await;
+        assert!(result.is_ok());
+        assert_eq!(served, 3);
+    }
+
+    #[tokio::test]
+    async fn retries_stop_at_the_limit() {
+        let (result, served) = send(&[500, 500, 500, 200]).await;
+        assert!(matches!(result, Err(AppError::Llm(_))));
+        assert_eq!(served, 3);
+    }
+
+    #[tokio::test]
+    async fn client_errors_are_not_retried() {
+        let (result, served) = send(&[400, 200]).await;
+        assert!(matches!(result, Err(AppError::Llm(_))));
+        assert_eq!(served, 1);
+
+        let (result, served) = send(&[404, 200]).await;
+        assert!(matches!(result, Err(AppError::LlmModelMissing(_))));
+        assert_eq!(served, 1);
+    }
+
+    /// Statuses reported while feeding `chunks` through the pull parser, then the rest of the buffer
+    fn pull_statuses(chunks: &[&str]) -> (AppResult<()>, Vec<String>) {
+        let mut statuses = Vec::new();
+        let mut on_progress = |p: PullProgress| statuses.push(p.status);
+        let mut buffer = Vec::new();
+        let result = chunks
+            .iter()
+            .try_for_each(|chunk| {
+                handle_pull_chunk(&mut buffer, chunk.as_bytes(), &mut on_progress)
+            })
+            .and_then(|()| handle_pull_line(&buffer, &mut on_progress));
+        (result, statuses)
+    }
+
+    #[test]
+    fn pull_lines_split_across_chunks_are_joined() {
+        let (result, statuses) = pull_statuses(&[
+            "{\"status\":\"pulling manifest\"}\n{\"status\":\"down",
+            "loading\",\"total\":10,",
+            "\"completed\":5}\n",
+            "{\"status\":\"success\"}",
+        ]);
+        assert!(result.is_ok());
+        assert_eq!(statuses, ["pulling manifest", "downloading", "success"]);
+    }
+
+    #[test]
+    fn pull_error_lines_fail_the_pull() {
+        let (result, statuses) = pull_statuses(&[
+            "{\"status\":\"pulling manifest\"}\n",
+            "{\"error\":\"pull model manifest: file does not exist\"}\n",
+        ]);
+        assert!(
+            matches!(result, Err(AppError::Llm(message)) if message.contains("file does not exist"))
+        );
+        assert_eq!(statuses, ["pulling manifest"]);
+    }
+
+    #[test]
+    fn pull_skips_blank_lines_and_rejects_garbage() {
+        assert!(pull_statuses(&["\n\r\n", "{\"status\":\"success\"}\n\n"])
+            .0
+            .is_ok());
+        assert!(pull_statuses(&["not json\n"]).0.is_err());
+    }
+
+    #[test]
+    fn untagged_models_match_latest() {
+        assert!(model_matches("gemma2:2b", "gemma2:2b"));
+        assert!(model_matches("grmr:latest", "grmr"));
+        assert!(model_matches("grmr", "grmr:latest"));
+        assert!(!model_matches("gemma2:2b", "gemma2"));
+        assert!(!model_matches("gemma2:latest", "gemma2:2b"));
+        assert!(!model_matches("llama3.1:8b", "llama3"));
+    }
+
+    #[tokio::test]
+    async fn refused_connections_report_ollama_unavailable() {
+        // Bind and drop a listener so the port is known to be closed
+        let port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let client = client_with(OllamaConfig {
+            max_retries: 1,
+            retry_backoff_ms: 1,
+            ..Default::default()
+        });
+        let url = format!("http://127.0.0.1:{}", port);
+        let result = client
+            .send_with_retry(|| client.client.get(&url), "test")
+            .await;
+        assert!(matches!(result, Err(AppError::LlmUnavailable(_))));
+    }
+}
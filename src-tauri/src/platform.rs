@@ -0,0 +1,58 @@
+use serde::Serialize;
+
+/// The windowing system the app runs under. Only Linux has a choice.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DisplayServer {
+    /// macOS or Windows
+    Native,
+    X11,
+    Wayland,
+    /// No display, e.g. the CLI over SSH
+    Headless,
+}
+
+impl DisplayServer {
+    /// Wayland clients can't grab keys globally; shortcuts only fire while an
+    /// XWayland window has focus
+    pub fn global_shortcuts(&self) -> bool {
+        matches!(self, Self::Native | Self::X11)
+    }
+
+    /// Wayland compositors place windows themselves
+    pub fn can_position_windows(&self) -> bool {
+        matches!(self, Self::Native | Self::X11)
+    }
+}
+
+pub fn display_server() -> DisplayServer {
+    if !cfg!(target_os = "linux") {
+        return DisplayServer::Native;
+    }
+    // XWayland sets DISPLAY as well, so Wayland wins when both are present
+    let session_type = std::env::var("XDG_SESSION_TYPE").unwrap_or_default();
+    if std::env::var_os("WAYLAND_DISPLAY").is_some() || session_type == "wayland" {
+        DisplayServer::Wayland
+    } else if std::env::var_os("DISPLAY").is_some() || session_type == "x11" {
+        DisplayServer::X11
+    } else {
+        DisplayServer::Headless
+    }
+}
+
+/// What works on this machine, so the UI can explain missing features
+#[derive(Debug, Clone, Serialize)]
+pub struct PlatformInfo {
+    pub os: &'static str,
+    pub display_server: DisplayServer,
+    pub global_shortcuts: bool,
+}
+
+pub fn info() -> PlatformInfo {
+    let display_server = display_server();
+    PlatformInfo {
+        os: std::env::consts::OS,
+        display_server,
+        global_shortcuts: display_server.global_shortcuts(),
+    }
+}
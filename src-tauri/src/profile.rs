@@ -0,0 +1,389 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use crate::clipboard::ClipboardSettings;
+use crate::dsp::DspSettings;
+use crate::error::{AppError, AppResult};
+use crate::history::now_ms;
+use crate::hotkeys::{HotkeyBinding, RecordingSettings};
+use crate::inject::InjectionMethod;
+use crate::ollama::GenerationOptions;
+use crate::settings::{self, Settings};
+use crate::whisper;
+
+const FORMAT: &str = "yap-to-text-profile";
+pub const PROFILE_VERSION: u32 = 2;
+
+/// A shareable bundle of settings. Every section is optional so a team can publish
+/// just the parts it wants to standardize.
+///
+/// Machine-specific settings (the input device, where models live on disk) are left
+/// out; the Whisper model is referenced by file name and resolved against the local
+/// models directory on import.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Profile {
+    pub format: String,
+    pub version: u32,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub exported_at: Option<u64>,
+    #[serde(default)]
+    pub hotkeys: Option<Vec<HotkeyBinding>>,
+    #[serde(default)]
+    pub recording: Option<RecordingSettings>,
+    #[serde(default)]
+    pub mode: Option<String>,
+    #[serde(default)]
+    pub ollama_enabled: Option<bool>,
+    #[serde(default)]
+    pub ollama_model: Option<String>,
+    #[serde(default)]
+    pub whisper_model: Option<String>,
+    #[serde(default)]
+    pub deterministic: Option<bool>,
+    #[serde(default)]
+    pub localized_prompts: Option<bool>,
+    #[serde(default)]
+    pub mode_options: Option<HashMap<String, GenerationOptions>>,
+    #[serde(default)]
+    pub injection: Option<InjectionMethod>,
+    #[serde(default)]
+    pub clipboard: Option<ClipboardSettings>,
+    #[serde(default)]
+    pub dsp: Option<DspSettings>,
+}
+
+impl Profile {
+    pub fn from_settings(settings: &Settings, name: Option<String>) -> Self {
+        Self {
+            format: FORMAT.to_string(),
+            version: PROFILE_VERSION,
+            name,
+            exported_at: Some(now_ms()),
+            hotkeys: Some(settings.hotkeys.clone()),
+            recording: Some(settings.recording.clone()),
+            mode: Some(settings.mode.clone()),
+            ollama_enabled: Some(settings.ollama.enabled),
+            ollama_model: Some(settings.ollama.model.clone()),
+            whisper_model: settings
+                .whisper_model_path
+                .as_deref()
+                .and_then(|p| Path::new(p).file_name())
+                .map(|f| f.to_string_lossy().to_string()),
+            deterministic: Some(settings.post_processing.deterministic),
+            localized_prompts: Some(settings.post_processing.localized_prompts),
+            mode_options: Some(settings.post_processing.mode_options.clone()),
+            injection: Some(settings.injection),
+            clipboard: Some(settings.clipboard.clone()),
+            dsp: Some(settings.dsp.clone()),
+        }
+    }
+
+    pub fn parse(json: &str) -> AppResult<Self> {
+        let mut value: Value = serde_json::from_str(json)
+            .map_err(|e| AppError::InvalidInput(format!("Not a valid profile: {}", e)))?;
+        if value.get("version").and_then(Value::as_u64).unwrap_or(0) < 2 {
+            settings::upgrade_single_hotkey(&mut value);
+        }
+        let profile: Profile = serde_json::from_value(value)
+            .map_err(|e| AppError::InvalidInput(format!("Not a valid profile: {}", e)))?;
+        if profile.format != FORMAT {
+            return Err(AppError::InvalidInput(format!("Not a yap-to-text profile (format '{}')", profile.format)));
+        }
+        if profile.version > PROFILE_VERSION {
+            return Err(AppError::InvalidInput(format!(
+                "Profile was written by a newer version of the app (version {})",
+                profile.version
+            )));
+        }
+        Ok(profile)
+    }
+
+    pub fn to_json(&self) -> AppResult<String> {
+        serde_json::to_string_pretty(self)
+            .map_err(|e| AppError::Internal(format!("Failed to serialize profile: {}", e)))
+    }
+
+    /// Every setting the profile would change, keyed by a dotted field name
+    fn fields(&self) -> Vec<(String, Value)> {
+        let mut fields = Vec::new();
+        let mut push = |name: &str, value: Value| fields.push((name.to_string(), value));
+
+        if let Some(hotkeys) = &self.hotkeys {
+            push("hotkeys", to_value(hotkeys));
+        }
+        if let Some(recording) = &self.recording {
+            push("recording", to_value(recording));
+        }
+        if let Some(mode) = &self.mode {
+            push("mode", to_value(mode));
+        }
+        if let Some(enabled) = self.ollama_enabled {
+            push("ollama.enabled", to_value(enabled));
+        }
+        if let Some(model) = &self.ollama_model {
+            push("ollama.model", to_value(model));
+        }
+        if let Some(model) = &self.whisper_model {
+            push("whisper_model", to_value(model));
+        }
+        if let Some(deterministic) = self.deterministic {
+            push("post_processing.deterministic", to_value(deterministic));
+        }
+        if let Some(localized) = self.localized_prompts {
+            push("post_processing.localized_prompts", to_value(localized));
+        }
+        if let Some(options) = &self.mode_options {
+            let mut modes: Vec<_> = options.keys().collect();
+            modes.sort();
+            for mode in modes {
+                push(&format!("post_processing.mode_options.{}", mode), to_value(&options[mode]));
+            }
+        }
+        if let Some(injection) = self.injection {
+            push("injection", to_value(injection));
+        }
+        if let Some(clipboard) = &self.clipboard {
+            push("clipboard", to_value(clipboard));
+        }
+        if let Some(dsp) = &self.dsp {
+            push("dsp", to_value(dsp));
+        }
+        fields
+    }
+}
+
+/// What to do when a profile field differs from the current setting
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictStrategy {
+    /// Take the profile's value
+    Overwrite,
+    /// Keep the current value
+    KeepExisting,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ImportOptions {
+    pub strategy: ConflictStrategy,
+    /// Per-field choices that win over `strategy`, keyed like `Conflict::field`
+    #[serde(default)]
+    pub overrides: HashMap<String, ConflictStrategy>,
+}
+
+impl ImportOptions {
+    pub fn new(strategy: ConflictStrategy) -> Self {
+        Self {
+            strategy,
+            overrides: HashMap::new(),
+        }
+    }
+
+    fn strategy_for(&self, field: &str) -> ConflictStrategy {
+        self.overrides.get(field).copied().unwrap_or(self.strategy)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Conflict {
+    pub field: String,
+    pub current: Value,
+    pub incoming: Value,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportReport {
+    pub settings: Settings,
+    /// Fields taken from the profile
+    pub applied: Vec<String>,
+    /// Fields that differed but were kept
+    pub kept: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+/// Fields where the profile disagrees with the current settings
+pub fn conflicts(profile: &Profile, current: &Settings) -> Vec<Conflict> {
+    let current_fields: HashMap<String, Value> = Profile::from_settings(current, None).fields().into_iter().collect();
+
+    profile
+        .fields()
+        .into_iter()
+        .filter_map(|(field, incoming)| {
+            let current = current_fields.get(&field).cloned().unwrap_or(Value::Null);
+            (current != incoming).then_some(Conflict { field, current, incoming })
+        })
+        .collect()
+}
+
+/// Merge a profile into `current`. The result is validated but not saved.
+pub fn import(profile: &Profile, current: &Settings, options: &ImportOptions) -> AppResult<ImportReport> {
+    let mut settings = current.clone();
+    let mut applied = Vec::new();
+    let mut kept = Vec::new();
+    let mut warnings = Vec::new();
+
+    for conflict in conflicts(profile, current) {
+        if options.strategy_for(&conflict.field) == ConflictStrategy::KeepExisting {
+            kept.push(conflict.field);
+            continue;
+        }
+
+        match conflict.field.as_str() {
+            "hotkeys" => settings.hotkeys = profile.hotkeys.clone().unwrap_or_default(),
+            "recording" => settings.recording = profile.recording.clone().unwrap_or_default(),
+            "mode" => settings.mode = profile.mode.clone().unwrap_or_default(),
+            "ollama.enabled" => settings.ollama.enabled = profile.ollama_enabled.unwrap_or(settings.ollama.enabled),
+            "ollama.model" => settings.ollama.model = profile.ollama_model.clone().unwrap_or_default(),
+            "whisper_model" => {
+                let file = profile.whisper_model.as_deref().unwrap_or_default();
+                let path = whisper::get_models_dir().join(file);
+                if file.contains(['/', '\\']) || !path.exists() {
+                    warnings.push(format!(
+                        "Whisper model '{}' is not in the models directory; download it, then load it in settings",
+                        file
+                    ));
+                    continue;
+                }
+                settings.whisper_model_path = Some(path.to_string_lossy().to_string());
+            }
+            "post_processing.deterministic" => {
+                settings.post_processing.deterministic = profile.deterministic.unwrap_or_default();
+            }
+            "injection" => settings.injection = profile.injection.unwrap_or_default(),
+            "clipboard" => settings.clipboard = profile.clipboard.clone().unwrap_or_default(),
+            "dsp" => settings.dsp = profile.dsp.clone().unwrap_or_default(),
+            "post_processing.localized_prompts" => {
+                settings.post_processing.localized_prompts = profile.localized_prompts.unwrap_or(true);
+            }
+            field => {
+                let Some(mode) = field.strip_prefix("post_processing.mode_options.") else {
+                    continue;
+                };
+                if let Some(options) = profile.mode_options.as_ref().and_then(|o| o.get(mode)) {
+                    settings.post_processing.mode_options.insert(mode.to_string(), options.clone());
+                }
+            }
+        }
+        applied.push(conflict.field);
+    }
+
+    settings.validate()?;
+
+    Ok(ImportReport {
+        settings,
+        applied,
+        kept,
+        warnings,
+    })
+}
+
+pub fn read_profile(path: &Path) -> AppResult<Profile> {
+    Profile::parse(&fs::read_to_string(path)?)
+}
+
+pub fn write_profile(path: &Path, profile: &Profile) -> AppResult<()> {
+    fs::write(path, profile.to_json()?)?;
+    Ok(())
+}
+
+fn to_value<T: Serialize>(value: T) -> Value {
+    serde_json::to_value(value).unwrap_or(Value::Null)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(fields: &str) -> Profile {
+        Profile::parse(&format!(r#"{{ "format": "{}", "version": {}, {} }}"#, FORMAT, PROFILE_VERSION, fields)).unwrap()
+    }
+
+    fn fields(conflicts: &[Conflict]) -> Vec<&str> {
+        conflicts.iter().map(|c| c.field.as_str()).collect()
+    }
+
+    #[test]
+    fn exported_profile_matches_its_settings() {
+        let settings = Settings::default();
+        let exported = Profile::parse(&Profile::from_settings(&settings, Some("team".to_string())).to_json().unwrap()).unwrap();
+        assert_eq!(exported.name.as_deref(), Some("team"));
+        assert!(conflicts(&exported, &settings).is_empty());
+    }
+
+    #[test]
+    fn parse_rejects_other_formats_and_newer_versions() {
+        assert!(Profile::parse(r#"{ "format": "something-else", "version": 1 }"#).is_err());
+        let newer = format!(r#"{{ "format": "{}", "version": {} }}"#, FORMAT, PROFILE_VERSION + 1);
+        assert!(Profile::parse(&newer).is_err());
+        assert!(Profile::parse("not json").is_err());
+    }
+
+    #[test]
+    fn version_one_hotkey_becomes_a_binding() {
+        let old = format!(
+            r#"{{ "format": "{}", "version": 1, "hotkey": {{ "key": "Space", "modifiers": ["Alt"], "enabled": true }} }}"#,
+            FORMAT
+        );
+        let hotkeys = Profile::parse(&old).unwrap().hotkeys.unwrap();
+        assert_eq!(hotkeys, vec![HotkeyBinding::record("Space", &["Alt"])]);
+    }
+
+    #[test]
+    fn conflicts_list_only_differing_fields() {
+        let current = Settings::default();
+        let incoming = profile(&format!(
+            r#""mode": "email", "ollama_model": "{}", "deterministic": {}"#,
+            current.ollama.model,
+            !current.post_processing.deterministic
+        ));
+        assert_eq!(fields(&conflicts(&incoming, &current)), ["mode", "post_processing.deterministic"]);
+    }
+
+    #[test]
+    fn strategies_and_overrides_decide_each_field() {
+        let current = Settings::default();
+        let incoming = profile(&format!(
+            r#""mode": "email", "deterministic": {}"#,
+            !current.post_processing.deterministic
+        ));
+
+        let overwrite = import(&incoming, &current, &ImportOptions::new(ConflictStrategy::Overwrite)).unwrap();
+        assert_eq!(overwrite.applied, ["mode", "post_processing.deterministic"]);
+        assert_eq!(overwrite.settings.mode, "email");
+        assert_ne!(overwrite.settings.post_processing.deterministic, current.post_processing.deterministic);
+
+        let keep = import(&incoming, &current, &ImportOptions::new(ConflictStrategy::KeepExisting)).unwrap();
+        assert!(keep.applied.is_empty());
+        assert_eq!(keep.kept, ["mode", "post_processing.deterministic"]);
+        assert_eq!(keep.settings, current);
+
+        let mut options = ImportOptions::new(ConflictStrategy::KeepExisting);
+        options.overrides.insert("mode".to_string(), ConflictStrategy::Overwrite);
+        let mixed = import(&incoming, &current, &options).unwrap();
+        assert_eq!(mixed.applied, ["mode"]);
+        assert_eq!(mixed.kept, ["post_processing.deterministic"]);
+        assert_eq!(mixed.settings.mode, "email");
+    }
+
+    #[test]
+    fn missing_whisper_model_is_a_warning() {
+        let current = Settings::default();
+        let incoming = profile(r#""whisper_model": "ggml-not-downloaded.bin""#);
+        let report = import(&incoming, &current, &ImportOptions::new(ConflictStrategy::Overwrite)).unwrap();
+
+        assert!(report.applied.is_empty());
+        assert_eq!(report.warnings.len(), 1);
+        assert_eq!(report.settings.whisper_model_path, current.whisper_model_path);
+    }
+
+    #[test]
+    fn invalid_merged_settings_are_rejected() {
+        let incoming = profile(r#""clipboard": { "restore": true, "restore_delay_ms": 5 }"#);
+        let options = ImportOptions::new(ConflictStrategy::Overwrite);
+        assert!(import(&incoming, &Settings::default(), &options).is_err());
+    }
+}
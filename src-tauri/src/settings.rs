@@ -0,0 +1,435 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::archive::ArchiveSettings;
+use crate::clipboard::ClipboardSettings;
+use crate::dsp::DspSettings;
+use crate::error::{AppError, AppResult};
+use crate::history::RetentionPolicy;
+use crate::hotkeys::{self, HotkeyBinding, RecordingSettings};
+use crate::inject::InjectionMethod;
+use crate::modes::TranscriptionMode;
+use crate::ollama;
+
+/// Bump when the on-disk layout changes, and add a step to `MIGRATIONS`
+pub const SCHEMA_VERSION: u32 = 2;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OllamaSettings {
+    pub enabled: bool,
+    pub model: String,
+    /// Timeouts, retries and the circuit breaker
+    pub connection: ollama::OllamaConfig,
+}
+
+impl Default for OllamaSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            model: ollama::DEFAULT_MODEL.to_string(),
+            connection: ollama::OllamaConfig::default(),
+        }
+    }
+}
+
+/// How cleanup output is generated, independent of which model runs it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PostProcessingSettings {
+    pub deterministic: bool,
+    pub localized_prompts: bool,
+    /// Per-mode overrides on top of each mode's built-in generation options
+    pub mode_options: HashMap<String, ollama::GenerationOptions>,
+}
+
+impl Default for PostProcessingSettings {
+    fn default() -> Self {
+        Self {
+            deterministic: false,
+            localized_prompts: true,
+            mode_options: HashMap::new(),
+        }
+    }
+}
+
+/// Everything the app persists between launches
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub version: u32,
+    pub hotkeys: Vec<HotkeyBinding>,
+    pub recording: RecordingSettings,
+    /// `None` uses the system default input device
+    pub input_device: Option<String>,
+    /// Tried in order when `input_device` is unplugged, before the system default
+    pub input_device_fallbacks: Vec<String>,
+    /// Audio host API by name, e.g. "ALSA" or "JACK". `None` uses the platform default.
+    pub audio_host: Option<String>,
+    /// Zero-based input channels to record, mixed to mono; empty uses all of them
+    pub input_channels: Vec<u16>,
+    pub mode: String,
+    pub ollama: OllamaSettings,
+    pub post_processing: PostProcessingSettings,
+    /// How transcripts are inserted into the focused app
+    pub injection: InjectionMethod,
+    pub clipboard: ClipboardSettings,
+    /// Filtering, noise suppression and gain between the microphone and Whisper
+    pub dsp: DspSettings,
+    /// How many transcripts, and how old, the history keeps
+    pub history: RetentionPolicy,
+    /// Keeping recordings so history entries can be replayed and re-processed
+    pub archive: ArchiveSettings,
+    /// Loaded at startup; `None` falls back to the default model in the models directory
+    pub whisper_model_path: Option<String>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            version: SCHEMA_VERSION,
+            hotkeys: hotkeys::default_bindings(),
+            recording: RecordingSettings::default(),
+            input_device: None,
+            input_device_fallbacks: Vec::new(),
+            audio_host: None,
+            input_channels: Vec::new(),
+            mode: TranscriptionMode::Default.as_str().to_string(),
+            ollama: OllamaSettings::default(),
+            post_processing: PostProcessingSettings::default(),
+            injection: InjectionMethod::default(),
+            clipboard: ClipboardSettings::default(),
+            dsp: DspSettings::default(),
+            history: RetentionPolicy::default(),
+            archive: ArchiveSettings::default(),
+            whisper_model_path: None,
+        }
+    }
+}
+
+impl Settings {
+    pub fn validate(&self) -> AppResult<()> {
+        hotkeys::validate_bindings(&self.hotkeys)?;
+        self.recording.validate()?;
+        self.clipboard.validate()?;
+        self.dsp.validate()?;
+        if TranscriptionMode::from_id(&self.mode).is_none() {
+            return Err(AppError::InvalidInput(format!("Unknown mode: {}", self.mode)));
+        }
+        if self.ollama.model.trim().is_empty() {
+            return Err(AppError::InvalidInput("Ollama model must not be empty".to_string()));
+        }
+        self.ollama.connection.validate()?;
+        if let Some(mode) = self.post_processing.mode_options.keys().find(|m| TranscriptionMode::from_id(m).is_none()) {
+            return Err(AppError::InvalidInput(format!("Generation options for unknown mode: {}", mode)));
+        }
+        if self.whisper_model_path.as_deref().is_some_and(|p| p.trim().is_empty()) {
+            return Err(AppError::InvalidInput("Whisper model path must not be empty".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Reset invalid sections to their defaults instead of refusing to start
+    fn sanitize(mut self) -> Self {
+        let defaults = Settings::default();
+        self.hotkeys.retain(|b| match b.validate() {
+            Ok(()) => true,
+            Err(e) => {
+                log::warn!("Dropping invalid hotkey {}: {}", b.combo(), e);
+                false
+            }
+        });
+        // Later duplicates lose to the first binding of a combination
+        for conflict in hotkeys::find_conflicts(&self.hotkeys) {
+            for &i in &conflict.bindings[1..] {
+                log::warn!("Disabling hotkey {} bound twice", conflict.combo);
+                self.hotkeys[i].enabled = false;
+            }
+        }
+        if let Err(e) = self.recording.validate() {
+            log::warn!("Invalid recording settings, using the defaults: {}", e);
+            self.recording = defaults.recording;
+        }
+        if let Err(e) = self.clipboard.validate() {
+            log::warn!("Invalid clipboard settings, using the defaults: {}", e);
+            self.clipboard = defaults.clipboard;
+        }
+        if let Err(e) = self.dsp.validate() {
+            log::warn!("Invalid audio processing settings, using the defaults: {}", e);
+            self.dsp = defaults.dsp;
+        }
+        if TranscriptionMode::from_id(&self.mode).is_none() {
+            log::warn!("Unknown mode '{}' in settings, using the default", self.mode);
+            self.mode = defaults.mode;
+        }
+        if self.ollama.model.trim().is_empty() {
+            self.ollama.model = defaults.ollama.model;
+        }
+        if let Err(e) = self.ollama.connection.validate() {
+            log::warn!("Invalid Ollama connection settings, using the defaults: {}", e);
+            self.ollama.connection = defaults.ollama.connection;
+        }
+        self.post_processing.mode_options.retain(|m, _| TranscriptionMode::from_id(m).is_some());
+        if self.whisper_model_path.as_deref().is_some_and(|p| p.trim().is_empty()) {
+            self.whisper_model_path = None;
+        }
+        self
+    }
+}
+
+/// One step per schema version, indexed by the version it upgrades from
+const MIGRATIONS: &[fn(&mut Value)] = &[
+    // 0 -> 1: files written before the schema was versioned
+    |_| {},
+    // 1 -> 2: a single push-to-talk hotkey became a list of bindings
+    upgrade_single_hotkey,
+];
+
+/// Turn a `hotkey: { key, modifiers, enabled }` object into a one-entry `hotkeys` list.
+/// Shared with profiles, which used the same layout.
+pub fn upgrade_single_hotkey(value: &mut Value) {
+    let Some(obj) = value.as_object_mut() else {
+        return;
+    };
+    let Some(mut hotkey) = obj.remove("hotkey") else {
+        return;
+    };
+    if let Some(binding) = hotkey.as_object_mut() {
+        binding.insert("action".to_string(), serde_json::json!({ "type": "record" }));
+        obj.insert("hotkeys".to_string(), Value::Array(vec![hotkey]));
+    }
+}
+
+fn schema_version(value: &Value) -> u32 {
+    value.get("version").and_then(Value::as_u64).unwrap_or(0) as u32
+}
+
+fn migrate(mut value: Value) -> AppResult<Value> {
+    let version = schema_version(&value);
+    if version > SCHEMA_VERSION {
+        return Err(AppError::InvalidInput(format!(
+            "Settings were written by a newer version of the app (schema {})",
+            version
+        )));
+    }
+
+    for step in &MIGRATIONS[version as usize..] {
+        step(&mut value);
+    }
+    if let Some(obj) = value.as_object_mut() {
+        obj.insert("version".to_string(), SCHEMA_VERSION.into());
+    }
+    Ok(value)
+}
+
+/// The settings file plus the settings currently in effect
+pub struct SettingsStore {
+    path: PathBuf,
+    settings: Settings,
+    /// Schema of a file written by a newer version of the app. It was backed up
+    /// like any unreadable file, so saving here doesn't lose it.
+    newer_schema: Option<u32>,
+}
+
+impl SettingsStore {
+    /// Load settings from `path`, falling back to defaults if the file is missing or
+    /// unreadable. An unreadable file is kept next to it as `settings.json.bak-<timestamp>`.
+    pub fn load(path: PathBuf) -> Self {
+        let mut newer_schema = None;
+        let settings = match read_settings(&path) {
+            Ok(Some(settings)) => settings,
+            Ok(None) => Settings::default(),
+            Err(e) => {
+                log::warn!("Failed to load settings from {}: {}", path.display(), e);
+                newer_schema = fs::read_to_string(&path)
+                    .ok()
+                    .and_then(|contents| serde_json::from_str::<Value>(&contents).ok())
+                    .map(|value| schema_version(&value))
+                    .filter(|&version| version > SCHEMA_VERSION);
+                match back_up(&path) {
+                    Ok(backup) => log::warn!("Moved the unreadable settings to {}", backup.display()),
+                    Err(e) => log::error!("Failed to back up {}: {}", path.display(), e),
+                }
+                Settings::default()
+            }
+        };
+        Self {
+            path,
+            settings,
+            newer_schema,
+        }
+    }
+
+    /// Read settings from `path` without touching the file. A missing file gives the
+    /// defaults and an unreadable one is an error, for commands that only look.
+    pub fn read(path: PathBuf) -> AppResult<Self> {
+        let settings = read_settings(&path)?.unwrap_or_default();
+        Ok(Self {
+            path,
+            settings,
+            newer_schema: None,
+        })
+    }
+
+    pub fn get(&self) -> &Settings {
+        &self.settings
+    }
+
+    /// Schema version of a newer settings file that was backed up at startup, if any
+    pub fn newer_schema(&self) -> Option<u32> {
+        self.newer_schema
+    }
+
+    /// Replace the settings after validating them, writing the file atomically
+    pub fn set(&mut self, mut settings: Settings) -> AppResult<&Settings> {
+        settings.version = SCHEMA_VERSION;
+        settings.validate()?;
+        write_settings(&self.path, &settings)?;
+        self.settings = settings;
+        Ok(&self.settings)
+    }
+
+    /// Modify a copy of the current settings and save it
+    pub fn update<F>(&mut self, f: F) -> AppResult<&Settings>
+    where
+        F: FnOnce(&mut Settings),
+    {
+        let mut settings = self.settings.clone();
+        f(&mut settings);
+        self.set(settings)
+    }
+}
+
+fn read_settings(path: &Path) -> AppResult<Option<Settings>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let contents = fs::read_to_string(path)?;
+    let value: Value = serde_json::from_str(&contents)
+        .map_err(|e| AppError::Decode(format!("Settings file is not valid JSON: {}", e)))?;
+    let settings: Settings = serde_json::from_value(migrate(value)?)
+        .map_err(|e| AppError::Decode(format!("Settings file has an unexpected shape: {}", e)))?;
+
+    Ok(Some(settings.sanitize()))
+}
+
+/// Move `path` aside to `<name>.bak-<unix seconds>`, returning the new path
+pub fn back_up(path: &Path) -> AppResult<PathBuf> {
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".bak-{}", timestamp));
+    let backup = path.with_file_name(name);
+    fs::rename(path, &backup)?;
+    Ok(backup)
+}
+
+fn write_settings(path: &Path, settings: &Settings) -> AppResult<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let json = serde_json::to_string_pretty(settings)
+        .map_err(|e| AppError::Internal(format!("Failed to serialize settings: {}", e)))?;
+
+    let tmp_path = path.with_extension("json.tmp");
+    {
+        let mut file = File::create(&tmp_path)?;
+        file.write_all(json.as_bytes())?;
+        file.sync_all()?;
+    }
+    fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+/// Get the default settings file path
+pub fn get_settings_path() -> PathBuf {
+    let mut path = dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from("."));
+    path.push("yap-to-text");
+    path.push("settings.json");
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A fresh directory holding `settings.json` with `contents`
+    fn settings_file(name: &str, contents: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("yap-settings-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("settings.json");
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    fn backups(path: &Path) -> Vec<String> {
+        fs::read_dir(path.parent().unwrap())
+            .unwrap()
+            .filter_map(|e| e.ok()?.file_name().into_string().ok())
+            .filter(|name| name.starts_with("settings.json.bak-"))
+            .collect()
+    }
+
+    #[test]
+    fn invalid_json_is_backed_up() {
+        let path = settings_file("invalid", "{ not json");
+        let mut store = SettingsStore::load(path.clone());
+
+        assert_eq!(store.get(), &Settings::default());
+        assert_eq!(backups(&path).len(), 1);
+        assert!(!path.exists());
+        // Nothing newer to protect, so saving works
+        store.update(|s| s.mode = "email".to_string()).unwrap();
+        assert!(path.exists());
+    }
+
+    #[test]
+    fn reading_leaves_an_unreadable_file_in_place() {
+        let path = settings_file("read", "{ not json");
+        assert!(SettingsStore::read(path.clone()).is_err());
+        assert!(backups(&path).is_empty());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "{ not json");
+    }
+
+    #[test]
+    fn wrong_shape_is_backed_up() {
+        let path = settings_file("shape", r#"{ "version": 2, "hotkeys": "Space" }"#);
+        let store = SettingsStore::load(path.clone());
+        assert_eq!(store.get(), &Settings::default());
+        assert_eq!(backups(&path).len(), 1);
+        assert_eq!(store.newer_schema(), None);
+    }
+
+    #[test]
+    fn newer_schema_is_backed_up_and_saving_continues() {
+        let newer = format!(r#"{{ "version": {} }}"#, SCHEMA_VERSION + 1);
+        let path = settings_file("newer", &newer);
+        let mut store = SettingsStore::load(path.clone());
+
+        assert_eq!(store.newer_schema(), Some(SCHEMA_VERSION + 1));
+        let backup = path.with_file_name(&backups(&path)[0]);
+        assert_eq!(fs::read_to_string(backup).unwrap(), newer);
+
+        store.update(|s| s.mode = "email".to_string()).unwrap();
+        assert_eq!(SettingsStore::load(path).get().mode, "email");
+    }
+
+    #[test]
+    fn old_schema_is_migrated_in_place() {
+        let path = settings_file(
+            "migrate",
+            r#"{ "version": 1, "hotkey": { "key": "KeyK", "modifiers": ["Alt"], "enabled": true } }"#,
+        );
+        let store = SettingsStore::load(path.clone());
+        assert_eq!(store.get().hotkeys, vec![HotkeyBinding::record("KeyK", &["Alt"])]);
+        assert!(backups(&path).is_empty());
+    }
+}
@@ -0,0 +1,402 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::modes::TranscriptionMode;
+
+/// Why a cleanup result was rejected
+#[derive(Debug, Clone, PartialEq)]
+pub enum Rejection {
+    Empty,
+    /// The model refused ("I'm sorry, I can't...")
+    Refusal,
+    /// The model asked for input or echoed its instructions
+    EchoedInstructions,
+    /// The transcript was a question and the model answered it
+    Answered,
+    /// Output shares almost no words with the input (translated or rewritten)
+    LowOverlap { overlap: f32 },
+    /// Output is far shorter or longer than the mode allows
+    LengthChanged { ratio: f32 },
+}
+
+impl fmt::Display for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "model returned no text"),
+            Self::Refusal => write!(f, "model refused to clean the transcript"),
+            Self::EchoedInstructions => write!(f, "model returned instructions instead of text"),
+            Self::Answered => write!(f, "model answered the transcript instead of cleaning it"),
+            Self::LowOverlap { overlap } => write!(
+                f,
+                "output shares only {:.0}% of its words with the transcript (translated or rewritten)",
+                overlap * 100.0
+            ),
+            Self::LengthChanged { ratio } => write!(f, "output is {:.1}x the transcript length", ratio),
+        }
+    }
+}
+
+/// Openers small models put before the actual output
+const PREAMBLES: &[&str] = &[
+    "here is",
+    "here's",
+    "here are",
+    "sure",
+    "certainly",
+    "of course",
+    "okay",
+    "cleaned",
+    "corrected",
+    "the cleaned",
+    "the corrected",
+    "the formatted",
+    "the summary",
+    "output",
+    "voici",
+    "aquí está",
+    "hier ist",
+];
+
+/// Words that mark an opener as talking about the output rather than being part of it
+const PREAMBLE_MARKERS: &[&str] = &["text", "version", "cleaned", "corrected", "formatted", "transcript"];
+
+/// Closers small models add after the actual output
+const POSTAMBLES: &[&str] = &[
+    "let me know",
+    "i hope this",
+    "hope this helps",
+    "feel free to",
+    "note:",
+    "i removed",
+    "i have removed",
+    "i've removed",
+    "i corrected",
+    "i fixed",
+];
+
+const REFUSALS: &[&str] = &[
+    "i'm sorry",
+    "i am sorry",
+    "sorry, but",
+    "i cannot",
+    "i can't",
+    "i can not",
+    "i'm unable",
+    "i am unable",
+    "as an ai",
+    "as a language model",
+];
+
+const INSTRUCTION_ECHOES: &[&str] = &[
+    "please provide",
+    "provide the transcript",
+    "paste the transcript",
+    "share the transcript",
+    "you haven't provided",
+    "you have not provided",
+    "no transcript",
+];
+
+/// Openers that make a transcript a question the model might be tempted to answer
+const QUESTION_OPENERS: &[&str] = &[
+    "what is", "what's", "what are", "how do", "how does", "how can", "why is",
+    "why do", "why does", "can you", "could you", "would you", "do you", "is there",
+    "are there", "who is", "where is", "when is",
+];
+
+/// Check a cleanup result against the raw transcript.
+///
+/// Returns the output with any chatty preamble/postamble removed, or the reason it
+/// should not replace the raw transcript.
+pub fn validate_cleanup(raw: &str, output: &str, mode: TranscriptionMode) -> Result<String, Rejection> {
+    let cleaned = strip_wrappers(output, mode);
+    if cleaned.trim().is_empty() {
+        return Err(Rejection::Empty);
+    }
+
+    let raw_lower = raw.to_lowercase();
+    let out_lower = cleaned.to_lowercase();
+
+    // Only flag phrases the speaker didn't say themselves
+    let introduced = |phrase: &&str| out_lower.contains(*phrase) && !raw_lower.contains(*phrase);
+
+    if REFUSALS.iter().any(|p| out_lower.starts_with(*p) && !raw_lower.contains(*p)) {
+        return Err(Rejection::Refusal);
+    }
+
+    if INSTRUCTION_ECHOES.iter().any(introduced)
+        || (out_lower.contains("provide") && out_lower.contains("transcript") && !raw_lower.contains("transcript"))
+    {
+        return Err(Rejection::EchoedInstructions);
+    }
+
+    let raw_words = tokenize(raw);
+    let out_words = tokenize(&cleaned);
+
+    // Very short inputs don't carry enough signal for the ratio checks
+    if raw_words.len() < 4 {
+        return Ok(cleaned);
+    }
+
+    let limits = Limits::for_mode(mode);
+
+    let ratio = out_words.len() as f32 / raw_words.len() as f32;
+    if ratio < limits.min_length_ratio || ratio > limits.max_length_ratio {
+        return Err(Rejection::LengthChanged { ratio });
+    }
+
+    // Share of the output's words that came from the transcript. Translations and
+    // answers score near zero; reformatting keeps most of the vocabulary.
+    let raw_set: HashSet<&str> = raw_words.iter().map(String::as_str).collect();
+    let shared = out_words.iter().filter(|w| raw_set.contains(w.as_str())).count();
+    let overlap = shared as f32 / out_words.len().max(1) as f32;
+    if overlap < limits.min_overlap {
+        return Err(Rejection::LowOverlap { overlap });
+    }
+
+    let raw_is_question = raw.contains('?') || QUESTION_OPENERS.iter().any(|q| raw_lower.trim_start().starts_with(q));
+    if mode == TranscriptionMode::Default && raw_is_question && !cleaned.contains('?') {
+        return Err(Rejection::Answered);
+    }
+
+    Ok(cleaned)
+}
+
+struct Limits {
+    min_length_ratio: f32,
+    max_length_ratio: f32,
+    min_overlap: f32,
+}
+
+impl Limits {
+    fn for_mode(mode: TranscriptionMode) -> Self {
+        match mode {
+            TranscriptionMode::Default => Self { min_length_ratio: 0.4, max_length_ratio: 1.6, min_overlap: 0.5 },
+            TranscriptionMode::Email => Self { min_length_ratio: 0.4, max_length_ratio: 3.5, min_overlap: 0.25 },
+            TranscriptionMode::Bullets => Self { min_length_ratio: 0.15, max_length_ratio: 1.8, min_overlap: 0.3 },
+            TranscriptionMode::Summary => Self { min_length_ratio: 0.05, max_length_ratio: 1.5, min_overlap: 0.25 },
+            TranscriptionMode::Slack => Self { min_length_ratio: 0.15, max_length_ratio: 1.6, min_overlap: 0.3 },
+            TranscriptionMode::MeetingNotes => Self { min_length_ratio: 0.15, max_length_ratio: 2.5, min_overlap: 0.2 },
+            TranscriptionMode::CodeComment => Self { min_length_ratio: 0.3, max_length_ratio: 2.5, min_overlap: 0.25 },
+        }
+    }
+}
+
+/// Remove quotes, code fences and chatty lines around the real output
+fn strip_wrappers(output: &str, mode: TranscriptionMode) -> String {
+    let mut text = output.trim().trim_matches('"').trim().to_string();
+
+    if mode != TranscriptionMode::CodeComment && text.starts_with("```") && text.ends_with("```") && text.len() > 6 {
+        text = text[3..text.len() - 3].to_string();
+        // Drop a language tag on the opening fence
+        if let Some(pos) = text.find('\n') {
+            if !text[..pos].contains(' ') {
+                text = text[pos + 1..].to_string();
+            }
+        }
+    }
+
+    let mut lines: Vec<&str> = text.lines().collect();
+
+    while let Some(first) = lines.first() {
+        let lower = first.trim().to_lowercase();
+        let is_preamble = PREAMBLES.iter().any(|p| lower.starts_with(p))
+            && lower.split_whitespace().count() <= 12
+            && lines.len() > 1
+            && (lower.ends_with(':') || PREAMBLE_MARKERS.iter().any(|w| lower.contains(w)));
+        if is_preamble || (lower.is_empty() && lines.len() > 1) {
+            lines.remove(0);
+        } else {
+            break;
+        }
+    }
+
+    // "Here is the cleaned text: actual text" on a single line
+    if let Some(first) = lines.first_mut() {
+        let lower = first.to_lowercase();
+        if PREAMBLES.iter().any(|p| lower.starts_with(p)) {
+            if let Some(colon) = first.find(':') {
+                let prefix = first[..colon].to_lowercase();
+                if prefix.split_whitespace().count() <= 8 && PREAMBLE_MARKERS.iter().any(|w| prefix.contains(w)) {
+                    *first = first[colon + 1..].trim_start();
+                }
+            }
+        }
+    }
+
+    while let Some(last) = lines.last() {
+        let lower = last.trim().to_lowercase();
+        let is_postamble = POSTAMBLES.iter().any(|p| lower.starts_with(p)) && lines.len() > 1;
+        if is_postamble || (lower.is_empty() && lines.len() > 1) {
+            lines.pop();
+        } else {
+            break;
+        }
+    }
+
+    lines.join("\n").trim().trim_matches('"').trim().to_string()
+}
+
+/// Lowercase word tokens; ideographic scripts without spaces count one token per character
+fn tokenize(text: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+
+    for c in text.chars() {
+        if is_ideographic(c) {
+            if !current.is_empty() {
+                tokens.push(std::mem::take(&mut current));
+            }
+            tokens.push(c.to_string());
+        } else if c.is_alphanumeric() || c == '\'' {
+            current.extend(c.to_lowercase());
+        } else if !current.is_empty() {
+            tokens.push(std::mem::take(&mut current));
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+
+    tokens
+}
+
+fn is_ideographic(c: char) -> bool {
+    matches!(c as u32,
+        0x3040..=0x30FF   // Hiragana, Katakana
+        | 0x3400..=0x4DBF // CJK Extension A
+        | 0x4E00..=0x9FFF // CJK Unified Ideographs
+        | 0xF900..=0xFAFF // CJK Compatibility Ideographs
+        | 0x0E00..=0x0E7F // Thai
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RAW: &str = "um so I think we should move the launch to next tuesday because the build is not ready yet";
+
+    fn check(output: &str) -> Result<String, Rejection> {
+        validate_cleanup(RAW, output, TranscriptionMode::Default)
+    }
+
+    #[test]
+    fn accepts_a_plain_cleanup() {
+        let out = "I think we should move the launch to next Tuesday because the build is not ready yet.";
+        assert_eq!(check(out).unwrap(), out);
+    }
+
+    #[test]
+    fn strips_preambles_on_their_own_line() {
+        let body = "I think we should move the launch to next Tuesday because the build is not ready yet.";
+        for preamble in [
+            "Here is the cleaned transcript:",
+            "Here's the corrected text:",
+            "Sure! Here is the cleaned version:",
+            "Certainly, here is the formatted text:",
+            "Cleaned transcript:",
+        ] {
+            let output = format!("{}\n\n{}", preamble, body);
+            assert_eq!(check(&output).unwrap(), body, "preamble {:?}", preamble);
+        }
+    }
+
+    #[test]
+    fn strips_an_inline_preamble_and_postamble() {
+        let output = "Here is the cleaned text: I think we should move the launch to next Tuesday because the build is not ready yet.\n\nLet me know if you need any changes!";
+        assert_eq!(
+            check(output).unwrap(),
+            "I think we should move the launch to next Tuesday because the build is not ready yet."
+        );
+    }
+
+    #[test]
+    fn strips_quotes_and_code_fences() {
+        let body = "I think we should move the launch to next Tuesday because the build is not ready yet.";
+        assert_eq!(check(&format!("\"{}\"", body)).unwrap(), body);
+        assert_eq!(check(&format!("```text\n{}\n```", body)).unwrap(), body);
+    }
+
+    #[test]
+    fn rejects_refusals() {
+        for output in [
+            "I'm sorry, but I can't help with that request.",
+            "I cannot process this transcript.",
+            "As an AI language model, I am unable to do that.",
+        ] {
+            assert_eq!(check(output), Err(Rejection::Refusal), "output {:?}", output);
+        }
+    }
+
+    #[test]
+    fn keeps_a_refusal_the_speaker_said() {
+        let raw = "i'm sorry but i can't make the meeting tomorrow morning";
+        let out = "I'm sorry, but I can't make the meeting tomorrow morning.";
+        assert!(validate_cleanup(raw, out, TranscriptionMode::Default).is_ok());
+    }
+
+    #[test]
+    fn rejects_echoed_instructions() {
+        for output in [
+            "Please provide the transcript you would like me to clean up.",
+            "It seems you haven't provided a transcript yet.",
+            "Could you provide the transcript text?",
+        ] {
+            assert_eq!(check(output), Err(Rejection::EchoedInstructions), "output {:?}", output);
+        }
+    }
+
+    #[test]
+    fn rejects_empty_output() {
+        assert_eq!(check(""), Err(Rejection::Empty));
+        assert_eq!(check("  \"\"  "), Err(Rejection::Empty));
+    }
+
+    #[test]
+    fn rejects_truncated_output() {
+        assert!(matches!(check("Move the launch."), Err(Rejection::LengthChanged { .. })));
+    }
+
+    #[test]
+    fn rejects_expanded_output() {
+        let output = "I think we should move the launch to next Tuesday because the build is not ready yet. \
+            The team has been working hard, but several critical bugs remain open and QA needs at least \
+            three more days to finish regression testing across all supported platforms before we can ship.";
+        assert!(matches!(check(output), Err(Rejection::LengthChanged { .. })));
+    }
+
+    #[test]
+    fn rejects_translations() {
+        let output = "Creo que deberíamos mover el lanzamiento al próximo martes porque la compilación aún no está lista.";
+        assert!(matches!(check(output), Err(Rejection::LowOverlap { .. })));
+    }
+
+    #[test]
+    fn rejects_answers_to_questions() {
+        let raw = "what is the capital of australia and why isn't it sydney";
+        let out = "The capital of Australia is Canberra, chosen as a compromise between Sydney and Melbourne.";
+        assert_eq!(validate_cleanup(raw, out, TranscriptionMode::Default), Err(Rejection::Answered));
+        let cleaned = "What is the capital of Australia, and why isn't it Sydney?";
+        assert!(validate_cleanup(raw, cleaned, TranscriptionMode::Default).is_ok());
+    }
+
+    #[test]
+    fn allows_modes_to_reshape_the_text() {
+        let bullets = "- Move the launch to next Tuesday\n- The build is not ready yet";
+        assert!(validate_cleanup(RAW, bullets, TranscriptionMode::Bullets).is_ok());
+        let summary = "Launch moves to Tuesday; build not ready.";
+        assert!(validate_cleanup(RAW, summary, TranscriptionMode::Summary).is_ok());
+    }
+
+    #[test]
+    fn skips_ratio_checks_for_short_input() {
+        assert!(validate_cleanup("yes", "Yes, absolutely, I agree with that.", TranscriptionMode::Default).is_ok());
+    }
+
+    #[test]
+    fn counts_ideographs_as_words() {
+        assert_eq!(tokenize("我们明天开会"), vec!["我", "们", "明", "天", "开", "会"]);
+        assert_eq!(tokenize("Don't stop"), vec!["don't", "stop"]);
+    }
+}
@@ -1,6 +1,8 @@
 use std::path::PathBuf;
 use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
 
+use crate::error::{AppError, AppResult};
+
 pub struct WhisperTranscriber {
     context: Option<WhisperContext>,
     model_path: PathBuf,
@@ -20,37 +22,49 @@ impl WhisperTranscriber {
         }
     }
 
-    pub fn load_model(&mut self) -> Result<(), String> {
+    pub fn load_model(&mut self) -> AppResult<()> {
         if self.context.is_some() {
             return Ok(());
         }
 
         if !self.model_path.exists() {
-            return Err(format!(
+            return Err(AppError::ModelMissing(format!(
                 "Model file not found: {}",
                 self.model_path.display()
-            ));
+            )));
         }
 
+        let model_path = self.model_path.to_str().ok_or_else(|| {
+            AppError::InvalidInput(format!("Model path is not valid UTF-8: {}", self.model_path.display()))
+        })?;
+
         let ctx = WhisperContext::new_with_params(
-            self.model_path.to_str().unwrap(),
+            model_path,
             WhisperContextParameters::default(),
         )
-        .map_err(|e| format!("Failed to load Whisper model: {}", e))?;
+        .map_err(|e| AppError::ModelLoad(format!("Failed to load Whisper model: {}", e)))?;
 
         self.context = Some(ctx);
         Ok(())
     }
 
-    pub fn transcribe(&self, audio_data: &[f32]) -> Result<TranscriptionResult, String> {
+    pub fn transcribe(&self, audio_data: &[f32]) -> AppResult<TranscriptionResult> {
+        self.transcribe_until(audio_data, || false)
+    }
+
+    /// Like `transcribe`, but Whisper gives up as soon as `cancelled` returns true
+    pub fn transcribe_until<F>(&self, audio_data: &[f32], cancelled: F) -> AppResult<TranscriptionResult>
+    where
+        F: Fn() -> bool + Clone + 'static,
+    {
         let ctx = self
             .context
             .as_ref()
-            .ok_or("Model not loaded. Call load_model() first")?;
+            .ok_or_else(|| AppError::ModelMissing("Model not loaded. Call load_model() first".to_string()))?;
 
         let mut state = ctx
             .create_state()
-            .map_err(|e| format!("Failed to create state: {}", e))?;
+            .map_err(|e| AppError::Transcription(format!("Failed to create state: {}", e)))?;
 
         let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
 
@@ -63,14 +77,17 @@ impl WhisperTranscriber {
         params.set_print_timestamps(false);
         params.set_suppress_blank(true);
         params.set_suppress_non_speech_tokens(true);
+        params.set_abort_callback_safe(Some(cancelled.clone()));
 
         // Run transcription
-        state
-            .full(params, audio_data)
-            .map_err(|e| format!("Transcription failed: {}", e))?;
+        let result = state.full(params, audio_data);
+        if cancelled() {
+            return Err(AppError::Cancelled("Transcription cancelled".to_string()));
+        }
+        result.map_err(|e| AppError::Transcription(format!("Transcription failed: {}", e)))?;
 
         // Collect results
-        let num_segments = state.full_n_segments().map_err(|e| format!("Failed to get segments: {}", e))?;
+        let num_segments = state.full_n_segments().map_err(|e| AppError::Transcription(format!("Failed to get segments: {}", e)))?;
         let mut text = String::new();
 
         for i in 0..num_segments {
@@ -96,13 +113,21 @@ impl WhisperTranscriber {
     pub fn is_model_loaded(&self) -> bool {
         self.context.is_some()
     }
+
+    /// Model file name without extension, e.g. "ggml-base"
+    pub fn model_name(&self) -> String {
+        self.model_path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default()
+    }
 }
 
 /// Convert WAV bytes to f32 samples at 16kHz
-pub fn wav_to_samples(wav_data: &[u8]) -> Result<Vec<f32>, String> {
+pub fn wav_to_samples(wav_data: &[u8]) -> AppResult<Vec<f32>> {
     let cursor = std::io::Cursor::new(wav_data);
     let mut reader = hound::WavReader::new(cursor)
-        .map_err(|e| format!("Failed to read WAV: {}", e))?;
+        .map_err(|e| AppError::Decode(format!("Failed to read WAV: {}", e)))?;
 
     let spec = reader.spec();
 
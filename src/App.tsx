@@ -3,6 +3,7 @@ import { invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
 
 interface TranscribeResult {
+  id?: number;
   raw_text: string;
   cleaned_text: string;
   language: string;
@@ -10,9 +11,44 @@ interface TranscribeResult {
   timestamp?: number;
 }
 
+interface HistoryEntry {
+  id: number;
+  created_at: number;
+  raw_text: string;
+  cleaned_text: string;
+  mode: string;
+  language: string;
+}
+
+interface HistoryPage {
+  entries: HistoryEntry[];
+  total: number;
+}
+
+const entryToResult = (entry: HistoryEntry): TranscribeResult => ({
+  id: entry.id,
+  raw_text: entry.raw_text,
+  cleaned_text: entry.cleaned_text,
+  language: entry.language,
+  mode: entry.mode,
+  timestamp: entry.created_at,
+});
+
 interface AudioDevice {
   id: string;
   name: string;
+  host: string;
+  is_default: boolean;
+}
+
+interface DeviceCapabilities {
+  device_id: string;
+  formats: { channels: number; min_sample_rate: number; max_sample_rate: number; sample_format: string }[];
+}
+
+interface AudioHost {
+  id: string;
+  is_default: boolean;
 }
 
 interface ModeInfo {
@@ -22,6 +58,14 @@ interface ModeInfo {
   requires_ollama: boolean;
 }
 
+interface Language {
+  code: string;
+  name: string;
+  native_name: string;
+  script: string;
+  rtl: boolean;
+}
+
 interface Stats {
   todayCount: number;
   sessionWords: number;
@@ -37,6 +81,96 @@ interface HotkeyConfig {
 
 const DEFAULT_HOTKEY: HotkeyConfig = { key: "Space", modifiers: ["Meta", "Shift"] };
 
+type HotkeyAction =
+  | { type: "record"; mode?: string | null }
+  | { type: "toggle_recording"; mode?: string | null }
+  | { type: "cancel" }
+  | { type: "repaste_last" }
+  | { type: "cycle_mode" };
+
+interface HotkeyBinding extends HotkeyConfig {
+  action: HotkeyAction;
+  enabled: boolean;
+}
+
+// The hold-to-talk binding edited in settings: the first "record" binding without a mode
+const primaryBindingIndex = (bindings: HotkeyBinding[]) =>
+  bindings.findIndex(b => b.action.type === "record" && !b.action.mode);
+
+interface RecordingSettings {
+  style: "hold" | "toggle";
+  double_tap_lock: boolean;
+  double_tap_ms: number;
+  max_duration_secs: number;
+  escape_cancels: boolean;
+}
+
+interface Settings {
+  version: number;
+  hotkeys: HotkeyBinding[];
+  recording: RecordingSettings;
+  input_device: string | null;
+  input_device_fallbacks: string[];
+  audio_host: string | null;
+  input_channels: number[];
+  mode: string;
+  ollama: {
+    enabled: boolean;
+    model: string;
+    connection: {
+      connect_timeout_ms: number;
+      request_timeout_ms: number;
+      max_retries: number;
+      retry_backoff_ms: number;
+      failure_threshold: number;
+      cooldown_secs: number;
+    };
+  };
+  injection: "paste" | "type";
+  clipboard: { restore: boolean; restore_delay_ms: number };
+  dsp: {
+    enabled: boolean;
+    high_pass: boolean;
+    high_pass_hz: number;
+    noise_suppression: boolean;
+    auto_gain: boolean;
+    target_level_db: number;
+  };
+  history: { max_entries: number | null; max_age_days: number | null };
+  archive: { enabled: boolean; max_bytes: number; max_age_days: number | null };
+  whisper_model_path: string | null;
+}
+
+interface PlatformInfo {
+  os: string;
+  display_server: "native" | "x11" | "wayland" | "headless";
+  global_shortcuts: boolean;
+}
+
+// Errors from Rust commands arrive as { code, message, hint }
+interface AppError {
+  code: string;
+  message: string;
+  hint?: string | null;
+}
+
+const formatError = (e: unknown): string => {
+  if (e && typeof e === "object" && "message" in e) {
+    const err = e as AppError;
+    return err.hint ? `${err.message} ${err.hint}` : err.message;
+  }
+  return `${e}`;
+};
+
+/** A problem the pipeline recovered from, e.g. a device fallback or failed cleanup */
+interface PipelineWarning {
+  code: string;
+  message: string;
+}
+
+const isCancelled = (e: unknown): boolean =>
+  !!e && typeof e === "object" && (e as AppError).code === "cancelled";
+
 // Mode icons as inline SVGs
 const ModeIcons: Record<string, React.ReactNode> = {
   default: (
@@ -80,27 +214,41 @@ function App() {
   const [status, setStatus] = useState<AppStatus>("idle");
   const [result, setResult] = useState<TranscribeResult | null>(null);
   const [error, setError] = useState<string | null>(null);
+  const [warnings, setWarnings] = useState<PipelineWarning[]>([]);
   const [isModelLoaded, setIsModelLoaded] = useState(false);
   const [isOllamaAvailable, setIsOllamaAvailable] = useState(false);
   const [aiCleanupEnabled, setAiCleanupEnabled] = useState(true);
   const [showSettings, setShowSettings] = useState(false);
   const [modelPath, setModelPath] = useState("");
-  const [history, setHistory] = useState<TranscribeResult[]>(() => {
-    const saved = localStorage.getItem("yap-history");
-    if (saved) { try { return JSON.parse(saved); } catch { return []; } }
-    return [];
-  });
-  const [initialHistoryLoaded, setInitialHistoryLoaded] = useState(false);
+  const [history, setHistory] = useState<TranscribeResult[]>([]);
   const [hotkey, setHotkey] = useState<HotkeyConfig>(DEFAULT_HOTKEY);
   const [isCapturingHotkey, setIsCapturingHotkey] = useState(false);
   const [hotkeyEnabled, setHotkeyEnabled] = useState(true);
+  const [recordingSettings, setRecordingSettings] = useState<RecordingSettings | null>(null);
+  const [injection, setInjection] = useState<Settings["injection"]>("paste");
+  const [restoreClipboard, setRestoreClipboard] = useState(true);
+  const [audioCleanup, setAudioCleanup] = useState(false);
+  const [keepRecordings, setKeepRecordings] = useState(false);
+  const [newerSettingsSchema, setNewerSettingsSchema] = useState<number | null>(null);
+  const [platform, setPlatform] = useState<PlatformInfo | null>(null);
   const [autoStartEnabled, setAutoStartEnabled] = useState(false);
   const [audioDevices, setAudioDevices] = useState<AudioDevice[]>([]);
   const [selectedDevice, setSelectedDevice] = useState<string | null>(null);
+  const [audioHosts, setAudioHosts] = useState<AudioHost[]>([]);
+  const [deviceChannels, setDeviceChannels] = useState(0);
+  const [inputChannels, setInputChannels] = useState<number[]>([]);
+  const [selectedHost, setSelectedHost] = useState<string | null>(null);
   const [availableModes, setAvailableModes] = useState<ModeInfo[]>([]);
-  const [selectedMode, setSelectedMode] = useState<string>(() => {
-    return localStorage.getItem("yap-selected-mode") || "default";
-  });
+  const [languages, setLanguages] = useState<Record<string, Language>>({});
+  const [selectedMode, setSelectedMode] = useState<string>("default");
+  const [settingsLoaded, setSettingsLoaded] = useState(false);
+  const hotkeyBindingsRef = useRef<HotkeyBinding[]>([]);
+  const recordingModeRef = useRef<string>("default");
+  // Bumped on cancel so an in-flight transcription stops before pasting
+  const pipelineRunRef = useRef(0);
+  // A quick tap can be released before start_recording returns; that stop is applied once it has
+  const startingRef = useRef(false);
+  const stopAfterStartRef = useRef(false);
   const [copiedFeedback, setCopiedFeedback] = useState(false);
   const [stats, setStats] = useState<Stats>({ todayCount: 0, sessionWords: 0, streak: 1 });
   const statusRef = useRef(status);
@@ -110,40 +258,92 @@ function App() {
   useEffect(() => { statusRef.current = status; }, [status]);
   useEffect(() => { isModelLoadedRef.current = isModelLoaded; }, [isModelLoaded]);
   useEffect(() => { selectedModeRef.current = selectedMode; }, [selectedMode]);
-  useEffect(() => {
-    if (history.length > 0) localStorage.setItem("yap-history", JSON.stringify(history));
-  }, [history]);
-  useEffect(() => {
-    // Restore last result from history on mount
-    if (!initialHistoryLoaded && history.length > 0) {
-      setResult(history[0]);
-      setInitialHistoryLoaded(true);
-    }
-  }, [history, initialHistoryLoaded]);
 
   useEffect(() => {
     checkWhisperStatus();
     checkOllamaStatus();
+    loadPlatformInfo();
     getModelsDir();
-    loadSavedHotkey();
+    loadSettings();
     loadAutoStartSetting();
     loadAudioDevices();
     loadAvailableModes();
-    autoLoadModel();
+    loadLanguages();
+    loadHistory();
   }, []);
 
-  const autoLoadModel = async () => {
-    try {
-      const loaded = await invoke<boolean>("is_whisper_loaded");
-      if (loaded) { setIsModelLoaded(true); return; }
-      const dir = await invoke<string>("get_models_directory");
-      const defaultPath = `${dir}/ggml-base.bin`;
-      await invoke("load_whisper_model", { modelPath: defaultPath });
+  // The backend loads the saved (or default) Whisper model at startup and announces it
+  useEffect(() => {
+    let unlisten: (() => void) | undefined;
+    listen<string>("whisper-model-loaded", (event) => {
       setIsModelLoaded(true);
-      setModelPath(defaultPath);
-    } catch { /* Model not available */ }
+      setModelPath(event.payload);
+    }).then(fn => { unlisten = fn; });
+    return () => unlisten?.();
+  }, []);
+
+
+  const applySettings = (settings: Settings) => {
+    hotkeyBindingsRef.current = settings.hotkeys;
+    const primary = settings.hotkeys[primaryBindingIndex(settings.hotkeys)];
+    if (primary) {
+      const { key, modifiers } = primary;
+      // Keep the same object when nothing changed so the registration effect doesn't loop
+      setHotkey(prev => prev.key === key && prev.modifiers.join("+") === modifiers.join("+") ? prev : { key, modifiers });
+      setHotkeyEnabled(primary.enabled);
+    }
+    setRecordingSettings(settings.recording);
+    setInjection(settings.injection);
+    setRestoreClipboard(settings.clipboard.restore);
+    setAudioCleanup(settings.dsp.enabled);
+    setKeepRecordings(settings.archive.enabled);
+    setSelectedDevice(settings.input_device);
+    setSelectedHost(settings.audio_host);
+    setInputChannels(settings.input_channels);
+    setSelectedMode(settings.mode);
+    selectedModeRef.current = settings.mode;
+    setAiCleanupEnabled(settings.ollama.enabled);
+    if (settings.whisper_model_path) setModelPath(settings.whisper_model_path);
+  };
+
+  const loadSettings = async () => {
+    try {
+      setNewerSettingsSchema(await invoke<number | null>("get_newer_settings_schema"));
+      let settings = await invoke<Settings>("get_settings");
+      // One-time migration of settings kept in localStorage by older versions
+      const legacyKeys = ["yap-hotkey", "yap-hotkey-enabled", "yap-selected-microphone", "yap-selected-mode"];
+      if (legacyKeys.some(k => localStorage.getItem(k) !== null)) {
+        const next: Settings = { ...settings, hotkeys: settings.hotkeys.map(b => ({ ...b })), ollama: { ...settings.ollama } };
+        const primary = next.hotkeys[primaryBindingIndex(next.hotkeys)];
+        const savedHotkey = localStorage.getItem("yap-hotkey");
+        if (savedHotkey && primary) {
+          try {
+            const parsed: HotkeyConfig = JSON.parse(savedHotkey);
+            primary.key = parsed.key;
+            primary.modifiers = parsed.modifiers;
+          } catch { /* Keep the current hotkey */ }
+        }
+        const savedEnabled = localStorage.getItem("yap-hotkey-enabled");
+        if (savedEnabled !== null && primary) primary.enabled = savedEnabled === "true";
+        next.input_device = localStorage.getItem("yap-selected-microphone") ?? next.input_device;
+        next.mode = localStorage.getItem("yap-selected-mode") ?? next.mode;
+        try {
+          settings = await invoke<Settings>("update_settings", { settings: next });
+        } catch (e) { console.error("Failed to migrate settings:", e); }
+        legacyKeys.forEach(k => localStorage.removeItem(k));
+      }
+      applySettings(settings);
+    } catch (e) { console.error("Failed to load settings:", e); }
+    setSettingsLoaded(true);
   };
 
+  useEffect(() => {
+    let unlisten: (() => void) | undefined;
+    listen<Settings>("settings-changed", (event) => applySettings(event.payload))
+      .then(fn => { unlisten = fn; });
+    return () => unlisten?.();
+  }, []);
+
   const loadAutoStartSetting = async () => {
     try {
       const enabled = await invoke<boolean>("plugin:autostart|is_enabled");
@@ -161,25 +361,43 @@ function App() {
 
   const loadAudioDevices = async () => {
     try {
-      const devices = await invoke<AudioDevice[]>("get_input_devices");
+      const [devices, hosts] = await Promise.all([
+        invoke<AudioDevice[]>("get_input_devices"),
+        invoke<AudioHost[]>("get_audio_hosts"),
+      ]);
       setAudioDevices(devices);
-      const savedDevice = localStorage.getItem("yap-selected-microphone");
-      if (savedDevice && devices.some(d => d.id === savedDevice)) {
-        setSelectedDevice(savedDevice);
-        await invoke("set_input_device", { deviceId: savedDevice });
-      }
+      setAudioHosts(hosts);
     } catch (e) { console.error("Failed to load audio devices:", e); }
   };
 
+  const handleChannelSelect = async (channels: number[]) => {
+    try {
+      await invoke("set_input_channels", { channels });
+      setInputChannels(channels);
+    } catch (e) { setError(formatError(e)); }
+  };
+
+  // Multi-channel interfaces get a channel picker
+  useEffect(() => {
+    if (!selectedDevice) { setDeviceChannels(0); return; }
+    invoke<DeviceCapabilities>("get_device_capabilities", { deviceId: selectedDevice })
+      .then(caps => setDeviceChannels(Math.max(0, ...caps.formats.map(f => f.channels))))
+      .catch(() => setDeviceChannels(0));
+  }, [selectedDevice]);
+
+  const handleHostSelect = async (hostId: string | null) => {
+    try {
+      await invoke("set_audio_host", { hostId });
+      setSelectedHost(hostId);
+      setSelectedDevice(null);
+    } catch (e) { setError(formatError(e)); }
+  };
+
   const handleDeviceSelect = async (deviceId: string | null) => {
     try {
       setSelectedDevice(deviceId);
+      setInputChannels([]);
       await invoke("set_input_device", { deviceId });
-      if (deviceId) {
-        localStorage.setItem("yap-selected-microphone", deviceId);
-      } else {
-        localStorage.removeItem("yap-selected-microphone");
-      }
     } catch (e) { console.error("Failed to set audio device:", e); }
   };
 
@@ -187,21 +405,49 @@ function App() {
     try {
       const modes = await invoke<ModeInfo[]>("get_available_modes");
       setAvailableModes(modes);
-      const savedMode = localStorage.getItem("yap-selected-mode") || "default";
-      setSelectedMode(savedMode);
-      selectedModeRef.current = savedMode;
-      await invoke("set_mode", { mode: savedMode });
-      await invoke("set_overlay_mode", { mode: savedMode });
     } catch (e) { console.error("Failed to load available modes:", e); }
   };
 
+  const loadHistory = async () => {
+    try {
+      // One-time migration of history kept in localStorage by older versions
+      const legacy = localStorage.getItem("yap-history");
+      if (legacy) {
+        const items: TranscribeResult[] = JSON.parse(legacy);
+        for (const item of [...items].reverse()) {
+          await invoke("add_history_entry", {
+            entry: {
+              raw_text: item.raw_text,
+              cleaned_text: item.cleaned_text,
+              mode: item.mode || "default",
+              language: item.language,
+              created_at: item.timestamp,
+            },
+          });
+        }
+        localStorage.removeItem("yap-history");
+      }
+      const page = await invoke<HistoryPage>("query_history", { query: { limit: 10 } });
+      const results = page.entries.map(entryToResult);
+      setHistory(results);
+      // Restore last result from history on mount
+      if (results.length > 0) setResult(results[0]);
+    } catch (e) { console.error("Failed to load history:", e); }
+  };
+
+  const loadLanguages = async () => {
+    try {
+      const list = await invoke<Language[]>("get_languages");
+      setLanguages(Object.fromEntries(list.map(l => [l.code, l])));
+    } catch (e) { console.error("Failed to load languages:", e); }
+  };
+
   const handleModeSelect = async (modeId: string) => {
     const mode = availableModes.find(m => m.id === modeId);
     if (mode?.requires_ollama && modeId !== "default" && !isOllamaAvailable) return;
     try {
       setSelectedMode(modeId);
       selectedModeRef.current = modeId;
-      localStorage.setItem("yap-selected-mode", modeId);
       await invoke("set_mode", { mode: modeId });
       await invoke("set_overlay_mode", { mode: modeId });
     } catch (e) { console.error("Failed to set mode:", e); }
@@ -210,53 +456,69 @@ function App() {
   const getModelsDir = async () => {
     try {
       const dir = await invoke<string>("get_models_directory");
-      setModelPath(`${dir}/ggml-base.bin`);
+      setModelPath(prev => prev || `${dir}/ggml-base.bin`);
     } catch (e) { console.error("Failed to get models directory:", e); }
   };
 
-  const loadSavedHotkey = () => {
-    const saved = localStorage.getItem("yap-hotkey");
-    if (saved) { try { setHotkey(JSON.parse(saved)); } catch { /* Use default */ } }
-    const enabledSaved = localStorage.getItem("yap-hotkey-enabled");
-    if (enabledSaved !== null) setHotkeyEnabled(enabledSaved === "true");
-  };
-
   const saveHotkey = (config: HotkeyConfig) => {
-    localStorage.setItem("yap-hotkey", JSON.stringify(config));
     setHotkey(config);
   };
 
-  const registerCurrentHotkey = async () => {
-    if (!hotkeyEnabled) return;
-    try {
-      await invoke("register_hotkey", { key: hotkey.key, modifiers: hotkey.modifiers });
-    } catch (e) { console.error("Failed to register hotkey:", e); }
-  };
-
   useEffect(() => {
-    if (hotkeyEnabled) registerCurrentHotkey();
-    else invoke("unregister_all_hotkeys").catch(console.error);
-    localStorage.setItem("yap-hotkey-enabled", hotkeyEnabled.toString());
-  }, [hotkeyEnabled, hotkey]);
-
+    // The backend registers the saved hotkeys at startup; don't overwrite them with defaults
+    if (!settingsLoaded) return;
+    const bindings = hotkeyBindingsRef.current.map(b => ({ ...b }));
+    const index = primaryBindingIndex(bindings);
+    const action: HotkeyAction = index >= 0 ? bindings[index].action : { type: "record" };
+    const primary: HotkeyBinding = { key: hotkey.key, modifiers: hotkey.modifiers, action, enabled: hotkeyEnabled };
+    if (index >= 0) bindings[index] = primary;
+    else bindings.unshift(primary);
+    const unchanged = JSON.stringify(bindings) === JSON.stringify(hotkeyBindingsRef.current);
+    if (unchanged) return;
+    invoke<{ index: number; registered: boolean; error: string | null }[]>("set_hotkeys", { bindings })
+      .then(statuses => statuses.filter(st => !st.registered).forEach(st => console.error("Failed to register hotkey:", st.error)))
+      .catch(e => setError(formatError(e)));
+  }, [hotkeyEnabled, hotkey, settingsLoaded]);
+
+  // Hotkeys are dispatched in Rust; recording actions and device changes arrive as events
   useEffect(() => {
-    let unlistenPressed: (() => void) | undefined;
-    let unlistenReleased: (() => void) | undefined;
+    const unlisteners: (() => void)[] = [];
     const setup = async () => {
-      unlistenPressed = await listen<string>("hotkey-pressed", () => {
+      unlisteners.push(await listen<string | null>("record-start", (event) => {
         if (!isModelLoadedRef.current) return;
         if (statusRef.current !== "idle" && statusRef.current !== "ready") return;
-        handleRecordStart();
-      });
-      unlistenReleased = await listen<string>("hotkey-released", () => {
+        handleRecordStart(event.payload ?? undefined);
+      }));
+      unlisteners.push(await listen("record-stop", () => {
         if (statusRef.current === "recording") handleRecordStop();
-      });
+        else if (startingRef.current) stopAfterStartRef.current = true;
+      }));
+      unlisteners.push(await listen<AudioDevice[]>("devices-changed", (event) => {
+        setAudioDevices(event.payload);
+      }));
+      unlisteners.push(await listen<PipelineWarning>("pipeline-warning", (event) => {
+        const warning = event.payload;
+        setWarnings(prev => [
+          // A recovered device supersedes the interruption
+          ...prev.filter(w => w.code !== warning.code && !(warning.code === "device_recovered" && w.code === "device_interrupted")),
+          warning,
+        ]);
+      }));
+      unlisteners.push(await listen("recording-cancelled", () => {
+        pipelineRunRef.current += 1;
+        if (statusRef.current !== "idle" && statusRef.current !== "ready") setStatus("idle");
+      }));
     };
     setup();
-    return () => { unlistenPressed?.(); unlistenReleased?.(); };
+    return () => unlisteners.forEach(fn => fn());
   }, []);
 
   const formatHotkey = (config: HotkeyConfig) => {
+    if (platform && platform.os !== "macos") {
+      const modNames: Record<string, string> = { Meta: "Super", Shift: "Shift", Alt: "Alt", Control: "Ctrl" };
+      const key = config.key.replace(/^(Key|Digit)(?=.)/, "");
+      return [...config.modifiers.map((m) => modNames[m] || m), key].join("+");
+    }
     const modSymbols: Record<string, string> = { Meta: "⌘", Shift: "⇧", Alt: "⌥", Control: "⌃" };
     const keySymbols: Record<string, string> = {
       MetaRight: "⌘R", MetaLeft: "⌘L",
@@ -265,10 +527,15 @@ function App() {
       ControlRight: "⌃R", ControlLeft: "⌃L",
       Space: "Space",
     };
-    const keyDisplay = keySymbols[config.key] || config.key;
+    const keyDisplay = keySymbols[config.key] || config.key.replace(/^(Key|Digit)(?=.)/, "");
     return `${config.modifiers.map((m) => modSymbols[m] || m).join("")}${keyDisplay}`;
   };
 
+  const loadPlatformInfo = async () => {
+    try { setPlatform(await invoke<PlatformInfo>("get_platform_info")); }
+    catch (e) { console.error("Failed to get platform info:", e); }
+  };
+
   const checkWhisperStatus = async () => {
     try { setIsModelLoaded(await invoke<boolean>("is_whisper_loaded")); }
     catch (e) { console.error("Failed to check Whisper status:", e); }
@@ -287,17 +554,21 @@ function App() {
       setIsModelLoaded(true);
       setStatus("idle");
     } catch (e) {
-      setError(`Failed to load model: ${e}`);
+      setError(`Failed to load model: ${formatError(e)}`);
       setStatus("idle");
     }
   };
 
-  const handleRecordStart = useCallback(async () => {
+  const handleRecordStart = useCallback(async (modeOverride?: string) => {
     if (!isModelLoadedRef.current) { setError("Load model first"); return; }
+    startingRef.current = true;
+    stopAfterStartRef.current = false;
     try {
       setError(null);
+      setWarnings([]);
       setResult(null);
-      const currentMode = selectedModeRef.current;
+      const currentMode = modeOverride ?? selectedModeRef.current;
+      recordingModeRef.current = currentMode;
       await invoke("start_recording");
       await invoke("set_overlay_mode", { mode: currentMode });
       await invoke("show_overlay", { overlayState: "recording", mode: currentMode });
@@ -305,56 +576,100 @@ function App() {
         await invoke("set_overlay_mode", { mode: currentMode });
       }, 100);
       setStatus("recording");
-    } catch (e) { setError(`Failed to start recording: ${e}`); }
+      statusRef.current = "recording";
+      startingRef.current = false;
+      if (stopAfterStartRef.current) {
+        stopAfterStartRef.current = false;
+        handleRecordStop();
+      }
+    } catch (e) {
+      startingRef.current = false;
+      setError(`Failed to start recording: ${formatError(e)}`);
+    }
   }, []);
 
   const handleRecordStop = useCallback(async () => {
     if (statusRef.current !== "recording") return;
+    const run = pipelineRunRef.current;
+    const cancelled = () => pipelineRunRef.current !== run;
     try {
       setStatus("transcribing");
       await invoke("set_overlay_state", { overlayState: "processing" });
       const wavData = await invoke<number[]>("stop_recording");
-      const currentMode = selectedModeRef.current;
+      if (cancelled()) return;
+      const currentMode = recordingModeRef.current;
 
       // First: Transcribe audio
+      const transcribeStarted = performance.now();
       const transcription = await invoke<{ text: string; language: string }>("transcribe_audio", {
         wavData: Array.from(wavData),
       });
 
+      if (cancelled()) return;
+
       const rawText = transcription.text.trim();
       if (!rawText || rawText.length < 2) {
         throw new Error("Could not transcribe audio. Try speaking louder or longer.");
       }
 
+      const transcribeMs = Math.round(performance.now() - transcribeStarted);
+
       // Second: AI Cleanup (if enabled)
       let cleanedText = rawText;
+      let cleanupMs: number | null = null;
       const ollamaEnabled = await invoke<boolean>("is_ollama_enabled");
 
       if (ollamaEnabled && rawText.length > 3) {
         setStatus("cleaning");
         await invoke("set_overlay_state", { overlayState: "generating" });
+        const cleanupStarted = performance.now();
         try {
-          const cleaned = await invoke<string>("cleanup_text", {
+          // The backend rejects refusals, echoed prompts and mangled text, falling back to the raw transcript
+          cleanedText = await invoke<string>("cleanup_text", {
             text: rawText,
             language: transcription.language,
             mode: currentMode,
           });
-          // Only use cleaned if it doesn't look like an error message
-          if (!cleaned.includes("provide") || !cleaned.includes("transcript")) {
-            cleanedText = cleaned;
-          }
         } catch {
-          // Fall back to raw text on cleanup error
+          // Fall back to raw text unless the recording was cancelled; the backend reports why as a pipeline warning
+          if (cancelled()) return;
         }
+        cleanupMs = Math.round(performance.now() - cleanupStarted);
+        if (cancelled()) return;
       }
 
-      const enhancedResult: TranscribeResult = {
+      let enhancedResult: TranscribeResult = {
         raw_text: rawText,
         cleaned_text: cleanedText,
         language: transcription.language,
         mode: currentMode,
         timestamp: Date.now(),
       };
+      let entryId: number | null = null;
+      try {
+        const entry = await invoke<HistoryEntry>("add_history_entry", {
+          entry: {
+            raw_text: rawText,
+            cleaned_text: cleanedText,
+            mode: currentMode,
+            language: transcription.language,
+            model: cleanupMs !== null ? await invoke<string>("get_ollama_model") : null,
+            // 16 kHz mono 16-bit WAV with a 44-byte header
+            audio_duration_ms: Math.round(Math.max(0, wavData.length - 44) / 32),
+            transcribe_ms: transcribeMs,
+            cleanup_ms: cleanupMs,
+          },
+        });
+        enhancedResult = entryToResult(entry);
+        entryId = entry.id;
+      } catch (e) { console.error("Failed to save history:", e); }
+      if (entryId !== null) {
+        // Keeps the recording for replay/re-processing when archiving is enabled
+        await invoke("archive_recording", { id: entryId, wavData: Array.from(wavData) }).catch(e => {
+          const warning = { code: "audio_not_archived", message: `Failed to archive recording: ${formatError(e)}` };
+          setWarnings(prev => [...prev.filter(w => w.code !== warning.code), warning]);
+        });
+      }
       setResult(enhancedResult);
       setHistory((prev) => [enhancedResult, ...prev.slice(0, 9)]);
       const finalText = cleanedText;
@@ -364,21 +679,59 @@ function App() {
         todayCount: prev.todayCount + 1,
         sessionWords: prev.sessionWords + finalText.split(/\s+/).length,
       }));
-      await invoke("copy_to_clipboard", { text: finalText });
       await invoke("add_recent_transcript", { text: finalText });
       await invoke("set_overlay_state", { overlayState: "done" });
       setStatus("ready");
       setTimeout(async () => {
+        if (cancelled()) return;
         await invoke("hide_overlay");
-        await invoke("simulate_paste");
+        // Puts the transcript on the clipboard itself, restoring the old contents afterwards
+        await invoke("insert_text", { text: finalText, mode: currentMode }).catch(e => setError(formatError(e)));
       }, 500);
     } catch (e) {
-      setError(`${e}`);
+      if (cancelled() || isCancelled(e)) return;
+      setError(formatError(e));
       setStatus("idle");
       await invoke("hide_overlay");
     }
   }, []);
 
+  const toggleRecordingStyle = async () => {
+    if (!recordingSettings) return;
+    const style = recordingSettings.style === "toggle" ? "hold" : "toggle";
+    try {
+      await invoke("set_recording_settings", { recording: { ...recordingSettings, style } });
+    } catch (e) { setError(formatError(e)); }
+  };
+
+  const toggleInjection = async () => {
+    try {
+      const settings = await invoke<Settings>("get_settings");
+      await invoke("update_settings", { settings: { ...settings, injection: settings.injection === "type" ? "paste" : "type" } });
+    } catch (e) { setError(formatError(e)); }
+  };
+
+  const toggleRestoreClipboard = async () => {
+    try {
+      const settings = await invoke<Settings>("get_settings");
+      await invoke("update_settings", { settings: { ...settings, clipboard: { ...settings.clipboard, restore: !settings.clipboard.restore } } });
+    } catch (e) { setError(formatError(e)); }
+  };
+
+  const toggleAudioCleanup = async () => {
+    try {
+      const settings = await invoke<Settings>("get_settings");
+      await invoke("update_settings", { settings: { ...settings, dsp: { ...settings.dsp, enabled: !settings.dsp.enabled } } });
+    } catch (e) { setError(formatError(e)); }
+  };
+
+  const toggleKeepRecordings = async () => {
+    try {
+      const settings = await invoke<Settings>("get_settings");
+      await invoke("update_settings", { settings: { ...settings, archive: { ...settings.archive, enabled: !settings.archive.enabled } } });
+    } catch (e) { setError(formatError(e)); }
+  };
+
   const toggleAiCleanup = async () => {
     const newValue = !aiCleanupEnabled;
     setAiCleanupEnabled(newValue);
@@ -417,20 +770,20 @@ function App() {
     if (e.altKey) modifiers.push("Alt");
     if (e.ctrlKey) modifiers.push("Control");
 
-    let key = e.key;
-    if (key === " ") key = "Space";
-    if (key.length === 1) key = key.toUpperCase();
+    // The physical key, so the shortcut doesn't depend on the keyboard layout
+    const modifierCodes = ["MetaLeft", "ShiftLeft", "AltLeft", "ControlLeft", "OSLeft", "OSRight"];
 
     // Only save if we have modifiers and the key isn't itself a modifier
-    if (modifiers.length > 0 && !["Meta", "Shift", "Alt", "Control"].includes(key)) {
-      saveHotkey({ key, modifiers });
+    if (modifiers.length > 0 && code && !modifierCodes.includes(code)) {
+      saveHotkey({ key: code, modifiers });
       setIsCapturingHotkey(false);
     }
   };
 
-  const copyText = async (text: string) => {
+  // Cleaned output of structured modes is copied as rich text too
+  const copyText = async (text: string, mode?: string) => {
     try {
-      await invoke("copy_to_clipboard", { text });
+      await invoke("copy_to_clipboard", { text, mode: mode ?? null });
       setCopiedFeedback(true);
       setTimeout(() => setCopiedFeedback(false), 1500);
     }
@@ -524,9 +877,14 @@ function App() {
                     <option value="" className="bg-[#1a1a1a]">System Default</option>
                     {audioDevices.map((device) => (
                       <option key={device.id} value={device.id} className="bg-[#1a1a1a]">
-                        {device.name}
+                        {device.name}{device.is_default ? " (default)" : ""}
                       </option>
                     ))}
+                    {selectedDevice && !audioDevices.some((d) => d.id === selectedDevice) && (
+                      <option value={selectedDevice} className="bg-[#1a1a1a]">
+                        {selectedDevice} (unplugged)
+                      </option>
+                    )}
                   </select>
                   <button
                     onClick={loadAudioDevices}
@@ -539,6 +897,42 @@ function App() {
                   </button>
                 </div>
               </div>
+
+              {/* Input Channel, for devices with more than one */}
+              {deviceChannels > 1 && (
+                <div className="mt-3">
+                  <label className="block text-[10px] font-medium uppercase tracking-[0.15em] text-white/30 mb-2">Input Channel</label>
+                  <select
+                    value={inputChannels.length === 1 ? String(inputChannels[0]) : ""}
+                    onChange={(e) => handleChannelSelect(e.target.value === "" ? [] : [Number(e.target.value)])}
+                    className="w-full px-3.5 py-2.5 bg-white/[0.03] border border-white/[0.06] rounded-xl text-[13px] text-white/80 focus:outline-none focus:border-white/20 focus:bg-white/[0.05] transition-all appearance-none cursor-pointer"
+                  >
+                    <option value="" className="bg-[#1a1a1a]">All channels (mixed)</option>
+                    {Array.from({ length: deviceChannels }, (_, i) => (
+                      <option key={i} value={String(i)} className="bg-[#1a1a1a]">Channel {i + 1}</option>
+                    ))}
+                  </select>
+                </div>
+              )}
+
+              {/* Audio Host, only when there's a choice (e.g. ALSA or JACK) */}
+              {audioHosts.length > 1 && (
+                <div className="mt-3">
+                  <label className="block text-[10px] font-medium uppercase tracking-[0.15em] text-white/30 mb-2">Audio System</label>
+                  <select
+                    value={selectedHost || ""}
+                    onChange={(e) => handleHostSelect(e.target.value || null)}
+                    className="w-full px-3.5 py-2.5 bg-white/[0.03] border border-white/[0.06] rounded-xl text-[13px] text-white/80 focus:outline-none focus:border-white/20 focus:bg-white/[0.05] transition-all appearance-none cursor-pointer"
+                  >
+                    <option value="" className="bg-[#1a1a1a]">System Default</option>
+                    {audioHosts.map((host) => (
+                      <option key={host.id} value={host.id} className="bg-[#1a1a1a]">
+                        {host.id}{host.is_default ? " (default)" : ""}
+                      </option>
+                    ))}
+                  </select>
+                </div>
+              )}
             </div>
 
             <div className="border-t border-white/[0.06] my-5" />
@@ -638,6 +1032,43 @@ function App() {
                   enabled={hotkeyEnabled}
                   onToggle={() => setHotkeyEnabled(!hotkeyEnabled)}
                 />
+                {hotkeyEnabled && platform && !platform.global_shortcuts && (
+                  <p className="text-[11px] text-amber-400/70 px-1">
+                    Wayland only delivers global shortcuts while an X11 app has focus. Use the record button or bind the shortcut in your desktop's settings.
+                  </p>
+                )}
+                <Toggle
+                  label="Tap to Toggle Recording"
+                  sublabel={recordingSettings?.style === "toggle" ? "Tap to start, tap again to stop" : "Hold to talk, double-tap to lock"}
+                  enabled={recordingSettings?.style === "toggle"}
+                  onToggle={toggleRecordingStyle}
+                />
+                <Toggle
+                  label="Type Instead of Paste"
+                  sublabel={injection === "type" ? "Sends keystrokes, leaves the clipboard alone" : "Pastes from the clipboard"}
+                  enabled={injection === "type"}
+                  onToggle={toggleInjection}
+                />
+                {injection === "paste" && (
+                  <Toggle
+                    label="Restore Clipboard"
+                    sublabel={restoreClipboard ? "Puts back what you had copied after pasting" : "Leaves the transcript on the clipboard"}
+                    enabled={restoreClipboard}
+                    onToggle={toggleRestoreClipboard}
+                  />
+                )}
+                <Toggle
+                  label="Clean Up Audio"
+                  sublabel={audioCleanup ? "Filters rumble and background noise, evens out volume" : "Sends the microphone signal as recorded"}
+                  enabled={audioCleanup}
+                  onToggle={toggleAudioCleanup}
+                />
+                <Toggle
+                  label="Keep Recordings"
+                  sublabel={keepRecordings ? "Saves audio with each transcript for replay and re-processing" : "Audio is discarded after transcribing"}
+                  enabled={keepRecordings}
+                  onToggle={toggleKeepRecordings}
+                />
               </div>
 
               {/* Hotkey Capture */}
@@ -678,10 +1109,10 @@ function App() {
           )}
 
           <button
-            onMouseDown={handleRecordStart}
+            onMouseDown={() => handleRecordStart()}
             onMouseUp={handleRecordStop}
             onMouseLeave={status === "recording" ? handleRecordStop : undefined}
-            onTouchStart={handleRecordStart}
+            onTouchStart={() => handleRecordStart()}
             onTouchEnd={handleRecordStop}
             disabled={!isModelLoaded || (status !== "idle" && status !== "ready")}
             className={`relative w-[120px] h-[120px] rounded-full flex items-center justify-center transition-all duration-300 ${
@@ -703,6 +1134,15 @@ function App() {
           </button>
         </div>
 
+        {status !== "idle" && status !== "ready" && (
+          <button
+            onClick={() => invoke("cancel_recording")}
+            className="mt-4 text-[11px] text-white/30 hover:text-white/60 transition-colors"
+          >
+            Cancel <span className="text-white/20">(Esc)</span>
+          </button>
+        )}
+
         {/* Status */}
         <p className={`mt-6 text-[13px] font-medium tracking-wide transition-all duration-300 ${
           status === "ready" ? "text-emerald-400" :
@@ -741,6 +1181,30 @@ function App() {
           {error && (
             <p className="mt-4 text-[12px] text-red-400/80 text-center max-w-[260px] animate-shake">{error}</p>
           )}
+
+          {/* Settings from a newer version were backed up rather than read */}
+          {newerSettingsSchema !== null && (
+            <div className="mt-3 flex flex-col items-center gap-2 max-w-[260px]">
+              <p className="text-[11px] text-amber-400/80 text-center">
+                Your settings were saved by a newer version of yap-to-text, so defaults are in use. The newer file was kept as a backup next to the settings file.
+              </p>
+              <button
+                onClick={() => setNewerSettingsSchema(null)}
+                className="px-3 py-1 text-[11px] rounded-md bg-amber-400/10 text-amber-400/90 hover:bg-amber-400/20 transition-colors"
+              >
+                Dismiss
+              </button>
+            </div>
+          )}
+
+          {/* Warnings from the last recording */}
+          {warnings.length > 0 && (
+            <div className="mt-3 flex flex-col items-center gap-1 max-w-[260px]">
+              {warnings.map(w => (
+                <p key={w.code} className="text-[11px] text-amber-400/80 text-center">{w.message}</p>
+              ))}
+            </div>
+          )}
         </div>
 
         {/* Results - Scrollable area */}
@@ -749,7 +1213,7 @@ function App() {
           {result && (
             <div className="w-full max-w-sm animate-slideUp">
               <div
-                onClick={() => copyText(result.cleaned_text || result.raw_text)}
+                onClick={() => copyText(result.cleaned_text || result.raw_text, result.cleaned_text ? result.mode : undefined)}
                 className={`relative p-5 rounded-2xl bg-white/[0.03] backdrop-blur-xl border cursor-pointer transition-all duration-200 ${
                   copiedFeedback
                     ? "border-emerald-400/50 bg-emerald-400/5"
@@ -765,13 +1229,16 @@ function App() {
                   </div>
                 )}
 
-                <p className="text-[15px] leading-[1.6] text-white/90 font-light pr-16">
+                <p
+                  className="text-[15px] leading-[1.6] text-white/90 font-light pr-16"
+                  dir={languages[result.language]?.rtl ? "rtl" : "auto"}
+                >
                   {result.cleaned_text || result.raw_text}
                 </p>
 
                 <div className="mt-4 flex items-center gap-3">
                   <span className="text-[10px] font-medium uppercase tracking-[0.15em] text-white/25">
-                    {result.language}
+                    {languages[result.language]?.native_name ?? result.language}
                   </span>
                   <span className="text-white/10">·</span>
                   <span className="text-[10px] text-white/25">
@@ -796,7 +1263,7 @@ function App() {
               {history.slice(1, 4).map((item, i) => (
                 <button
                   key={i}
-                  onClick={() => copyText(item.cleaned_text || item.raw_text)}
+                  onClick={() => copyText(item.cleaned_text || item.raw_text, item.cleaned_text ? item.mode : undefined)}
                   className="w-full p-4 rounded-xl bg-white/[0.02] text-left text-[13px] text-white/40 hover:bg-white/[0.04] hover:text-white/60 transition-all duration-200 truncate group relative"
                 >
                   <span className="truncate block pr-16">{item.cleaned_text || item.raw_text}</span>